@@ -11,6 +11,7 @@ pub struct Migrator;
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
         vec![
+            Box::new(insane_jobs::migration::Migration),
             Box::new(m20220101_000001_users::Migration),
             Box::new(m20231103_114510_notes::Migration),
         ]
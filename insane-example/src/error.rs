@@ -1,16 +1,6 @@
 //! # Application Error Handling
+//!
+//! The application returns the error of the framework, its own errors carry
+//! an [`ErrorCode`], see [`insane_core::error`].
 
-/// Application results options list
-pub type Result<T, E = Error> = std::result::Result<T, E>;
-
-#[derive(thiserror::Error, Debug)]
-pub enum Error {
-    #[error("{0}")]
-    Message(String),
-
-    #[error(transparent)]
-    JSON(serde_json::Error),
-
-    #[error(transparent)]
-    IO(#[from] std::io::Error),
-}
\ No newline at end of file
+pub use insane_core::error::{Error, ErrorCode, Result};
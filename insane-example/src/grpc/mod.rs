@@ -0,0 +1,14 @@
+use async_trait::async_trait;
+use insane_core::error::Result;
+use insane_grpc::prelude::*;
+
+pub struct GrpcApp;
+
+#[async_trait]
+impl GrpcHooks for GrpcApp {
+    async fn services(&self, _routes: &mut RoutesBuilder, _ctx: &GrpcContext) -> Result<()> {
+        // Register the tonic services generated by `tonic-build` here, e.g.
+        // routes.add_service(GreeterServer::new(MyGreeter::default()));
+        Ok(())
+    }
+}
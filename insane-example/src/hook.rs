@@ -9,8 +9,14 @@ use async_trait::async_trait;
 use insane_core::prelude::*;
 use std::path::Path;
 
+use crate::grpc::GrpcApp;
 use crate::http::HttpApp;
+use insane_core::hook::Initializer;
+use insane_grpc::server::GrpcServer;
 use insane_http::server::HttpServer;
+use insane_jobs::prelude::*;
+use insane_mailer::prelude::*;
+use insane_scheduler::prelude::*;
 use std::sync::Arc;
 
 pub struct App;
@@ -39,10 +45,24 @@ impl Hooks for App {
         Ok(())
     }
 
+    async fn initializers(
+        _ctx: Arc<Box<dyn Context>>,
+    ) -> Result<Vec<Box<dyn Initializer<Context = Arc<Box<dyn Context>>>>>> {
+        Ok(vec![Box::new(JobsInitializer), Box::new(MailerInitializer)])
+    }
+
     async fn servers(_ctx: Arc<Box<dyn Context>>) -> Result<Vec<Box<dyn Server>>> {
         let http_server = HttpServer::<HttpApp>::new(HttpApp);
         let http_server = Box::new(http_server);
+        let grpc_server = Box::new(GrpcServer::new(GrpcApp));
+        let jobs_server = Box::new(JobServer::new(crate::jobs::registry()));
+        let scheduler_server = Box::new(SchedulerServer::new(crate::tasks::tasks()));
         // You can add more servers here
-        Ok(vec![http_server])
+        Ok(vec![
+            http_server,
+            grpc_server,
+            jobs_server,
+            scheduler_server,
+        ])
     }
 }
@@ -2,9 +2,15 @@ pub mod routes;
 
 use async_trait::async_trait;
 // use insane_core::{config::InsaneConfig, context::Context, error::Result};
-use insane_http::{context::HttpContext, hook::HttpHooks, http_routes::HttpRoutes};
+use insane_http::{
+    context::HttpContext,
+    hook::HttpHooks,
+    http_routes::HttpRoutes,
+    renderer::{ErrorRenderer, ProblemRenderer},
+};
 // use insane_core::server::Server;
 use insane_core::prelude::*;
+use std::sync::Arc;
 
 pub struct HttpApp;
 
@@ -15,6 +21,10 @@ impl HttpHooks for HttpApp {
             .prefix("/api")
             .add_route(routes::routes())
     }
+
+    fn error_renderer(&self) -> Option<Arc<dyn ErrorRenderer>> {
+        Some(Arc::new(ProblemRenderer::new()))
+    }
 }
 
 // #[async_trait::async_trait]
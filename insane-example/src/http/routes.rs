@@ -7,14 +7,39 @@
 use insane_http::error::Result;
 use insane_http::prelude::*;
 use insane_http::{context::HttpContext, format, routes::Routes};
+use insane_jobs::queue::Queue;
+use insane_mailer::mailer::Mailer;
+use serde_json::json;
+
+use crate::jobs::{PingArgs, PingJob};
 
 pub async fn ping(State(_ctx): State<HttpContext>) -> Result<Response> {
     // load_item(&ctx, id).await?.delete(&ctx.db).await?;
     format::empty()
 }
 
+pub async fn ping_later(Ext(queue): Ext<Queue>) -> Result<Response> {
+    queue
+        .enqueue::<PingJob>(&PingArgs {
+            message: "pong".to_string(),
+        })
+        .await?;
+    format::empty()
+}
+
+pub async fn ping_mail(Ext(mailer): Ext<Mailer>) -> Result<Response> {
+    mailer
+        .send_template("welcome", "user@example.com", json!({ "name": "user" }))
+        .await?;
+    format::empty()
+}
+
 pub fn routes() -> Routes {
-    Routes::new().prefix("ping_user").add("/", get(ping))
+    Routes::new()
+        .prefix("ping_user")
+        .add("/", get(ping))
+        .add("/later", post(ping_later))
+        .add("/mail", post(ping_mail))
     // .add("/", post(add))
     // .add("/:id", get(get_one))
     // .add("/:id", delete(remove))
@@ -0,0 +1,30 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use insane_core::{context::Context, error::Result};
+use insane_jobs::job::{Job, JobRegistry};
+use insane_mailer::job::SendEmail;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PingArgs {
+    pub message: String,
+}
+
+pub struct PingJob;
+
+#[async_trait]
+impl Job for PingJob {
+    const NAME: &'static str = "ping";
+    type Args = PingArgs;
+
+    async fn perform(&self, _ctx: Arc<Box<dyn Context>>, args: PingArgs) -> Result<()> {
+        tracing::info!(message = args.message, "ping job performed");
+        Ok(())
+    }
+}
+
+/// The jobs performed by the job server
+pub fn registry() -> JobRegistry {
+    JobRegistry::new().register(PingJob).register(SendEmail)
+}
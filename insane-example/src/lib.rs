@@ -2,5 +2,7 @@ pub mod hook;
 // pub mod database;
 pub mod commands;
 pub mod error;
+pub mod grpc;
 pub mod http;
-// pub mod grpc;
\ No newline at end of file
+pub mod jobs;
+pub mod tasks;
@@ -1,10 +1,21 @@
 use insane_cli::InsaneCli;
-use insane_example::{commands::TestUserCommand, hook::App};
+use insane_example::{commands::TestUserCommand, hook::App, tasks::tasks};
+use insane_grpc::config::GrpcServerConfig;
+use insane_http::config::HTTPServerConfig;
+use insane_jobs::config::JobsConfig;
+use insane_mailer::config::MailerConfig;
+use insane_scheduler::{command::SchedulerCommand, config::SchedulerConfig};
 use migration::Migrator;
 
 #[tokio::main]
 async fn main() -> eyre::Result<()> {
     let mut cli = InsaneCli::new();
     cli.add_custom_command(TestUserCommand {});
+    cli.add_custom_command(SchedulerCommand::<App>::new(tasks()));
+    cli.add_config_section::<HTTPServerConfig>("http");
+    cli.add_config_section::<GrpcServerConfig>("grpc");
+    cli.add_config_section::<JobsConfig>("jobs");
+    cli.add_config_section::<SchedulerConfig>("scheduler");
+    cli.add_config_section::<MailerConfig>("mailer");
     cli.run::<App, Migrator>().await
 }
@@ -0,0 +1,14 @@
+use std::sync::Arc;
+
+use insane_core::{context::Context, error::Result};
+use insane_scheduler::task::Tasks;
+
+async fn heartbeat(ctx: Arc<Box<dyn Context>>) -> Result<()> {
+    tracing::info!(environment = %ctx.environment(), "heartbeat");
+    Ok(())
+}
+
+/// The tasks run by the scheduler server
+pub fn tasks() -> Tasks {
+    Tasks::new().add("heartbeat", heartbeat)
+}
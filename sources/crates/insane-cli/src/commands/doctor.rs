@@ -14,7 +14,7 @@ pub fn make_subcommand() -> Command {
 pub async fn execute(_args: &ArgMatches, config: &InsaneConfig, _env: &Environment) -> Result<()> {
     // println!("doctor command {:?}", args);
     let mut should_exit = false;
-    for (_, check) in crate::doctor::run_all(&config).await {
+    for (_, check) in crate::doctor::run_all(config).await {
         if !should_exit && !check.valid() {
             should_exit = true;
         }
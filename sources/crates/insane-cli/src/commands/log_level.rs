@@ -0,0 +1,67 @@
+use insane_core::environment::Environment;
+
+use super::custom::*;
+use crate::error::{Error, ErrorCode, Result};
+use insane_core::{config::InsaneConfig, control};
+
+// Create clap subcommand arguments
+pub fn make_subcommand() -> Command {
+    Command::new("log-level")
+        .about("Show or change the log filter of the running application, through `control.socket`")
+        .subcommand(
+            Command::new("set")
+                .about("Add filter directives to the configured filters")
+                .arg(
+                    Arg::new("directives")
+                        .required(true)
+                        .help("Filter directives, like `insane_http=debug,sqlx=trace`"),
+                )
+                .arg(
+                    Arg::new("ttl")
+                        .long("ttl")
+                        .value_name("SECONDS")
+                        .value_parser(clap::value_parser!(u64))
+                        .help("Reset the directives after SECONDS"),
+                ),
+        )
+        .subcommand(Command::new("reset").about("Go back to the configured filters"))
+}
+
+// Log level command implementation
+pub async fn execute(args: &ArgMatches, config: &InsaneConfig, _env: &Environment) -> Result<()> {
+    let socket = config
+        .control
+        .socket
+        .as_ref()
+        .ok_or_else(|| Error::new(ErrorCode::CONFIG, "`control.socket` is not configured"))?;
+
+    let command = match args.subcommand() {
+        Some(("set", args)) => {
+            let directives = args
+                .get_one::<String>("directives")
+                .expect("directives are required");
+            match args.get_one::<u64>("ttl") {
+                Some(ttl) => format!("log-level set {directives} {ttl}"),
+                None => format!("log-level set {directives}"),
+            }
+        }
+        Some(("reset", _)) => "log-level reset".to_string(),
+        _ => "log-level".to_string(),
+    };
+
+    let answer: serde_json::Value =
+        serde_json::from_str(&control::send(socket, &command).await?)
+            .map_err(|err| Error::Message(format!("invalid answer: {err}")))?;
+    if let Some(err) = answer.get("error").and_then(serde_json::Value::as_str) {
+        return Err(Error::Message(err.to_string()));
+    }
+
+    println!("filter: {}", answer["filter"].as_str().unwrap_or_default());
+    if let Some(overrides) = answer["overrides"].as_str() {
+        match answer["expires_in"].as_u64() {
+            Some(expires_in) => println!("overrides: {overrides} (reset in {expires_in}s)"),
+            None => println!("overrides: {overrides}"),
+        }
+    }
+    Ok(())
+}
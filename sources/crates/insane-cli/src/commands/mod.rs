@@ -1,7 +1,9 @@
+pub mod config;
 pub mod custom;
-pub mod start;
-pub mod sql;
 pub mod doctor;
+pub mod log_level;
+pub mod sql;
+pub mod start;
 pub mod version;
 
-pub use custom::*;
\ No newline at end of file
+pub use custom::*;
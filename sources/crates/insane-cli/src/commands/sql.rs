@@ -1,10 +1,11 @@
 use super::custom::*;
-use crate::error::Result;
+use crate::error::{Error, ErrorCode, Result};
 
-use insane_core::{config::InsaneConfig, hook::Hooks};
 use insane_core::environment::Environment;
+use insane_core::{config::InsaneConfig, hook::Hooks};
 
 use clap::ArgAction;
+use std::io::{BufRead, Write};
 
 use insane_core::sql::{connect, create, migrate, migrate_down, reset, status};
 use sea_orm_migration::MigratorTrait;
@@ -30,26 +31,73 @@ pub fn make_subcommand() -> Command {
                         .long("down")
                         .num_args(1)
                         .help("Downgrade the database by n steps"),
-                ),
+                )
+                .arg(force_arg()),
+        )
+        .subcommand(
+            Command::new("reset")
+                .about("Drop all tables, then reapply all migrations")
+                .arg(force_arg()),
         )
-        .subcommand(Command::new("reset").about("Drop all tables, then reapply all migrations"))
         .subcommand(Command::new("status").about("Migration status"))
         .subcommand(
             Command::new("entities").about("Generate entity .rs files from database schema"),
         )
-        .subcommand(Command::new("truncate").about("Truncate data in tables (without dropping)"))
+        .subcommand(
+            Command::new("truncate")
+                .about("Truncate data in tables (without dropping)")
+                .arg(force_arg()),
+        )
+}
+
+fn force_arg() -> Arg {
+    Arg::new("force")
+        .short('f')
+        .long("force")
+        .action(ArgAction::SetTrue)
+        .help("Don't ask for confirmation")
+}
+
+/// Ask to confirm the destructive `operation`, unless `--force` is given, and
+/// log it.
+///
+/// # Errors
+/// When it's not confirmed, the command then exits with a non-zero code.
+fn confirm(args: &ArgMatches, env: &Environment, operation: &str) -> Result<()> {
+    if !args.get_flag("force") {
+        print!("This will {operation} of the `{env}` database. Continue? [y/N] ");
+        std::io::stdout().flush()?;
+        confirmed(&mut std::io::stdin().lock())?;
+    }
+
+    tracing::warn!(environment = %env, operation, "running a destructive database command");
+    Ok(())
+}
+
+/// Read the answer to the confirmation from `input`, an empty input is a no.
+fn confirmed(input: &mut impl BufRead) -> Result<()> {
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    if matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ErrorCode::BAD_REQUEST,
+            "aborted, the command was not confirmed",
+        ))
+    }
 }
 
 // Clean command implementation
 pub async fn execute<H: Hooks, M: MigratorTrait>(
     matches: &ArgMatches,
     config: &InsaneConfig,
-    _env: &Environment,
+    env: &Environment,
 ) -> Result<()> {
     println!("database command {:?}", matches);
 
     match matches.subcommand() {
-        Some(("create", _)) => create(&config.sql.uri).await?,
+        Some(("create", _)) => create(config.sql.uri.expose()).await?,
         Some(("migrate", _)) | Some(("reset", _)) | Some(("status", _)) | Some(("truncate", _)) => {
             println!("migrate, reset, status, truncate");
             let connection = connect(&config.sql).await?;
@@ -58,15 +106,23 @@ pub async fn execute<H: Hooks, M: MigratorTrait>(
                     let down_steps = args.get_one::<String>("down");
                     if let Some(steps) = down_steps {
                         let steps = steps.parse::<u32>().unwrap();
+                        let operation = format!("roll back {steps} migration(s)");
+                        confirm(args, env, &operation)?;
                         migrate_down::<M>(&connection, Some(steps)).await?
                     } else {
                         // by default apply all pending migrations
                         migrate::<M>(&connection).await?
                     }
                 }
-                Some(("reset", _)) => reset::<M>(&connection).await?,
+                Some(("reset", args)) => {
+                    confirm(args, env, "drop all the tables")?;
+                    reset::<M>(&connection).await?
+                }
                 Some(("status", _)) => status::<M>(&connection).await?,
-                Some(("truncate", _)) => H::truncate(&connection).await?,
+                Some(("truncate", args)) => {
+                    confirm(args, env, "delete all the data")?;
+                    H::truncate(&connection).await?
+                }
                 // Some(("entities", _)) => println!("entities"),
                 _ => unreachable!(), // This should never happen due to the match arm patterns
             }
@@ -76,3 +132,20 @@ pub async fn execute<H: Hooks, M: MigratorTrait>(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refuses_what_is_not_confirmed() {
+        assert!(confirmed(&mut "y\n".as_bytes()).is_ok());
+        assert!(confirmed(&mut " YES \n".as_bytes()).is_ok());
+
+        for answer in ["n\n", "\n", "maybe\n", ""] {
+            let err = confirmed(&mut answer.as_bytes()).unwrap_err();
+            assert_eq!(err.code(), ErrorCode::BAD_REQUEST);
+            assert_ne!(err.code().exit_code(), 0);
+        }
+    }
+}
@@ -6,11 +6,11 @@ use super::custom::*;
 
 use clap::builder::NonEmptyStringValueParser;
 use clap::ArgAction;
-use insane_core::sql;
 use insane_core::environment::Environment;
+use insane_core::sql;
 
 use crate::error::Result;
-use insane_core::boot_loader::{boot_app, create_context};
+use insane_core::boot_loader::{boot_app_with, create_context, BootOptions};
 use insane_core::{config::InsaneConfig, hook::Hooks};
 
 #[cfg(feature = "with-sql")]
@@ -24,13 +24,22 @@ pub fn make_subcommand() -> Command {
             Arg::new("http")
                 .long("http")
                 .action(ArgAction::SetTrue)
-                .help("Start HTTP server"),
+                .help("Start the HTTP server and not the gRPC one (combinable with --grpc)"),
         )
         .arg(
             Arg::new("grpc")
                 .long("grpc")
                 .action(ArgAction::SetTrue)
-                .help("Start GRPC server"),
+                .help("Start the gRPC server and not the HTTP one (combinable with --http)"),
+        )
+        .arg(
+            Arg::new("skip")
+                .long("skip")
+                .num_args(1)
+                .action(ArgAction::Append)
+                .value_name("SERVER")
+                .value_parser(NonEmptyStringValueParser::new())
+                .help("Do not start the server, e.g. jobs_server (repeatable)"),
         )
         .arg(
             Arg::new("binding")
@@ -52,6 +61,27 @@ pub fn make_subcommand() -> Command {
         )
 }
 
+/// Servers selected by the flags. `--http` and `--grpc` choose among the
+/// protocol servers, which must then be enabled, and `--skip` drops any
+/// server. Every enabled server runs when none is given.
+fn boot_options(args: &ArgMatches) -> BootOptions {
+    let protocols = [("http", "http_server"), ("grpc", "grpc_server")];
+    let narrowed = protocols.iter().any(|(flag, _)| args.get_flag(flag));
+
+    let mut options = BootOptions::default();
+    for (flag, server) in protocols {
+        if args.get_flag(flag) {
+            options.servers.push(server.to_string());
+        } else if narrowed {
+            options.skip.push(server.to_string());
+        }
+    }
+    options
+        .skip
+        .extend(args.get_many::<String>("skip").unwrap_or_default().cloned());
+    options
+}
+
 #[cfg(feature = "with-sql")]
 pub async fn execute<H: Hooks, M: MigratorTrait>(
     args: &ArgMatches,
@@ -59,8 +89,8 @@ pub async fn execute<H: Hooks, M: MigratorTrait>(
     environment: &Environment,
 ) -> Result<()> {
     let default_context = create_context::<H>(environment, config).await?;
-    sql::prepare::<H, M>(&default_context.sql(), &config.sql).await?;
-    boot_app::<H>(default_context).await?;
+    sql::prepare::<H, M>(default_context.sql(), &config.sql, environment).await?;
+    boot_app_with::<H>(default_context, boot_options(args)).await?;
     Ok(())
 }
 
@@ -71,6 +101,70 @@ pub async fn execute<H: Hooks>(
     environment: &Environment,
 ) -> Result<()> {
     let default_context = create_context::<H>(environment, config).await?;
-    boot_app::<H>(default_context).await?;
+    boot_app_with::<H>(default_context, boot_options(args)).await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(args: &[&str]) -> (Vec<String>, Vec<String>) {
+        let args = make_subcommand()
+            .try_get_matches_from(std::iter::once("start").chain(args.iter().copied()))
+            .unwrap();
+        let options = boot_options(&args);
+        (options.servers, options.skip)
+    }
+
+    #[test]
+    fn runs_every_server_by_default() {
+        assert_eq!(options(&[]), (vec![], vec![]));
+    }
+
+    #[test]
+    fn narrows_the_protocol_servers_only() {
+        assert_eq!(
+            options(&["--http"]),
+            (
+                vec!["http_server".to_string()],
+                vec!["grpc_server".to_string()]
+            )
+        );
+        assert_eq!(
+            options(&["--grpc"]),
+            (
+                vec!["grpc_server".to_string()],
+                vec!["http_server".to_string()]
+            )
+        );
+        assert_eq!(
+            options(&["--http", "--grpc"]),
+            (
+                vec!["http_server".to_string(), "grpc_server".to_string()],
+                vec![]
+            )
+        );
+    }
+
+    #[test]
+    fn skips_the_given_servers() {
+        assert_eq!(
+            options(&[
+                "--http",
+                "--skip",
+                "jobs_server",
+                "--skip",
+                "scheduler_server"
+            ]),
+            (
+                vec!["http_server".to_string()],
+                vec![
+                    "grpc_server".to_string(),
+                    "jobs_server".to_string(),
+                    "scheduler_server".to_string()
+                ]
+            )
+        );
+    }
+}
@@ -7,7 +7,7 @@ use insane_core::config::InsaneConfig;
 use insane_core::config::SqlConfig;
 
 #[cfg(feature = "with-redis")]
-use crate::configs::RedisConfig;
+use insane_core::config::RedisConfig;
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "with-sql")] {
@@ -33,7 +33,8 @@ cfg_if::cfg_if! {
 pub enum Resource {
     SeaOrmCLI,
     Database,
-    // Redis,
+    #[cfg(feature = "with-redis")]
+    Redis,
 }
 
 /// Represents the status of a resource check.
@@ -41,6 +42,7 @@ pub enum Resource {
 pub enum CheckStatus {
     Ok,
     NotOk,
+    #[allow(dead_code)]
     NotConfigure,
 }
 
@@ -65,6 +67,7 @@ impl Check {
     /// # Errors
     ///
     /// This function will return an error if Check fails
+    #[allow(dead_code)]
     pub fn to_result(&self) -> Result<()> {
         if self.valid() {
             Ok(())
@@ -150,31 +153,31 @@ pub async fn check_db(config: &SqlConfig) -> Check {
 
 /// Checks the Redis connection.
 #[cfg(feature = "with-redis")]
-pub async fn check_redis(_config: &RedisConfig) -> Check {
-    // if let Some(conn) = boot::connect_redis(config).await {
-    //     match redis::ping(&conn).await {
-    //         Ok(()) => Check {
-    //             status: CheckStatus::Ok,
-    //             message: REDIS_CONNECTION_SUCCESS.to_string(),
-    //             description: None,
-    //         },
-    //         Err(err) => Check {
-    //             status: CheckStatus::NotOk,
-    //             message: REDIS_CONNECTION_FAILED.to_string(),
-    //             description: Some(err.to_string()),
-    //         },
-    //     }
-    // } else {
-    //     Check {
-    //         status: CheckStatus::NotConfigure,
-    //         message: REDIS_CONNECTION_NOT_CONFIGURE.to_string(),
-    //         description: None,
-    //     }
-    // }
-    Check {
-        status: CheckStatus::NotConfigure,
-        message: REDIS_CONNECTION_NOT_CONFIGURE.to_string(),
-        description: None,
+pub async fn check_redis(config: &InsaneConfig) -> Check {
+    let Some(redis) = &config.redis else {
+        return Check {
+            status: CheckStatus::NotConfigure,
+            message: REDIS_CONNECTION_NOT_CONFIGURE.to_string(),
+            description: None,
+        };
+    };
+
+    // A single attempt: the doctor reports, it does not wait for the server.
+    let config = RedisConfig {
+        connect_retries: 0,
+        ..redis.clone()
+    };
+    match insane_core::redis::connect(&config).await {
+        Ok(_) => Check {
+            status: CheckStatus::Ok,
+            message: REDIS_CONNECTION_SUCCESS.to_string(),
+            description: None,
+        },
+        Err(err) => Check {
+            status: CheckStatus::NotOk,
+            message: REDIS_CONNECTION_FAILED.to_string(),
+            description: Some(err.to_string()),
+        },
     }
 }
 
@@ -195,3 +198,47 @@ pub fn check_seaorm_cli() -> Check {
         },
     }
 }
+
+#[cfg(all(test, feature = "with-redis"))]
+mod tests {
+    use std::net::TcpListener;
+
+    use super::*;
+
+    fn config(addr: std::net::SocketAddr) -> InsaneConfig {
+        InsaneConfig {
+            redis: Some(RedisConfig {
+                uri: format!("redis://{addr}").into(),
+                connect_timeout: 1_000,
+                ..RedisConfig::default()
+            }),
+            ..InsaneConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn pings_the_redis_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        insane_core::redis::test_server(listener);
+
+        let check = check_redis(&config(addr)).await;
+        assert_eq!(check.status, CheckStatus::Ok);
+        assert_eq!(check.message, REDIS_CONNECTION_SUCCESS);
+    }
+
+    #[tokio::test]
+    async fn reports_an_unreachable_redis_server_without_retrying() {
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let check = check_redis(&config(addr)).await;
+        assert_eq!(check.status, CheckStatus::NotOk);
+        assert!(check.description.is_some());
+
+        let check = check_redis(&InsaneConfig::default()).await;
+        assert_eq!(check.status, CheckStatus::NotConfigure);
+    }
+}
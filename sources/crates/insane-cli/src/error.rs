@@ -1,30 +1,8 @@
 //! # Application Error Handling
+//!
+//! The commands return the error of the framework,
+//! [`insane_core::error::Error`]. A failed command exits with the exit code
+//! of the [`ErrorCode`] of its error, like `66` for a `not_found` or `78` for
+//! an invalid configuration.
 
-/// Application results options list
-pub type Result<T, E = Error> = std::result::Result<T, E>;
-
-#[derive(thiserror::Error, Debug)]
-pub enum Error {
-    #[error("{0}")]
-    Message(String),
-
-    #[error(transparent)]
-    EnvVar(#[from] std::env::VarError),
-
-    #[error(transparent)]
-    IO(#[from] std::io::Error),
-
-    #[error(transparent)]
-    Any(#[from] Box<dyn std::error::Error + Send + Sync>),
-
-    #[error(transparent)]
-    Anyhow(#[from] eyre::Report),
-
-    #[cfg(feature = "with-sql")]
-    #[error(transparent)]
-    DB(#[from] sea_orm::DbErr),
-
-    #[error(transparent)]
-    InsaneError(#[from] insane_core::error::Error),
-}
-
+pub use insane_core::error::{Error, ErrorCode, Result};
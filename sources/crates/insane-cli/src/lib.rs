@@ -1,6 +1,6 @@
-pub mod error;
 pub mod commands;
 pub(crate) mod doctor;
+pub mod error;
 
 use std::collections::HashMap;
 
@@ -9,26 +9,38 @@ use sea_orm_migration::MigratorTrait;
 // #[cfg(feature = "with-sql")]
 // use insane_database::hook::DatabaseHooks;
 
-use crate::commands::custom::CommandCustom;
-use insane_core::environment::{DEFAULT_ENVIRONMENT, resolve_from_env, Environment};
+use crate::commands::{config::ConfigSection, custom::CommandCustom};
+use crate::error::{Error, ErrorCode};
+use clap::{Arg, ArgMatches, Command};
+use clap_complete::Shell;
+use insane_core::environment::{resolve_from_env, Environment, DEFAULT_ENVIRONMENT};
 use insane_core::{
-    config::InsaneConfig,
+    config::{
+        loader::{self, Config},
+        InsaneConfig,
+    },
     hook::Hooks,
-    traces
+    traces,
 };
-use clap::{Arg, ArgMatches, Command};
-use clap_complete::Shell;
 
 const VERSION: &str = concat!("v", clap::crate_version!());
 
 pub struct InsaneCli {
     custom_commands: HashMap<String, Box<dyn CommandCustom>>,
+    config_sections: Vec<ConfigSection>,
+}
+
+impl Default for InsaneCli {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl InsaneCli {
     pub fn new() -> Self {
         Self {
             custom_commands: HashMap::new(),
+            config_sections: Vec::new(),
         }
     }
 
@@ -36,6 +48,12 @@ impl InsaneCli {
         self.custom_commands
             .insert(command.name().to_string(), Box::new(command));
     }
+    /// Register the `key` section of the configuration, read as a `C` by
+    /// another crate, for `config validate` and `config init`.
+    pub fn add_config_section<C: Config>(&mut self, key: &str) {
+        self.config_sections.push(ConfigSection::new::<C>(key));
+    }
+
     pub fn pre_run<H: Hooks>(&self) -> eyre::Result<(Environment, InsaneConfig, ArgMatches)> {
         let command = self.create_clap_command();
         let matches = command.get_matches();
@@ -46,11 +64,27 @@ impl InsaneCli {
             .unwrap_or_else(resolve_from_env)
             .into();
 
-        let config = InsaneConfig::load_from_env::<H>(&environment)?;
+        if let Some(dir) = matches.get_one::<String>("config_dir") {
+            loader::set_config_dir(dir);
+        }
+
+        let config = match InsaneConfig::load_from_env::<H>(&environment) {
+            Ok(config) => config,
+            // `config` reports the errors of the configuration itself
+            Err(_) if matches.subcommand_name() == Some("config") => InsaneConfig::default(),
+            Err(err) => exit(&invalid_config(err)),
+        };
         // let config = environment.load::<I>()?;
 
-        if !H::init_logger(&config, &environment)? {
-            traces::init(&config.tracing)?;
+        let traced = H::init_logger(&config, &environment).and_then(|initialized| {
+            if initialized {
+                Ok(())
+            } else {
+                traces::init::<H>(&config.tracing)
+            }
+        });
+        if let Err(err) = traced {
+            exit(&invalid_config(err));
         }
 
         Ok((environment, config, matches))
@@ -65,18 +99,25 @@ impl InsaneCli {
         let subcommand = matches.subcommand();
         let res = match subcommand {
             Some(("doctor", sub_matches)) => {
-                let _ = commands::doctor::execute(sub_matches, &config, &environment).await?;
-                Ok(Some(true))
+                commands::doctor::execute(sub_matches, config, environment).await
             }
+            Some(("config", sub_matches)) => commands::config::execute(
+                sub_matches,
+                environment,
+                I::app_name(),
+                &self.config_sections,
+            ),
             Some(("version", sub_matches)) => {
-                let _ = commands::version::execute::<I>(sub_matches, &config, &environment).await?;
-                Ok(Some(true))
+                commands::version::execute::<I>(sub_matches, config, environment).await
+            }
+            Some(("log-level", sub_matches)) => {
+                commands::log_level::execute(sub_matches, config, environment).await
             }
 
             Some(("completions", sub_matches)) => (|| {
                 let shell = sub_matches
                     .get_one::<Shell>("shell")
-                    .ok_or_else(|| eyre::eyre!("Shell name missing."))?;
+                    .ok_or_else(|| Error::string("Shell name missing."))?;
 
                 let mut complete_app = self.create_clap_command();
                 clap_complete::generate(
@@ -85,19 +126,16 @@ impl InsaneCli {
                     "ocol",
                     &mut std::io::stdout().lock(),
                 );
-                Ok(Some(true))
+                Ok(())
             })(),
-            _ => Ok(None),
+            _ => return Ok(None),
         };
 
         if let Err(e) = res {
-            // utils::log_backtrace(&e);
-            eprintln!("Error: {}", e);
-
-            std::process::exit(101);
+            exit(&e);
         }
 
-        res
+        Ok(Some(true))
     }
 
     /// Create a list of valid arguments and sub-commands
@@ -121,8 +159,17 @@ impl InsaneCli {
                     .default_value(DEFAULT_ENVIRONMENT)
                     .help("Specify the environment"),
             )
+            .arg(
+                Arg::new("config_dir")
+                    .long("config-dir")
+                    .global(true)
+                    .value_name("DIR")
+                    .help("Folder of the configuration files, `.config` by default (or INSANE_CONFIG_DIR)"),
+            )
             .subcommand(commands::start::make_subcommand())
             .subcommand(commands::doctor::make_subcommand())
+            .subcommand(commands::config::make_subcommand())
+            .subcommand(commands::log_level::make_subcommand())
             .subcommand(commands::version::make_subcommand());
 
         #[cfg(feature = "with-sql")]
@@ -130,7 +177,7 @@ impl InsaneCli {
             app = app.subcommand(commands::sql::make_subcommand());
         }
 
-        for (_, command) in &self.custom_commands {
+        for command in self.custom_commands.values() {
             app = app.subcommand(command.make_subcommand());
         }
 
@@ -157,25 +204,21 @@ impl InsaneCli {
                 // Check if the user ran a custom command
                 if let Some((name, sub_matches)) = subcommand {
                     if let Some(command) = self.custom_commands.get(name) {
-                        command.execute(sub_matches, &config, &environment).await?;
+                        command.execute(sub_matches, &config, &environment).await
                     } else {
                         eprintln!("Command not found: {}", name);
-                        std::process::exit(101);
+                        std::process::exit(ErrorCode::BAD_REQUEST.exit_code());
                     }
                 } else {
                     eprintln!("Command not found");
-                    std::process::exit(101);
+                    std::process::exit(ErrorCode::BAD_REQUEST.exit_code());
                 }
-
-                Ok(())
             }
         };
+        traces::flush();
 
         if let Err(e) = res {
-            // utils::log_backtrace(&e);
-            eprintln!("Error: {}", e);
-
-            std::process::exit(101);
+            exit(&e);
         }
 
         Ok(())
@@ -197,34 +240,81 @@ impl InsaneCli {
             Some(("start", sub_matches)) => {
                 commands::start::execute::<H, M>(sub_matches, &config, &environment).await
             }
-            Some(("sql", sub_matches)) => {
+            Some(("database", sub_matches)) => {
                 commands::sql::execute::<H, M>(sub_matches, &config, &environment).await
             }
             _ => {
                 // Check if the user ran a custom command
                 if let Some((name, sub_matches)) = subcommand {
                     if let Some(command) = self.custom_commands.get(name) {
-                        command.execute(sub_matches, &config, &environment).await?;
+                        command.execute(sub_matches, &config, &environment).await
                     } else {
                         eprintln!("Command not found: {}", name);
-                        std::process::exit(101);
+                        std::process::exit(ErrorCode::BAD_REQUEST.exit_code());
                     }
                 } else {
                     eprintln!("Command not found");
-                    std::process::exit(101);
+                    std::process::exit(ErrorCode::BAD_REQUEST.exit_code());
                 }
-
-                Ok(())
             }
         };
+        traces::flush();
 
         if let Err(e) = res {
-            // utils::log_backtrace(&e);
-            eprintln!("Error: {}", e);
-
-            std::process::exit(101);
+            exit(&e);
         }
 
         Ok(())
     }
 }
+
+/// A failure to load the configuration or to set up the logs from it, exiting
+/// with [`ErrorCode::CONFIG`] whatever its cause.
+fn invalid_config(err: Error) -> Error {
+    Error::new(ErrorCode::CONFIG, err.to_string()).with_source(err)
+}
+
+/// Print `err` and exit with the exit code of its [`ErrorCode`].
+fn exit(err: &Error) -> ! {
+    // utils::log_backtrace(&e);
+    eprintln!("Error: {err}");
+    traces::flush();
+
+    std::process::exit(err.code().exit_code());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_dir(args: &[&str]) -> Option<String> {
+        InsaneCli::new()
+            .create_clap_command()
+            .try_get_matches_from(args)
+            .unwrap()
+            .get_one::<String>("config_dir")
+            .cloned()
+    }
+
+    #[test]
+    fn exits_with_the_config_code_on_configuration_failures() {
+        let err = invalid_config(Error::string("missing field `server`"));
+
+        assert_eq!(err.code(), ErrorCode::CONFIG);
+        assert_eq!(err.code().exit_code(), 78);
+        assert_eq!(err.to_string(), "missing field `server`");
+    }
+
+    #[test]
+    fn takes_the_config_dir_before_or_after_the_command() {
+        assert_eq!(config_dir(&["app", "version"]), None);
+        assert_eq!(
+            config_dir(&["app", "--config-dir", "/etc/myapp", "version"]).as_deref(),
+            Some("/etc/myapp")
+        );
+        assert_eq!(
+            config_dir(&["app", "version", "--config-dir", "conf"]).as_deref(),
+            Some("conf")
+        );
+    }
+}
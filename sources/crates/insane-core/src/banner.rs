@@ -6,6 +6,7 @@ use crate::context::Context;
 
 // use crate::boot_loader::{BootResult, ServerParams};
 /// Configuration structure for serving an application.
+#[allow(dead_code)]
 pub struct ServerParams {
     /// The port number on which the server will listen for incoming
     /// connections.
@@ -13,8 +14,8 @@ pub struct ServerParams {
     /// The network address to which the server will bind. It specifies the
     /// interface to listen on.
     pub binding: String,
-  }
-  
+}
+
 //   pub struct BootResult {
 //     /// Context
 //     pub context: Box<dyn Context>,
@@ -23,7 +24,6 @@ pub struct ServerParams {
 //     // /// worker processor
 //     // pub processor: Option<Processor>,
 //   }
-  
 
 pub const BANNER: &str = r"
                       ▄     ▀                     
@@ -65,9 +65,13 @@ pub fn print_banner(ctx: &Arc<Box<dyn Context>>) {
             database.push("truncate".bright_red());
         }
 
-        if !database.is_empty() {
+        // the uri is a secret, only shown redacted
+        if database.is_empty() {
+            println!("   database: {}", config.sql.uri.redacted().green());
+        } else {
             println!(
-                "   database: {}",
+                "   database: {} ({})",
+                config.sql.uri.redacted().green(),
                 database
                     .iter()
                     .map(ToString::to_string)
@@ -1,10 +1,25 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
+
+use tokio::task::{JoinError, JoinSet};
 
-use crate::{
-    banner::print_banner, config::InsaneConfig, context::{Context, DefaultContext}, environment::Environment, error::Result, hook::Hooks
-};
 #[cfg(feature = "with-sql")]
 use crate::sql;
+use crate::{
+    banner::print_banner,
+    cache,
+    config::InsaneConfig,
+    context::{Context, DefaultContext},
+    control,
+    environment::Environment,
+    error::{Error, ErrorCode, Result},
+    extensions::Extensions,
+    hook::Hooks,
+    initializers::InitializerChain,
+    reload::{self, ConfigReloader},
+    server::{Readiness, Server},
+    shutdown::{self, ShutdownToken},
+    storage, supervisor, traces,
+};
 
 /// Initializes the application context by loading configuration and
 /// establishing connections.
@@ -25,9 +40,24 @@ pub async fn create_context<H: Hooks>(
 
     #[cfg(feature = "with-sql")]
     let sql: sea_orm::prelude::DatabaseConnection = sql::connect(&config.sql).await?;
+    #[cfg(feature = "with-sql")]
+    if config.metrics.enable {
+        crate::metrics::track_pool(&sql)?;
+    }
+
+    #[cfg(feature = "with-redis")]
+    let redis = match &config.redis {
+        Some(redis) => Some(crate::redis::connect(redis).await?),
+        None => None,
+    };
 
-    // #[cfg(feature = "with-redis")]
-    // let redis = connect_redis(&config).await;
+    let cache = cache::create(
+        &config.cache,
+        #[cfg(feature = "with-redis")]
+        redis.as_ref(),
+    )?;
+    let storage = storage::create(&config.storage)?;
+    let reloader = Arc::new(ConfigReloader::new(environment, config)?);
 
     let context: Arc<Box<dyn Context>> = Arc::new(Box::new(DefaultContext {
         environment: environment.clone(),
@@ -35,46 +65,498 @@ pub async fn create_context<H: Hooks>(
 
         #[cfg(feature = "with-sql")]
         sql,
-        // #[cfg(feature = "with-redis")]
-        // redis,
-        // storage: C::storage(&config, environment).await?.map(Arc::new),
+        cache,
+        storage,
+        extensions: Extensions::default(),
+        reloader,
+        log_filter: traces::log_filter(),
+        #[cfg(feature = "with-redis")]
+        redis,
     }));
 
     Ok(context)
 }
 
-/// Boots the application based on the specified mode.
+/// Options of [`boot_app_with`].
+#[derive(Debug, Clone, Default)]
+pub struct BootOptions {
+    /// Names of the servers that must run, see [`Server::name`]. Booting
+    /// fails when one of them isn't provided by the application or is
+    /// disabled by the configuration.
+    pub servers: Vec<String>,
+    /// Names of the servers not to run. Every other server runs, when
+    /// enabled.
+    pub skip: Vec<String>,
+}
+
+/// Boots the application, running every server.
+///
+/// # Errors
+/// See [`boot_app_with`].
 pub async fn boot_app<H: Hooks>(context: Arc<Box<dyn Context>>) -> Result<()> {
+    boot_app_with::<H>(context, BootOptions::default()).await
+}
+
+/// Boots the application based on the specified mode.
+///
+/// Every server runs on its own task. On `SIGINT`/`SIGTERM` the shared
+/// [`ShutdownToken`] is triggered and the servers get
+/// `servers.shutdown_timeout` milliseconds to drain before being aborted.
+///
+/// Servers are run by [`supervisor::supervise`], which applies the
+/// `servers.supervision` policy when a server fails.
+///
+/// The configuration is reloaded on `SIGHUP` and, with `reload.watch`, when
+/// its files change, see [`reload`].
+///
+/// Initializers run `before_run` before the servers start, `after_start` once
+/// every server reported it is ready, see [`Readiness`], and
+/// `before_shutdown` after they stopped.
+///
+/// # Errors
+/// When a hook fails, when a server required by `options` is not provided by
+/// [`Hooks::servers`] or is disabled, or when a server fails under the
+/// `fail_fast` policy.
+pub async fn boot_app_with<H: Hooks>(
+    context: Arc<Box<dyn Context>>,
+    options: BootOptions,
+) -> Result<()> {
     // Global app lifecycle hooks
+    let initializers = match start_initializers::<H>(&context).await {
+        Ok(initializers) => initializers,
+        Err(err) => {
+            close(&context).await;
+            traces::flush();
+            return Err(err);
+        }
+    };
+
+    // Built once the initializers ran, so they see the services registered
+    let servers = match H::servers(context.clone())
+        .await
+        .and_then(|servers| select_servers(servers, &options))
+    {
+        Ok(servers) => servers,
+        Err(err) => {
+            initializers.before_shutdown(&context, None).await;
+            close(&context).await;
+            traces::flush();
+            return Err(err);
+        }
+    };
+
+    print_banner(&context);
+
+    let shutdown = ShutdownToken::new();
+    tokio::spawn({
+        let shutdown = shutdown.clone();
+        async move {
+            shutdown::signal().await;
+            tracing::info!("shutdown signal received");
+            shutdown.trigger();
+        }
+    });
+    tokio::spawn(reload::watch(
+        context.reloader().clone(),
+        context.config().reload.clone(),
+        shutdown.clone(),
+    ));
+    tokio::spawn(control::serve(context.clone(), shutdown.clone()));
+
+    let mut running = JoinSet::new();
+    let mut readiness = Vec::new();
+    for server in &servers {
+        let supervision = context
+            .config()
+            .servers
+            .supervision_for(&server.name())
+            .clone();
+        let name = server.name();
+        let required = options.servers.contains(&name);
+        let ready = Readiness::new();
+        readiness.push(ready.clone());
+        let supervised = supervisor::supervise(
+            server.clone(),
+            context.clone(), // Clone the context for each server
+            shutdown.clone(),
+            supervision,
+            ready,
+            required,
+        );
+        running.spawn(async move { (name, supervised.await) });
+    }
+
+    let drain_timeout = Duration::from_millis(context.config().servers.shutdown_timeout);
+    let (started, result) = tokio::join!(
+        after_start(&initializers, &context, &readiness, &shutdown),
+        wait_servers(&mut running, &shutdown, drain_timeout)
+    );
+    let result = started.and(result);
+
+    for server in &servers {
+        if let Err(err) = server.shutdown(context.clone()).await {
+            tracing::error!(server = server.name(), err.msg = %err, "server shutdown failed");
+        }
+    }
+
+    initializers.before_shutdown(&context, None).await;
+    close(&context).await;
+
+    tracing::info!("shutdown completed");
+    traces::flush();
+    result
+}
+
+/// Run the `before_run` hook of the application and of its initializers. The
+/// initializers started are shut down when one fails.
+async fn start_initializers<H: Hooks>(
+    context: &Arc<Box<dyn Context>>,
+) -> Result<InitializerChain<Arc<Box<dyn Context>>>> {
     H::before_run(context.clone()).await?;
-    let initializers = H::initializers(context.clone()).await?;
-    tracing::info!(initializers = ?initializers.iter().map(|init| init.name()).collect::<Vec<_>>().join(","), "initializers loaded");
-    for initializer in &initializers {
-        initializer.before_run(context.clone(), None).await?;
+    let initializers = InitializerChain::new(H::initializers(context.clone()).await?)?;
+    tracing::info!(initializers = ?initializers.names().join(","), "initializers loaded");
+    initializers.before_run(context, None).await?;
+    Ok(initializers)
+}
+
+/// Close the database connection of the context.
+#[cfg(feature = "with-sql")]
+async fn close(context: &Arc<Box<dyn Context>>) {
+    if let Err(err) = context.sql().close_by_ref().await {
+        tracing::error!(err.msg = %err, "failed to close the database connection");
     }
+}
 
-    print_banner(&context);
+#[cfg(not(feature = "with-sql"))]
+async fn close(_context: &Arc<Box<dyn Context>>) {}
 
-    let servers = H::servers(context.clone()).await?;
-    let serve_futures = servers.into_iter().map(|server| {
-        let context = context.clone(); // Clone the context for each server
-        tokio::spawn(async move {
-            if server.enable(context.clone()).await? {
-                server.serve(context).await // Serve the server
-            } else {
-                tracing::info!(server = server.name(), "is disabled. skipping...");
-                Ok(()) // Server is disabled, return Ok(())
+/// Run the `after_start` initializers once every server is ready, unless the
+/// application stops before. Their failure stops the application.
+async fn after_start(
+    initializers: &InitializerChain<Arc<Box<dyn Context>>>,
+    context: &Arc<Box<dyn Context>>,
+    readiness: &[Readiness],
+    shutdown: &ShutdownToken,
+) -> Result<()> {
+    tokio::select! {
+        biased;
+        () = shutdown.triggered() => return Ok(()),
+        _ = futures_util::future::join_all(readiness.iter().map(Readiness::wait)) => {}
+    }
+    tracing::info!("servers ready");
+
+    let started = initializers.after_start(context, None).await;
+    if let Err(err) = &started {
+        tracing::error!(err.msg = %err, "initializers after_start failed");
+        shutdown.trigger();
+    }
+    started
+}
+
+/// Drop the servers skipped by `options`, checking the required ones are
+/// provided.
+fn select_servers(
+    servers: Vec<Box<dyn Server>>,
+    options: &BootOptions,
+) -> Result<Vec<Arc<dyn Server>>> {
+    if let Some(missing) = options
+        .servers
+        .iter()
+        .find(|name| !servers.iter().any(|server| &server.name() == *name))
+    {
+        return Err(Error::new(
+            ErrorCode::CONFIG,
+            format!("server `{missing}` is not provided by the application"),
+        ));
+    }
+
+    Ok(servers
+        .into_iter()
+        .filter(|server| {
+            let name = server.name();
+            let skip = options.skip.contains(&name) && !options.servers.contains(&name);
+            if skip {
+                tracing::info!(server = name, "server skipped");
             }
+            !skip
         })
-    });
+        .map(Arc::from)
+        .collect())
+}
+
+/// Wait until every server returned, or until the shutdown is triggered and
+/// the servers had `drain_timeout` to finish. Servers still running after that
+/// are aborted.
+///
+/// A server failing under the `fail_fast` policy triggers the shutdown of the
+/// others, and its error is returned once they are drained.
+async fn wait_servers(
+    running: &mut JoinSet<(String, Result<()>)>,
+    shutdown: &ShutdownToken,
+    drain_timeout: Duration,
+) -> Result<()> {
+    let mut failure = None;
+    loop {
+        tokio::select! {
+            joined = running.join_next() => match joined {
+                Some(joined) => {
+                    if let Err(err) = server_exit(joined) {
+                        if failure.is_none() {
+                            tracing::error!("stopping the application");
+                            shutdown.trigger();
+                            failure = Some(err);
+                        }
+                    }
+                }
+                None => return failure.map_or(Ok(()), Err),
+            },
+            () = shutdown.triggered() => break,
+        }
+    }
+
+    tracing::info!(timeout = ?drain_timeout, servers = running.len(), "draining servers");
+    let drained = tokio::time::timeout(drain_timeout, async {
+        while let Some(joined) = running.join_next().await {
+            let _ = server_exit(joined);
+        }
+    })
+    .await;
 
-    for serve_future in serve_futures {
-        // Spawn each serve future concurrently
-        if let Err(err) = serve_future.await {
-            // Use the cloned context inside the closure
+    if drained.is_err() {
+        tracing::warn!(
+            servers = running.len(),
+            "shutdown timeout reached, aborting the remaining servers"
+        );
+        running.shutdown().await;
+    }
+
+    failure.map_or(Ok(()), Err)
+}
+
+fn server_exit(joined: std::result::Result<(String, Result<()>), JoinError>) -> Result<()> {
+    match joined {
+        Ok((_, Ok(()))) => Ok(()),
+        Ok((name, Err(err))) => {
+            tracing::error!(server = name, err.msg = %err, "Error in processing");
+            Err(Error::new(
+                err.code(),
+                format!("server `{name}` failed: {err}"),
+            ))
+        }
+        Err(err) => {
             tracing::error!("Error in processing: {:?}", err);
+            Err(Error::Message(err.to_string()))
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use super::*;
+    use crate::{config::servers::SupervisionPolicy, context::test_context, hook::Initializer};
+
+    /// What a boot observed, one per test as the hooks are static.
+    #[derive(Default)]
+    struct Observed {
+        ready: AtomicBool,
+        started: AtomicBool,
+        started_when_ready: AtomicBool,
+        shut_down: AtomicBool,
+    }
+
+    /// Reports ready after a while, then stops once the initializers started.
+    struct SlowServer(&'static Observed);
+
+    #[async_trait::async_trait]
+    impl Server for SlowServer {
+        fn name(&self) -> String {
+            "slow".to_string()
+        }
+
+        async fn enable(&self, _context: Arc<Box<dyn Context>>) -> Result<bool> {
+            Ok(true)
+        }
+
+        async fn serve(
+            &self,
+            _context: Arc<Box<dyn Context>>,
+            shutdown: ShutdownToken,
+            ready: Readiness,
+        ) -> Result<()> {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            self.0.ready.store(true, Ordering::SeqCst);
+            ready.ready();
+            while !self.0.started.load(Ordering::SeqCst) && !shutdown.is_triggered() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            Ok(())
+        }
+    }
+
+    /// Fails before reporting ready.
+    struct FailingServer;
+
+    #[async_trait::async_trait]
+    impl Server for FailingServer {
+        fn name(&self) -> String {
+            "failing".to_string()
+        }
+
+        async fn enable(&self, _context: Arc<Box<dyn Context>>) -> Result<bool> {
+            Ok(true)
+        }
+
+        async fn serve(
+            &self,
+            _context: Arc<Box<dyn Context>>,
+            _shutdown: ShutdownToken,
+            _ready: Readiness,
+        ) -> Result<()> {
+            Err(Error::string("address already in use"))
+        }
+    }
+
+    struct Recorder(&'static Observed);
+
+    #[async_trait::async_trait]
+    impl Initializer for Recorder {
+        type Context = Arc<Box<dyn Context>>;
 
-    Ok(())
+        fn name(&self) -> String {
+            "recorder".to_string()
+        }
+
+        async fn after_start(
+            &self,
+            _app_context: Arc<Box<dyn Context>>,
+            _context: Option<Self::Context>,
+        ) -> Result<()> {
+            let ready = self.0.ready.load(Ordering::SeqCst);
+            self.0.started_when_ready.store(ready, Ordering::SeqCst);
+            self.0.started.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+
+        async fn before_shutdown(
+            &self,
+            _app_context: Arc<Box<dyn Context>>,
+            _context: Option<Self::Context>,
+        ) -> Result<()> {
+            self.0.shut_down.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    macro_rules! app {
+        ($name:ident, $observed:ident, [$($server:expr),*]) => {
+            static $observed: Observed = Observed {
+                ready: AtomicBool::new(false),
+                started: AtomicBool::new(false),
+                started_when_ready: AtomicBool::new(false),
+                shut_down: AtomicBool::new(false),
+            };
+
+            struct $name;
+
+            #[async_trait::async_trait]
+            impl Hooks for $name {
+                fn app_name() -> &'static str {
+                    "boot_test"
+                }
+
+                async fn initializers(
+                    _app_context: Arc<Box<dyn Context>>,
+                ) -> Result<Vec<Box<dyn Initializer<Context = Arc<Box<dyn Context>>>>>> {
+                    Ok(vec![Box::new(Recorder(&$observed))])
+                }
+
+                async fn servers(
+                    _app_context: Arc<Box<dyn Context>>,
+                ) -> Result<Vec<Box<dyn Server>>> {
+                    Ok(vec![$(Box::new($server)),*])
+                }
+
+                #[cfg(feature = "with-sql")]
+                async fn truncate(_db: &sea_orm::DatabaseConnection) -> Result<()> {
+                    Ok(())
+                }
+
+                #[cfg(feature = "with-sql")]
+                async fn seed(
+                    _db: &sea_orm::DatabaseConnection,
+                    _path: &std::path::Path,
+                ) -> Result<()> {
+                    Ok(())
+                }
+            }
+        };
+    }
+
+    #[tokio::test]
+    async fn drains_the_servers_within_the_shutdown_timeout() {
+        static DRAINED: AtomicBool = AtomicBool::new(false);
+
+        let shutdown = ShutdownToken::new();
+        let mut running = JoinSet::new();
+        running.spawn({
+            let shutdown = shutdown.clone();
+            async move {
+                shutdown.triggered().await;
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                DRAINED.store(true, Ordering::SeqCst);
+                ("draining".to_string(), Ok(()))
+            }
+        });
+        // ignores the token, so it is aborted once the timeout is reached
+        running.spawn(async {
+            std::future::pending::<()>().await;
+            ("stuck".to_string(), Ok(()))
+        });
+
+        let started = std::time::Instant::now();
+        shutdown.trigger();
+        wait_servers(&mut running, &shutdown, Duration::from_millis(300))
+            .await
+            .unwrap();
+
+        assert!(DRAINED.load(Ordering::SeqCst));
+        assert!(running.is_empty());
+        let elapsed = started.elapsed();
+        assert!(elapsed >= Duration::from_millis(300), "{elapsed:?}");
+        assert!(elapsed < Duration::from_secs(2), "{elapsed:?}");
+    }
+
+    #[tokio::test]
+    async fn after_start_waits_for_the_servers_to_be_ready() {
+        app!(ReadyApp, READY, [SlowServer(&READY)]);
+
+        boot_app::<ReadyApp>(test_context(InsaneConfig::default()))
+            .await
+            .unwrap();
+
+        assert!(READY.started.load(Ordering::SeqCst));
+        assert!(READY.started_when_ready.load(Ordering::SeqCst));
+        assert!(READY.shut_down.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn after_start_is_skipped_when_a_server_fails_to_start() {
+        app!(FailingApp, FAILING, [FailingServer, SlowServer(&FAILING)]);
+
+        let mut config = InsaneConfig::default();
+        config.servers.supervision.policy = SupervisionPolicy::FailFast;
+        config.servers.shutdown_timeout = 1_000;
+        let err = boot_app::<FailingApp>(test_context(config))
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "server `failing` failed: address already in use"
+        );
+        assert!(!FAILING.started.load(Ordering::SeqCst));
+        assert!(FAILING.shut_down.load(Ordering::SeqCst));
+    }
 }
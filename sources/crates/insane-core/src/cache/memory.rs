@@ -0,0 +1,132 @@
+use std::{
+    num::NonZeroUsize,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use lru::LruCache;
+
+use super::Cache;
+use crate::error::Result;
+
+#[derive(Debug)]
+struct Entry {
+    value: Vec<u8>,
+    expires_at: Option<Instant>,
+}
+
+impl Entry {
+    fn expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|at| at <= now)
+    }
+}
+
+/// An in-process LRU cache.
+///
+/// Expired entries are removed when they are looked up, or evicted with the
+/// least recently used ones once the capacity is reached.
+#[derive(Debug)]
+pub struct MemoryCache {
+    entries: Mutex<LruCache<String, Entry>>,
+}
+
+impl MemoryCache {
+    /// Create a cache holding at most `capacity` entries. A capacity of 0 is
+    /// treated as 1.
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(LruCache::new(
+                NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN),
+            )),
+        }
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, LruCache<String, Entry>> {
+        // The entries stay consistent even if a holder panicked.
+        self.entries
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+#[async_trait::async_trait]
+impl Cache for MemoryCache {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let mut entries = self.entries();
+        match entries.get(key) {
+            Some(entry) if entry.expired(Instant::now()) => {
+                entries.pop(key);
+                Ok(None)
+            }
+            Some(entry) => Ok(Some(entry.value.clone())),
+            None => Ok(None),
+        }
+    }
+
+    async fn set(&self, key: &str, value: Vec<u8>, ttl: Option<Duration>) -> Result<()> {
+        let expires_at = ttl.map(|ttl| Instant::now() + ttl);
+        self.entries()
+            .put(key.to_string(), Entry { value, expires_at });
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<bool> {
+        Ok(self
+            .entries()
+            .pop(key)
+            .is_some_and(|entry| !entry.expired(Instant::now())))
+    }
+
+    async fn clear(&self) -> Result<()> {
+        self.entries().clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn expires_the_entries_after_their_ttl() {
+        let cache = MemoryCache::new(10);
+        cache
+            .set("short", b"1".to_vec(), Some(Duration::from_millis(20)))
+            .await
+            .unwrap();
+        cache.set("forever", b"2".to_vec(), None).await.unwrap();
+        assert_eq!(cache.get("short").await.unwrap(), Some(b"1".to_vec()));
+
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert_eq!(cache.get("short").await.unwrap(), None);
+        assert_eq!(cache.get("forever").await.unwrap(), Some(b"2".to_vec()));
+        assert!(!cache.delete("short").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn evicts_the_least_recently_used_entries() {
+        let cache = MemoryCache::new(2);
+        cache.set("a", b"a".to_vec(), None).await.unwrap();
+        cache.set("b", b"b".to_vec(), None).await.unwrap();
+        // `a` becomes the most recently used, so `b` is evicted
+        cache.get("a").await.unwrap();
+        cache.set("c", b"c".to_vec(), None).await.unwrap();
+
+        assert_eq!(cache.get("a").await.unwrap(), Some(b"a".to_vec()));
+        assert_eq!(cache.get("b").await.unwrap(), None);
+        assert_eq!(cache.get("c").await.unwrap(), Some(b"c".to_vec()));
+    }
+
+    #[tokio::test]
+    async fn deletes_and_clears() {
+        let cache = MemoryCache::new(0);
+        cache.set("a", b"a".to_vec(), None).await.unwrap();
+        assert!(cache.delete("a").await.unwrap());
+        assert!(!cache.delete("a").await.unwrap());
+
+        cache.set("a", b"a".to_vec(), None).await.unwrap();
+        cache.clear().await.unwrap();
+        assert_eq!(cache.get("a").await.unwrap(), None);
+    }
+}
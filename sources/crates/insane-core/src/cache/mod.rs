@@ -0,0 +1,196 @@
+//! Key/value cache shared by the application, see [`Context::cache`].
+//!
+//! The backend is selected by the `cache` configuration, see
+//! [`CacheConfig`]. Values are stored as bytes; the helpers on `dyn Cache`
+//! store any serializable value as JSON.
+//!
+//! ```rust
+//! use insane_core::{cache::Cache, error::Result};
+//! use std::time::Duration;
+//!
+//! async fn count_users() -> Result<u64> {
+//!     Ok(42)
+//! }
+//!
+//! async fn users(cache: &dyn Cache) -> Result<u64> {
+//!     cache
+//!         .get_or_insert_with("users:count", Some(Duration::from_secs(60)), count_users)
+//!         .await
+//! }
+//! ```
+//!
+//! [`Context::cache`]: crate::context::Context::cache
+
+mod memory;
+mod null;
+#[cfg(feature = "with-redis")]
+mod redis;
+
+use std::{fmt::Debug, future::Future, sync::Arc, time::Duration};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+pub use self::memory::MemoryCache;
+pub use self::null::NullCache;
+#[cfg(feature = "with-redis")]
+pub use self::redis::RedisCache;
+use crate::{
+    config::CacheConfig,
+    error::{Error, Result},
+};
+
+/// A cache backend.
+#[async_trait::async_trait]
+pub trait Cache: Send + Sync + Debug {
+    /// Get the value of `key`, `None` when missing or expired.
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Store `value` under `key`, expiring after `ttl` when given.
+    async fn set(&self, key: &str, value: Vec<u8>, ttl: Option<Duration>) -> Result<()>;
+
+    /// Remove `key`, returns whether it was present.
+    async fn delete(&self, key: &str) -> Result<bool>;
+
+    /// Remove every entry.
+    async fn clear(&self) -> Result<()>;
+}
+
+impl dyn Cache + '_ {
+    /// Get the value of `key`, deserialized from JSON.
+    ///
+    /// # Errors
+    /// When the backend fails or the stored value is not a `T`.
+    pub async fn get_json<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        match self.get(key).await? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes).map_err(Error::JSON)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Store `value` under `key`, serialized to JSON.
+    ///
+    /// # Errors
+    /// When the backend fails or `value` cannot be serialized.
+    pub async fn set_json<T: Serialize + Sync>(
+        &self,
+        key: &str,
+        value: &T,
+        ttl: Option<Duration>,
+    ) -> Result<()> {
+        let bytes = serde_json::to_vec(value).map_err(Error::JSON)?;
+        self.set(key, bytes, ttl).await
+    }
+
+    /// Get the value of `key`, or compute it with `f` and store it when
+    /// missing.
+    ///
+    /// # Errors
+    /// When the backend or `f` fails.
+    pub async fn get_or_insert_with<T, F, Fut>(
+        &self,
+        key: &str,
+        ttl: Option<Duration>,
+        f: F,
+    ) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned + Sync,
+        F: FnOnce() -> Fut + Send,
+        Fut: Future<Output = Result<T>> + Send,
+    {
+        if let Some(value) = self.get_json(key).await? {
+            return Ok(value);
+        }
+        let value = f().await?;
+        self.set_json(key, &value, ttl).await?;
+        Ok(value)
+    }
+}
+
+/// Create the cache selected by the configuration.
+///
+/// # Errors
+/// When the `redis` backend is selected but Redis is not configured, or with
+/// an empty prefix.
+pub fn create(
+    config: &CacheConfig,
+    #[cfg(feature = "with-redis")] redis: Option<&crate::redis::RedisPool>,
+) -> Result<Arc<dyn Cache>> {
+    Ok(match config {
+        CacheConfig::Null => Arc::new(NullCache),
+        CacheConfig::Memory { capacity } => Arc::new(MemoryCache::new(*capacity)),
+        #[cfg(feature = "with-redis")]
+        CacheConfig::Redis { prefix } => {
+            if prefix.is_empty() {
+                return Err(Error::new(
+                    crate::error::ErrorCode::CONFIG,
+                    "the `redis` cache backend requires a non-empty `cache.prefix`",
+                ));
+            }
+            let pool = redis.ok_or_else(|| {
+                Error::string("the `redis` cache backend requires the `redis` configuration")
+            })?;
+            Arc::new(RedisCache::new(pool.clone(), prefix))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn get_or_insert_with_computes_a_missing_value_once() {
+        let cache: Arc<dyn Cache> = Arc::new(MemoryCache::new(10));
+        let calls = AtomicUsize::new(0);
+        let count = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Ok(42_u64)
+        };
+
+        assert_eq!(
+            cache
+                .get_or_insert_with("count", None, count)
+                .await
+                .unwrap(),
+            42
+        );
+        assert_eq!(
+            cache
+                .get_or_insert_with("count", None, count)
+                .await
+                .unwrap(),
+            42
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(cache.get("count").await.unwrap(), Some(b"42".to_vec()));
+    }
+
+    #[tokio::test]
+    async fn get_or_insert_with_does_not_store_errors() {
+        let cache: Arc<dyn Cache> = Arc::new(MemoryCache::new(10));
+        let err = cache
+            .get_or_insert_with::<u64, _, _>("count", None, || async {
+                Err(Error::string("database is down"))
+            })
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), "database is down");
+        assert_eq!(cache.get("count").await.unwrap(), None);
+    }
+
+    #[cfg(feature = "with-redis")]
+    #[test]
+    fn refuses_an_empty_redis_prefix() {
+        let config = CacheConfig::Redis {
+            prefix: String::new(),
+        };
+        let err = create(&config, None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the `redis` cache backend requires a non-empty `cache.prefix`"
+        );
+    }
+}
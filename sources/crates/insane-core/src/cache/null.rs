@@ -0,0 +1,27 @@
+use std::time::Duration;
+
+use super::Cache;
+use crate::error::Result;
+
+/// A cache storing nothing, for tests and to disable caching.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NullCache;
+
+#[async_trait::async_trait]
+impl Cache for NullCache {
+    async fn get(&self, _key: &str) -> Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    async fn set(&self, _key: &str, _value: Vec<u8>, _ttl: Option<Duration>) -> Result<()> {
+        Ok(())
+    }
+
+    async fn delete(&self, _key: &str) -> Result<bool> {
+        Ok(false)
+    }
+
+    async fn clear(&self) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+use std::time::Duration;
+
+use redis::AsyncCommands;
+
+use super::Cache;
+use crate::{
+    error::{Error, Result},
+    redis::RedisPool,
+};
+
+/// A cache stored in Redis, shared by every instance of the application.
+#[derive(Debug, Clone)]
+pub struct RedisCache {
+    pool: RedisPool,
+    prefix: String,
+}
+
+impl RedisCache {
+    /// Create a cache storing its keys under `prefix`.
+    #[must_use]
+    pub fn new(pool: RedisPool, prefix: &str) -> Self {
+        Self {
+            pool,
+            prefix: prefix.to_string(),
+        }
+    }
+
+    fn key(&self, key: &str) -> String {
+        format!("{}{key}", self.prefix)
+    }
+}
+
+/// The `SCAN MATCH` pattern of the keys under `prefix`, with the glob
+/// characters of the prefix escaped.
+fn scan_pattern(prefix: &str) -> String {
+    let mut pattern = String::with_capacity(prefix.len() + 1);
+    for c in prefix.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('*');
+    pattern
+}
+
+#[async_trait::async_trait]
+impl Cache for RedisCache {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let mut conn = self.pool.get().await?;
+        Ok(conn.get(self.key(key)).await?)
+    }
+
+    async fn set(&self, key: &str, value: Vec<u8>, ttl: Option<Duration>) -> Result<()> {
+        let mut conn = self.pool.get().await?;
+        let mut cmd = redis::cmd("SET");
+        cmd.arg(self.key(key)).arg(value);
+        if let Some(ttl) = ttl {
+            // PX expects at least one millisecond.
+            cmd.arg("PX").arg(ttl.as_millis().max(1) as u64);
+        }
+        cmd.query_async::<()>(&mut *conn).await?;
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<bool> {
+        let mut conn = self.pool.get().await?;
+        let removed: u64 = conn.del(self.key(key)).await?;
+        Ok(removed > 0)
+    }
+
+    /// Remove the keys under the prefix, scanning in batches so Redis is not
+    /// blocked on large caches. Refuses to run without a prefix, as it would
+    /// remove every key of the database.
+    async fn clear(&self) -> Result<()> {
+        if self.prefix.is_empty() {
+            return Err(Error::string(
+                "refusing to clear a redis cache without a prefix",
+            ));
+        }
+        let mut conn = self.pool.get().await?;
+        let pattern = scan_pattern(&self.prefix);
+        let mut cursor: u64 = 0;
+        loop {
+            let (next, keys): (u64, Vec<String>) = redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(&pattern)
+                .arg("COUNT")
+                .arg(500)
+                .query_async(&mut *conn)
+                .await?;
+            if !keys.is_empty() {
+                conn.del::<_, ()>(keys).await?;
+            }
+            if next == 0 {
+                return Ok(());
+            }
+            cursor = next;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(prefix: &str) -> RedisCache {
+        let manager = crate::redis::RedisConnectionManager::new("redis://127.0.0.1:1").unwrap();
+        RedisCache::new(bb8::Pool::builder().build_unchecked(manager), prefix)
+    }
+
+    #[test]
+    fn escapes_the_glob_characters_of_the_prefix() {
+        assert_eq!(scan_pattern("cache:"), "cache:*");
+        assert_eq!(scan_pattern("a*b?[c]\\:"), "a\\*b\\?\\[c\\]\\\\:*");
+        assert_eq!(scan_pattern(""), "*");
+    }
+
+    #[tokio::test]
+    async fn refuses_to_clear_without_a_prefix() {
+        let err = cache("").clear().await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "refusing to clear a redis cache without a prefix"
+        );
+    }
+}
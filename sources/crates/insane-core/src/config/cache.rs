@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+
+fn default_capacity() -> usize {
+    1_000
+}
+
+#[cfg(feature = "with-redis")]
+fn default_prefix() -> String {
+    "cache:".to_string()
+}
+
+/// Cache configuration
+///
+/// Selects the backend of [`crate::context::Context::cache`]. Defaults to an
+/// in-process LRU cache when the section is missing.
+///
+/// Example:
+/// ```yaml
+/// # production
+/// cache:
+///   backend: redis
+///   prefix: "myapp:cache:"
+///
+/// # test
+/// cache:
+///   backend: "null"
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "backend", rename_all = "snake_case")]
+pub enum CacheConfig {
+    /// Stores nothing, every lookup is a miss.
+    Null,
+
+    /// In-process LRU cache, lost on restart and not shared between
+    /// instances.
+    Memory {
+        /// Maximum number of entries, the least recently used are evicted
+        /// first.
+        #[serde(default = "default_capacity")]
+        capacity: usize,
+    },
+
+    /// Cache stored in Redis, using the pool of the `redis` configuration.
+    #[cfg(feature = "with-redis")]
+    Redis {
+        /// Prefix added to every key, so that `clear` only removes the cache
+        /// entries.
+        #[serde(default = "default_prefix")]
+        prefix: String,
+    },
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self::Memory {
+            capacity: default_capacity(),
+        }
+    }
+}
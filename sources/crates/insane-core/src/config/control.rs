@@ -0,0 +1,18 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// Local control socket configuration, see [`crate::control`].
+///
+/// Example:
+/// ```yaml
+/// control:
+///   socket: /run/myapp/control.sock
+/// ```
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct ControlConfig {
+    /// Path of the Unix socket, only readable by the user running the
+    /// application. No socket is opened when not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub socket: Option<PathBuf>,
+}
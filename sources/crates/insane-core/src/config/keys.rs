@@ -0,0 +1,401 @@
+//! The keys of a configuration, found by running its `Deserialize`
+//! implementation on a deserializer recording the fields it asks for. Unlike
+//! serializing the default configuration, this also finds the optional keys,
+//! like `sql.max_connections`.
+
+use std::{cell::RefCell, collections::BTreeSet, fmt};
+
+use serde::de::{
+    self, value::StrDeserializer, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+};
+
+use super::loader::{to_plain_yaml, Config};
+use crate::error::Result;
+
+/// Every key of `C`, as dotted paths, sorted. Maps, lists and the sections
+/// whose shape depends on their content (the tagged enums) are keys
+/// themselves, completed by the keys of the default configuration.
+///
+/// # Errors
+/// When the default configuration can't be serialized.
+pub fn keys<C: Config>() -> Result<BTreeSet<String>> {
+    let state = RefCell::new(State::default());
+
+    // A field failing to deserialize aborts its parent, skip it and try again
+    // for the fields after it.
+    loop {
+        let _ = C::deserialize(Recorder {
+            path: String::new(),
+            state: &state,
+        });
+        let mut state = state.borrow_mut();
+        let Some(failed) = state.failed.take() else {
+            break;
+        };
+        if !state.skip.insert(failed) {
+            break;
+        }
+    }
+
+    let mut keys = state.into_inner().keys;
+    let defaults: serde_yaml::Value = serde_yaml::from_str(&to_plain_yaml(&C::default())?)?;
+    collect_leaves(&defaults, "", &mut keys);
+
+    keep_leaves(&mut keys);
+    Ok(keys)
+}
+
+/// Remove the keys having other keys under them, `sql` when `sql.uri` is
+/// there.
+pub fn keep_leaves(keys: &mut BTreeSet<String>) {
+    let parents = keys
+        .iter()
+        .filter(|key| {
+            let prefix = format!("{key}.");
+            keys.range(prefix.clone()..)
+                .next()
+                .is_some_and(|next| next.starts_with(&prefix))
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    for parent in parents {
+        keys.remove(&parent);
+    }
+}
+
+fn collect_leaves(value: &serde_yaml::Value, prefix: &str, keys: &mut BTreeSet<String>) {
+    match value {
+        serde_yaml::Value::Mapping(mapping) if !mapping.is_empty() => {
+            for (key, value) in mapping {
+                if let Some(key) = key.as_str() {
+                    collect_leaves(value, &join(prefix, key), keys);
+                }
+            }
+        }
+        _ if !prefix.is_empty() => {
+            keys.insert(prefix.to_string());
+        }
+        _ => {}
+    }
+}
+
+fn join(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{prefix}.{key}")
+    }
+}
+
+#[derive(Default)]
+struct State {
+    keys: BTreeSet<String>,
+    /// The fields not to visit anymore.
+    skip: BTreeSet<String>,
+    /// The first field that failed in this run.
+    failed: Option<String>,
+}
+
+#[derive(Debug)]
+struct Stop(String);
+
+impl fmt::Display for Stop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Stop {}
+
+impl de::Error for Stop {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+/// Answers every request with an empty value, recording the path of the
+/// leaves.
+struct Recorder<'a> {
+    path: String,
+    state: &'a RefCell<State>,
+}
+
+impl Recorder<'_> {
+    fn leaf(&self) {
+        if !self.path.is_empty() {
+            self.state.borrow_mut().keys.insert(self.path.clone());
+        }
+    }
+}
+
+macro_rules! leaf {
+    ($($method:ident => $visit:ident($value:expr)),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Stop> {
+                self.leaf();
+                visitor.$visit($value)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Recorder<'_> {
+    type Error = Stop;
+
+    leaf! {
+        deserialize_bool => visit_bool(false),
+        deserialize_i8 => visit_i64(0),
+        deserialize_i16 => visit_i64(0),
+        deserialize_i32 => visit_i64(0),
+        deserialize_i64 => visit_i64(0),
+        deserialize_u8 => visit_u64(0),
+        deserialize_u16 => visit_u64(0),
+        deserialize_u32 => visit_u64(0),
+        deserialize_u64 => visit_u64(0),
+        deserialize_f32 => visit_f64(0.0),
+        deserialize_f64 => visit_f64(0.0),
+        deserialize_char => visit_char(' '),
+        deserialize_str => visit_str(""),
+        deserialize_string => visit_str(""),
+        deserialize_bytes => visit_bytes(&[]),
+        deserialize_byte_buf => visit_bytes(&[]),
+        deserialize_identifier => visit_str(""),
+    }
+
+    /// The shape depends on the content, nothing to visit.
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Stop> {
+        self.leaf();
+        Err(Stop(self.path))
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Stop> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Stop> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Stop> {
+        self.leaf();
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Stop> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Stop> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Stop> {
+        self.leaf();
+        visitor.visit_seq(Empty)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Stop> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Stop> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Stop> {
+        self.leaf();
+        visitor.visit_map(Empty)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Stop> {
+        let skip = self.state.borrow().skip.clone();
+        let fields = fields
+            .iter()
+            .filter(|field| !skip.contains(&join(&self.path, field)))
+            .copied()
+            .collect::<Vec<_>>();
+        visitor.visit_map(Fields {
+            path: self.path,
+            fields: fields.into_iter(),
+            current: None,
+            state: self.state,
+        })
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Stop> {
+        self.leaf();
+        let variant: StrDeserializer<'_, Stop> = variants
+            .first()
+            .copied()
+            .unwrap_or_default()
+            .into_deserializer();
+        visitor.visit_enum(variant)
+    }
+}
+
+/// The fields of a struct, each value recorded under its path.
+struct Fields<'a> {
+    path: String,
+    fields: std::vec::IntoIter<&'static str>,
+    current: Option<String>,
+    state: &'a RefCell<State>,
+}
+
+impl<'de> MapAccess<'de> for Fields<'_> {
+    type Error = Stop;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Stop> {
+        let Some(field) = self.fields.next() else {
+            return Ok(None);
+        };
+        self.current = Some(join(&self.path, field));
+        let field: StrDeserializer<'_, Stop> = field.into_deserializer();
+        seed.deserialize(field).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Stop> {
+        let path = self.current.take().unwrap_or_default();
+        seed.deserialize(Recorder {
+            path: path.clone(),
+            state: self.state,
+        })
+        .inspect_err(|_| {
+            self.state.borrow_mut().failed.get_or_insert(path);
+        })
+    }
+}
+
+/// An empty list or map.
+struct Empty;
+
+impl<'de> SeqAccess<'de> for Empty {
+    type Error = Stop;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        _seed: T,
+    ) -> Result<Option<T::Value>, Stop> {
+        Ok(None)
+    }
+}
+
+impl<'de> MapAccess<'de> for Empty {
+    type Error = Stop;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        _seed: K,
+    ) -> Result<Option<K::Value>, Stop> {
+        Ok(None)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, _seed: V) -> Result<V::Value, Stop> {
+        Err(Stop("no value".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::config::{CacheConfig, InsaneConfig};
+
+    #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+    struct Pool {
+        size: u32,
+        timeout: Option<u64>,
+    }
+
+    #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+    struct AppConfig {
+        name: String,
+        #[serde(default)]
+        pool: Pool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        replica: Option<Pool>,
+        tags: Vec<String>,
+        labels: BTreeMap<String, String>,
+        #[serde(default)]
+        cache: CacheConfig,
+        debug: bool,
+    }
+
+    impl Config for AppConfig {}
+
+    fn set(keys: &[&str]) -> BTreeSet<String> {
+        keys.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn finds_the_optional_and_nested_keys() {
+        assert_eq!(
+            keys::<AppConfig>().unwrap(),
+            set(&[
+                // the tagged enum, from the default configuration
+                "cache.backend",
+                "cache.capacity",
+                "debug",
+                "labels",
+                "name",
+                "pool.size",
+                "pool.timeout",
+                "replica.size",
+                "replica.timeout",
+                "tags",
+            ])
+        );
+    }
+
+    #[test]
+    fn finds_the_keys_of_the_core_configuration() {
+        let keys = keys::<InsaneConfig>().unwrap();
+        assert!(keys.contains("application_name"));
+        assert!(keys.contains("servers.shutdown_timeout"));
+        assert!(keys.contains("control.socket"));
+        assert!(!keys.contains("servers"));
+    }
+
+    #[test]
+    fn keeps_the_leaves_only() {
+        let mut keys = set(&[
+            "sql",
+            "sql.uri",
+            "sql.pool",
+            "sql.pool.size",
+            "sqlite",
+            "a.b",
+            "a",
+        ]);
+        keep_leaves(&mut keys);
+        assert_eq!(keys, set(&["a.b", "sql.pool.size", "sql.uri", "sqlite"]));
+    }
+}
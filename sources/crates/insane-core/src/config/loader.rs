@@ -1,23 +1,127 @@
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use std::{
-    fmt::Debug,
+    collections::BTreeMap,
+    fmt::{self, Debug},
     path::{Path, PathBuf},
+    sync::RwLock,
 };
 
+use super::{secret, template};
 use crate::environment::Environment;
 use crate::error;
 use std::error::Error as StdError;
 use {
     config::{
-        builder::DefaultState, Config as ExtConfig, ConfigError, Environment as ConfigEnvironment,
-        File,
+        builder::DefaultState, Config as ExtConfig, ConfigError, File, FileFormat, Map, Source,
+        Value, ValueKind,
     },
     std::{fs::OpenOptions, io::Write},
 };
 
 lazy_static! {
     static ref DEFAULT_FOLDER: PathBuf = PathBuf::from(".config");
+    static ref CONFIG_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+    static ref ENV_SEPARATOR: RwLock<Option<String>> = RwLock::new(None);
+}
+
+/// Environment variable setting the configuration folder, see [`config_dir`].
+pub const CONFIG_DIR_ENV: &str = "INSANE_CONFIG_DIR";
+
+/// Default separator of the environment variables, see [`EnvSource`].
+pub const DEFAULT_ENV_SEPARATOR: &str = "__";
+
+/// Last segment of the environment variables naming a file to read the value
+/// from, after the separator: `MYAPP__SQL__URI__FILE`, see [`EnvSource`].
+pub const ENV_FILE_SEGMENT: &str = "FILE";
+
+/// Extensions of the configuration files, in order of preference.
+const EXTENSIONS: [(&str, FileFormat); 4] = [
+    ("yaml", FileFormat::Yaml),
+    ("yml", FileFormat::Yaml),
+    ("toml", FileFormat::Toml),
+    ("json", FileFormat::Json),
+];
+
+/// Set the configuration folder of the process, used instead of
+/// `INSANE_CONFIG_DIR`. Must be called before the configuration is loaded.
+pub fn set_config_dir(path: impl Into<PathBuf>) {
+    *CONFIG_DIR.write().unwrap_or_else(|err| err.into_inner()) = Some(path.into());
+}
+
+/// The configuration folder: the one given to [`set_config_dir`], else the
+/// `INSANE_CONFIG_DIR` environment variable, else `.config`.
+#[must_use]
+pub fn config_dir() -> PathBuf {
+    if let Some(path) = &*CONFIG_DIR.read().unwrap_or_else(|err| err.into_inner()) {
+        return path.clone();
+    }
+    std::env::var_os(CONFIG_DIR_ENV).map_or_else(|| DEFAULT_FOLDER.clone(), PathBuf::from)
+}
+
+/// Set the separator of the environment variables overriding the
+/// configuration, `__` by default. Must be called before the configuration is
+/// loaded.
+pub fn set_env_separator(separator: impl Into<String>) {
+    *ENV_SEPARATOR.write().unwrap_or_else(|err| err.into_inner()) = Some(separator.into());
+}
+
+/// Serializes the tests changing the environment variables or the
+/// configuration folder of the process.
+#[cfg(any(test, feature = "testing"))]
+pub fn env_lock() -> std::sync::MutexGuard<'static, ()> {
+    static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    LOCK.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Use `path` as the configuration folder until dropped, holding the
+/// [`env_lock`] meanwhile.
+#[cfg(any(test, feature = "testing"))]
+pub struct TestConfigDir {
+    previous: Option<PathBuf>,
+    _env: std::sync::MutexGuard<'static, ()>,
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl TestConfigDir {
+    #[must_use]
+    pub fn new(path: &Path) -> Self {
+        let env = env_lock();
+        let previous = CONFIG_DIR
+            .write()
+            .unwrap_or_else(|err| err.into_inner())
+            .replace(path.to_path_buf());
+        Self {
+            previous,
+            _env: env,
+        }
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl Drop for TestConfigDir {
+    fn drop(&mut self) {
+        *CONFIG_DIR.write().unwrap_or_else(|err| err.into_inner()) = self.previous.take();
+    }
+}
+
+fn env_separator() -> String {
+    ENV_SEPARATOR
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+        .clone()
+        .unwrap_or_else(|| DEFAULT_ENV_SEPARATOR.to_string())
+}
+
+/// The format of a configuration file, from its extension. YAML when
+/// unknown.
+#[must_use]
+pub fn file_format(path: &Path) -> FileFormat {
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    EXTENSIONS
+        .iter()
+        .find(|(ext, _)| Some(*ext) == extension)
+        .map_or(FileFormat::Yaml, |(_, format)| *format)
 }
 
 #[async_trait::async_trait]
@@ -28,38 +132,93 @@ pub trait Config: Serialize + for<'de> Deserialize<'de> + Default + Clone + Debu
         false
     }
 
+    /// The YAML of the configuration, with the [`secret::Secret`]s redacted.
     fn to_yaml(&self) -> error::Result<String> {
-        Ok(serde_yaml::to_string(self)?)
+        secret::redacting(|| Ok(serde_yaml::to_string(self)?))
+    }
+
+    /// The YAML of the configuration, with the [`secret::Secret`]s redacted,
+    /// like [`Config::to_yaml`].
+    fn to_redacted_yaml(&self) -> error::Result<String> {
+        self.to_yaml()
     }
 
     fn print(&self) -> error::Result<()> {
-        println!("{}", self.to_yaml()?);
+        println!("{}", self.to_redacted_yaml()?);
         Ok(())
     }
 
-    fn file_names(env: &Environment, app_name: &str, path: Option<&Path>) -> [PathBuf; 2] {
-        let path = path.unwrap_or(DEFAULT_FOLDER.as_path().into());
+    /// The configuration files of `env` found in `path`, or in [`config_dir`]
+    /// when `None`. They are merged in this order, the later ones winning:
+    /// 1. `{app}.yaml`, shared by every environment
+    /// 2. `{env}-{app}.yaml`
+    /// 3. `{env}-{app}.local.yaml`, for the overrides of a machine
+    ///
+    /// Each file can also be `.yml`, `.toml` or `.json`, the first one found
+    /// in this order is used.
+    fn file_names(env: &Environment, app_name: &str, path: Option<&Path>) -> Vec<PathBuf> {
+        let path = path.map_or_else(config_dir, Path::to_path_buf);
         [
-            path.join(format!("{env}-{app_name}.local.yaml")),
-            path.join(format!("{env}-{app_name}.yaml")),
+            app_name.to_string(),
+            format!("{env}-{app_name}"),
+            format!("{env}-{app_name}.local"),
         ]
+        .iter()
+        .filter_map(|name| {
+            EXTENSIONS
+                .iter()
+                .map(|(extension, _)| path.join(format!("{name}.{extension}")))
+                .find(|file| file.exists())
+        })
+        .collect()
     }
 
+    /// Write the configuration, secrets redacted, to the
+    /// `{env}-{app}.local.yaml` file of [`config_dir`].
     fn generate(&self, env: &Environment, app_name: &str) -> error::Result<()> {
         // let files = Self::file_names(env, &self.application_name(), None);
-        let files = Self::file_names(env, app_name, None);
-        let file_name = files[0].clone();
+        let file_name = config_dir().join(format!("{env}-{app_name}.local.yaml"));
 
         let mut file = OpenOptions::new()
             .read(true)
-            .write(true) // <--------- this
+            .write(true)
             .create(true)
+            .truncate(true)
             .open(&file_name)?;
         file.write_all(self.to_yaml()?.as_bytes())?;
         Ok(())
     }
 }
 
+/// The YAML of `config` with the [`secret::Secret`]s as they are, to load
+/// the defaults back. Anything shown or written out goes through
+/// [`Config::to_yaml`] instead.
+#[doc(hidden)]
+pub fn to_plain_yaml<C: Serialize>(config: &C) -> error::Result<String> {
+    Ok(serde_yaml::to_string(config)?)
+}
+
+/// Where a configuration value comes from, see [`ConfigLoader::sources`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// The default value of the configuration.
+    Default,
+    /// A configuration file.
+    File(PathBuf),
+    /// An environment variable.
+    Env(String),
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::File(path) => write!(f, "file {}", path.display()),
+            Self::Env(var) => write!(f, "env {var}"),
+        }
+    }
+}
+
 #[async_trait::async_trait]
 pub trait ConfigLoader {
     type Config: Config;
@@ -70,11 +229,8 @@ pub trait ConfigLoader {
         app_name: &str,
         path: Option<&Path>,
     ) -> error::Result<Self::Config> {
-        let files = Self::Config::file_names(env, app_name, path);
-
-        let config_builder = Self::parse(app_name, files)?;
         let config = Self::raw_from_folder(env, app_name, path)?;
-  
+
         // You can deserialize (and thus freeze) the entire configuration as
         Ok(config.try_deserialize()?)
     }
@@ -86,33 +242,29 @@ pub trait ConfigLoader {
     ) -> error::Result<ExtConfig> {
         let files = Self::Config::file_names(env, app_name, path);
 
-        let config_builder = Self::parse(app_name, files)?;
+        let config_builder = Self::parse(env, app_name, files)?;
         let config = config_builder
             .set_override("application_name", app_name.to_string())?
             .build()?;
         Ok(config)
     }
 
-    fn from_key(
-        key: &str,
-        env: &Environment,
-        app_name: &str,   
-    ) -> error::Result<Self::Config> {
+    fn from_key(key: &str, env: &Environment, app_name: &str) -> error::Result<Self::Config> {
         let raw_config = Self::raw_from_folder(env, app_name, None)?;
         let config = raw_config.get::<Self::Config>(key)?;
         Ok(config)
     }
 
     fn parse(
+        env: &Environment,
         app_name: &str,
-        files: [PathBuf; 2],
+        files: Vec<PathBuf>,
     ) -> error::Result<config::ConfigBuilder<DefaultState>> {
         let config = ExtConfig::builder();
 
         // Load Defaults
         let mut config = config.add_source(File::from_str(
-            Self::Config::default()
-                .to_yaml()
+            to_plain_yaml(&Self::Config::default())
                 .map_err(|err| ConfigError::Foreign(Box::new(err)))?
                 .as_str(),
             config::FileFormat::Yaml,
@@ -120,24 +272,599 @@ pub trait ConfigLoader {
 
         for file in files.iter() {
             if file.exists() {
-                // Merge Config File from Default Location
-                config = config.add_source(File::with_name(file.to_str().unwrap()).required(false));
+                // Merge Config File from Default Location, rendered first
+                let content = template::render_file(file, env, app_name)?;
+                config = config.add_source(File::from_str(&content, file_format(file)));
             }
         }
 
         // Merge Environment Variable Overrides
-        let config = config.add_source(
-            ConfigEnvironment::with_prefix(&app_name.to_uppercase())
-                .separator("_")
-                // std::env::set_var("APP_LIST", "Hello World");
-                // will be parsed as a list of strings
-                .list_separator(" "),
-        );
+        let config = config.add_source(Self::env_source(app_name));
 
         Ok(config)
     }
 
-    fn file_name(env: &Environment, app_name: &str, path: Option<&Path>) -> [PathBuf; 2] {
+    /// The environment variables overriding the configuration.
+    fn env_source(app_name: &str) -> EnvSource {
+        EnvSource::new(app_name)
+    }
+
+    /// Where each value of the configuration comes from, keyed by its dotted
+    /// path (`http.port`). Later sources override the earlier ones, in the
+    /// order of [`ConfigLoader::parse`].
+    ///
+    /// # Errors
+    /// When a file can't be rendered or parsed.
+    fn sources(
+        env: &Environment,
+        app_name: &str,
+        path: Option<&Path>,
+    ) -> error::Result<BTreeMap<String, ConfigSource>> {
+        let mut sources = BTreeMap::new();
+
+        let defaults: serde_yaml::Value =
+            serde_yaml::from_str(&to_plain_yaml(&Self::Config::default())?)?;
+        flatten_keys(&defaults, "", &mut |key| {
+            sources.insert(key, ConfigSource::Default);
+        });
+
+        for file in Self::Config::file_names(env, app_name, path) {
+            if file.exists() {
+                let content = template::render_file(&file, env, app_name)?;
+                let content: serde_yaml::Value = ExtConfig::builder()
+                    .add_source(File::from_str(&content, file_format(&file)))
+                    .build()?
+                    .try_deserialize()?;
+                flatten_keys(&content, "", &mut |key| {
+                    sources.insert(key, ConfigSource::File(file.clone()));
+                });
+            }
+        }
+
+        for (key, var) in Self::env_source(app_name).vars() {
+            sources.insert(key, ConfigSource::Env(var));
+        }
+
+        Ok(sources)
+    }
+
+    fn file_name(env: &Environment, app_name: &str, path: Option<&Path>) -> Vec<PathBuf> {
         Self::Config::file_names(env, app_name, path)
     }
 }
+
+/// The environment variables overriding the configuration: the application
+/// name in upper case, then the path of the key, separated by `__`.
+/// `MYAPP__SQL__MAX_CONNECTIONS` sets `sql.max_connections`. The separator
+/// can be changed with [`set_env_separator`].
+///
+/// Values are typed: `true` and `false` are booleans, `8080` and `0.5`
+/// numbers, and `[a, b]` or `{a: 1}` are parsed as YAML lists and maps. Any
+/// other value is a string, including the numbers written differently from
+/// their canonical form, like `00123`, `1.10` or `1e3`.
+///
+/// Variables ending with the separator and `FILE` set their key to the
+/// content of the file they name, for Docker and Kubernetes secrets:
+/// `MYAPP__SQL__URI__FILE` sets `sql.uri`, see [`super::secret`].
+#[derive(Debug, Clone)]
+pub struct EnvSource {
+    prefix: String,
+    separator: String,
+}
+
+impl EnvSource {
+    #[must_use]
+    pub fn new(app_name: &str) -> Self {
+        Self {
+            prefix: app_name.to_uppercase(),
+            separator: env_separator(),
+        }
+    }
+
+    /// The key set by the variable `var`, `None` when it doesn't have the
+    /// prefix.
+    fn key(&self, var: &str) -> Option<String> {
+        let var = var.to_lowercase();
+        let key = var.strip_prefix(&format!("{}{}", self.prefix.to_lowercase(), self.separator))?;
+        if key.is_empty() {
+            return None;
+        }
+        Some(
+            key.split(self.separator.as_str())
+                .collect::<Vec<_>>()
+                .join("."),
+        )
+    }
+
+    /// The suffix of the variables naming a file, `__FILE` with the default
+    /// separator.
+    fn file_suffix(&self) -> String {
+        format!("{}{ENV_FILE_SEGMENT}", self.separator)
+    }
+
+    /// The variable setting `key`.
+    #[must_use]
+    pub fn var(&self, key: &str) -> String {
+        let path = key
+            .split('.')
+            .map(str::to_uppercase)
+            .collect::<Vec<_>>()
+            .join(&self.separator);
+        format!("{}{}{path}", self.prefix, self.separator)
+    }
+
+    /// The variables overriding the configuration, by key.
+    #[must_use]
+    pub fn vars(&self) -> BTreeMap<String, String> {
+        let file_suffix = self.file_suffix();
+        std::env::vars()
+            .filter_map(|(var, _)| {
+                let key = self.key(var.strip_suffix(&file_suffix).unwrap_or(&var))?;
+                Some((key, var))
+            })
+            .collect()
+    }
+}
+
+impl Source for EnvSource {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        let mut values = Map::new();
+        let file_suffix = self.file_suffix();
+        for (var, value) in std::env::vars() {
+            if let Some(key) = var
+                .strip_suffix(&file_suffix)
+                .and_then(|name| self.key(name))
+            {
+                let secret = secret::read_file(&value)
+                    .map_err(|err| ConfigError::Message(format!("{var}: {err}")))?;
+                values.insert(key, Value::new(Some(&var), ValueKind::String(secret)));
+            } else if let Some(key) = self.key(&var) {
+                values.insert(key, env_value(&value, &var));
+            }
+        }
+        Ok(values)
+    }
+}
+
+/// The typed value of an environment variable, see [`EnvSource`].
+fn env_value(value: &str, var: &String) -> Value {
+    let trimmed = value.trim();
+    if trimmed.starts_with('[') || trimmed.starts_with('{') {
+        if let Ok(yaml) = serde_yaml::from_str(trimmed) {
+            return yaml_value(yaml, var);
+        }
+    }
+
+    // typed only when written in their canonical form, so that `00123`
+    // stays `00123`
+    let kind = if let Ok(boolean) = value.parse::<bool>() {
+        ValueKind::Boolean(boolean)
+    } else if let Some(integer) = value
+        .parse::<i64>()
+        .ok()
+        .filter(|integer| integer.to_string() == value)
+    {
+        ValueKind::I64(integer)
+    } else if let Some(float) = value.parse::<f64>().ok().filter(|float| {
+        // `inf` and `NaN` stay strings
+        float.is_finite() && (float.to_string() == value || format!("{float:?}") == value)
+    }) {
+        ValueKind::Float(float)
+    } else {
+        ValueKind::String(value.to_string())
+    };
+    Value::new(Some(var), kind)
+}
+
+fn yaml_value(value: serde_yaml::Value, var: &String) -> Value {
+    let kind = match value {
+        serde_yaml::Value::Null => ValueKind::Nil,
+        serde_yaml::Value::Bool(boolean) => ValueKind::Boolean(boolean),
+        serde_yaml::Value::Number(number) => number.as_i64().map_or_else(
+            || ValueKind::Float(number.as_f64().unwrap_or_default()),
+            ValueKind::I64,
+        ),
+        serde_yaml::Value::String(string) => ValueKind::String(string),
+        serde_yaml::Value::Sequence(values) => ValueKind::Array(
+            values
+                .into_iter()
+                .map(|value| yaml_value(value, var))
+                .collect(),
+        ),
+        serde_yaml::Value::Mapping(mapping) => ValueKind::Table(
+            mapping
+                .into_iter()
+                .filter_map(|(key, value)| {
+                    Some((key.as_str()?.to_string(), yaml_value(value, var)))
+                })
+                .collect(),
+        ),
+        serde_yaml::Value::Tagged(tagged) => return yaml_value(tagged.value, var),
+    };
+    Value::new(Some(var), kind)
+}
+
+/// Call `f` with the dotted path of every leaf of `value`. Sequences are
+/// leaves.
+fn flatten_keys(value: &serde_yaml::Value, prefix: &str, f: &mut impl FnMut(String)) {
+    match value {
+        serde_yaml::Value::Mapping(mapping) => {
+            for (key, value) in mapping {
+                let key = match key {
+                    serde_yaml::Value::String(key) => key.clone(),
+                    key => serde_yaml::to_string(key)
+                        .unwrap_or_default()
+                        .trim()
+                        .to_string(),
+                };
+                let key = if prefix.is_empty() {
+                    key
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                flatten_keys(value, &key, f);
+            }
+        }
+        _ if !prefix.is_empty() => f(prefix.to_string()),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Secret;
+
+    fn kind(value: &str) -> ValueKind {
+        env_value(value, &"TEST__KEY".to_string()).kind
+    }
+
+    #[test]
+    fn types_the_canonical_values() {
+        assert_eq!(kind("true"), ValueKind::Boolean(true));
+        assert_eq!(kind("false"), ValueKind::Boolean(false));
+        assert_eq!(kind("8080"), ValueKind::I64(8080));
+        assert_eq!(kind("-3"), ValueKind::I64(-3));
+        assert_eq!(kind("0.5"), ValueKind::Float(0.5));
+        assert_eq!(kind("1.0"), ValueKind::Float(1.0));
+    }
+
+    #[test]
+    fn keeps_the_other_values_as_strings() {
+        let values = [
+            "00123", "+5", "1.10", "1e3", "0x10", "inf", "NaN", " 8080", "True", "hello",
+        ];
+        for value in values {
+            assert_eq!(kind(value), ValueKind::String(value.to_string()), "{value}");
+        }
+    }
+
+    #[test]
+    fn parses_lists_and_maps() {
+        let ValueKind::Array(values) = kind("[a, 2, \"007\"]") else {
+            panic!("not a list");
+        };
+        let values = values
+            .into_iter()
+            .map(|value| value.kind)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            vec![
+                ValueKind::String("a".to_string()),
+                ValueKind::I64(2),
+                ValueKind::String("007".to_string()),
+            ]
+        );
+
+        let ValueKind::Table(table) = kind("{enable: true}") else {
+            panic!("not a map");
+        };
+        assert_eq!(table["enable"].kind, ValueKind::Boolean(true));
+
+        // not YAML
+        assert_eq!(kind("[a"), ValueKind::String("[a".to_string()));
+    }
+
+    #[test]
+    fn maps_the_variables_to_the_keys() {
+        let source = EnvSource::new("my_app");
+        assert_eq!(
+            source.key("MY_APP__SQL__MAX_CONNECTIONS").as_deref(),
+            Some("sql.max_connections")
+        );
+        assert_eq!(source.key("MY_APP__LOG_FILE").as_deref(), Some("log_file"));
+        assert_eq!(source.key("MY_APP__"), None);
+        assert_eq!(source.key("OTHER__SQL__URI"), None);
+        assert_eq!(
+            source.var("sql.max_connections"),
+            "MY_APP__SQL__MAX_CONNECTIONS"
+        );
+    }
+
+    #[test]
+    fn collects_the_overrides() {
+        // a prefix of its own and the lock, the variables are shared by the
+        // tests
+        let _env = env_lock();
+        let secret = std::env::temp_dir().join(format!("insane-env-test-{}", std::process::id()));
+        std::fs::write(&secret, "postgres://secret\n").unwrap();
+        let overrides: [(&str, std::ffi::OsString); 4] = [
+            ("ENV_TEST_APP__HTTP__PORT", "8080".into()),
+            ("ENV_TEST_APP__MAILER__CODE", "00123".into()),
+            ("ENV_TEST_APP__LOGGER__LOG_FILE", "app.log".into()),
+            (
+                "ENV_TEST_APP__SQL__URI__FILE",
+                secret.clone().into_os_string(),
+            ),
+        ];
+        for (var, value) in &overrides {
+            std::env::set_var(var, value);
+        }
+
+        let source = EnvSource::new("env_test_app");
+        let values = source.collect().unwrap();
+        let vars = source.vars();
+        for (var, _) in &overrides {
+            std::env::remove_var(var);
+        }
+        std::fs::remove_file(&secret).unwrap();
+
+        assert_eq!(values["http.port"].kind, ValueKind::I64(8080));
+        assert_eq!(
+            values["mailer.code"].kind,
+            ValueKind::String("00123".to_string())
+        );
+        assert_eq!(
+            values["logger.log_file"].kind,
+            ValueKind::String("app.log".to_string())
+        );
+        assert_eq!(
+            values["sql.uri"].kind,
+            ValueKind::String("postgres://secret".to_string())
+        );
+        assert_eq!(values.len(), 4);
+
+        assert_eq!(vars["sql.uri"], "ENV_TEST_APP__SQL__URI__FILE");
+        assert_eq!(vars["logger.log_file"], "ENV_TEST_APP__LOGGER__LOG_FILE");
+    }
+
+    #[test]
+    fn reads_the_files_with_the_custom_separator() {
+        let _env = env_lock();
+        let secret =
+            std::env::temp_dir().join(format!("insane-env-separator-{}", std::process::id()));
+        std::fs::write(&secret, "postgres://secret\n").unwrap();
+        std::env::set_var("SEP_TEST_APP_SQL_URI_FILE", &secret);
+
+        let source = EnvSource {
+            prefix: "SEP_TEST_APP".to_string(),
+            separator: "_".to_string(),
+        };
+        let values = source.collect().unwrap();
+        let vars = source.vars();
+        std::env::remove_var("SEP_TEST_APP_SQL_URI_FILE");
+        std::fs::remove_file(&secret).unwrap();
+
+        assert_eq!(
+            values["sql.uri"].kind,
+            ValueKind::String("postgres://secret".to_string())
+        );
+        assert_eq!(values.len(), 1);
+        assert_eq!(vars["sql.uri"], "SEP_TEST_APP_SQL_URI_FILE");
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct TokenConfig {
+        token: Secret,
+    }
+
+    impl Default for TokenConfig {
+        fn default() -> Self {
+            Self {
+                token: Secret::new("default-token"),
+            }
+        }
+    }
+
+    impl Config for TokenConfig {}
+
+    impl ConfigLoader for TokenConfig {
+        type Config = Self;
+        type Error = error::Error;
+    }
+
+    #[test]
+    fn keeps_the_default_secrets() {
+        let folder = std::env::temp_dir().join(format!("insane-no-config-{}", std::process::id()));
+        let config =
+            TokenConfig::from_folder(&Environment::Test, "token_test", Some(&folder)).unwrap();
+        assert_eq!(config.token.expose(), "default-token");
+
+        assert_eq!(to_plain_yaml(&config).unwrap(), "token: default-token\n");
+        assert_eq!(config.to_yaml().unwrap(), "token: '********'\n");
+        assert_eq!(config.to_redacted_yaml().unwrap(), "token: '********'\n");
+    }
+
+    /// A folder of configuration files, removed when dropped.
+    struct Folder(PathBuf);
+
+    impl Folder {
+        fn new(name: &str, files: &[(&str, &str)]) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("insane-loader-{name}-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&path);
+            std::fs::create_dir_all(&path).unwrap();
+            for (file, content) in files {
+                std::fs::write(path.join(file), content).unwrap();
+            }
+            Self(path)
+        }
+    }
+
+    impl Drop for Folder {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+    struct ServiceConfig {
+        name: String,
+        port: u16,
+        debug: bool,
+        tags: Vec<String>,
+    }
+
+    impl Config for ServiceConfig {}
+
+    impl ConfigLoader for ServiceConfig {
+        type Config = Self;
+        type Error = error::Error;
+    }
+
+    /// A YAML base, a TOML file for the environment and a JSON local file.
+    fn layered(name: &str) -> Folder {
+        Folder::new(
+            name,
+            &[
+                ("svc.yaml", "name: base\nport: 3000\ntags: [a, b]\n"),
+                (
+                    "svc.json",
+                    r#"{"name": "ignored, the yaml file comes first"}"#,
+                ),
+                ("test-svc.toml", "port = 4000\ndebug = true\n"),
+                (
+                    "test-svc.local.json",
+                    r#"{"debug": false, "tags": ["local"]}"#,
+                ),
+                ("development-svc.yaml", "name: development\n"),
+            ],
+        )
+    }
+
+    #[test]
+    fn finds_the_files_of_the_environment() {
+        let folder = layered("names");
+        let names = ServiceConfig::file_names(&Environment::Test, "svc", Some(&folder.0));
+        assert_eq!(
+            names,
+            vec![
+                folder.0.join("svc.yaml"),
+                folder.0.join("test-svc.toml"),
+                folder.0.join("test-svc.local.json"),
+            ]
+        );
+
+        let names = ServiceConfig::file_names(&Environment::Development, "svc", Some(&folder.0));
+        assert_eq!(
+            names,
+            vec![
+                folder.0.join("svc.yaml"),
+                folder.0.join("development-svc.yaml")
+            ]
+        );
+    }
+
+    #[test]
+    fn merges_the_files_in_order() {
+        let folder = layered("merge");
+        let config =
+            ServiceConfig::from_folder(&Environment::Test, "svc", Some(&folder.0)).unwrap();
+
+        // the base, overridden by the environment, then by the local file
+        assert_eq!(config.name, "base");
+        assert_eq!(config.port, 4000);
+        assert!(!config.debug);
+        assert_eq!(config.tags, vec!["local".to_string()]);
+
+        let sources = ServiceConfig::sources(&Environment::Test, "svc", Some(&folder.0)).unwrap();
+        assert_eq!(
+            sources["name"],
+            ConfigSource::File(folder.0.join("svc.yaml"))
+        );
+        assert_eq!(
+            sources["port"],
+            ConfigSource::File(folder.0.join("test-svc.toml"))
+        );
+        assert_eq!(
+            sources["debug"],
+            ConfigSource::File(folder.0.join("test-svc.local.json"))
+        );
+    }
+
+    #[test]
+    fn parses_the_files_in_the_given_order() {
+        let folder = layered("parse");
+        let files = vec![
+            folder.0.join("test-svc.local.json"),
+            folder.0.join("test-svc.toml"),
+            folder.0.join("missing.yaml"),
+        ];
+        let config: ServiceConfig = ServiceConfig::parse(&Environment::Test, "svc", files)
+            .unwrap()
+            .build()
+            .unwrap()
+            .try_deserialize()
+            .unwrap();
+
+        assert_eq!(config.name, "");
+        assert_eq!(config.port, 4000);
+        assert!(config.debug);
+        assert_eq!(config.tags, vec!["local".to_string()]);
+    }
+
+    #[test]
+    fn overrides_the_files_with_the_environment_variables() {
+        let folder = layered("env");
+        let _env = env_lock();
+        std::env::set_var("LOADER_ENV_SVC__PORT", "5000");
+        let config =
+            ServiceConfig::from_folder(&Environment::Test, "loader_env_svc", Some(&folder.0));
+        std::env::remove_var("LOADER_ENV_SVC__PORT");
+
+        assert_eq!(config.unwrap().port, 5000);
+    }
+
+    #[test]
+    fn reports_the_file_of_an_invalid_configuration() {
+        let folder = Folder::new("invalid", &[("test-svc.toml", "port = \n")]);
+        let err = ServiceConfig::from_folder(&Environment::Test, "svc", Some(&folder.0))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.starts_with(&folder.0.join("test-svc.toml").display().to_string()),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn selects_the_configuration_folder() {
+        let _env = env_lock();
+        let previous = CONFIG_DIR.write().unwrap().take();
+        std::env::remove_var(CONFIG_DIR_ENV);
+        assert_eq!(config_dir(), PathBuf::from(".config"));
+
+        std::env::set_var(CONFIG_DIR_ENV, "/etc/myapp");
+        assert_eq!(config_dir(), PathBuf::from("/etc/myapp"));
+
+        // `--config-dir` wins over the variable
+        set_config_dir("conf");
+        assert_eq!(config_dir(), PathBuf::from("conf"));
+
+        std::env::remove_var(CONFIG_DIR_ENV);
+        *CONFIG_DIR.write().unwrap() = previous;
+    }
+
+    #[test]
+    fn loads_from_the_configuration_folder() {
+        let folder = layered("folder");
+        let _config_dir = TestConfigDir::new(&folder.0);
+        let config = ServiceConfig::from_folder(&Environment::Test, "svc", None).unwrap();
+        assert_eq!(config.port, 4000);
+    }
+}
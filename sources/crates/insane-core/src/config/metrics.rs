@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+/// Prometheus metrics configuration, see [`crate::metrics`].
+///
+/// The metrics are served on `/_metrics` by the routes of the HTTP server, or
+/// on their own port when `port` is set.
+///
+/// Example:
+/// ```yaml
+/// metrics:
+///   enable: true
+///   port: 9100
+/// ```
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct MetricsConfig {
+    #[serde(default)]
+    pub enable: bool,
+
+    /// Serve the metrics on this port, with the binding of the HTTP server,
+    /// instead of the port of the HTTP server.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+}
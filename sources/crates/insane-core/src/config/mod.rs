@@ -1,19 +1,43 @@
+pub mod cache;
+pub mod control;
+pub mod keys;
 pub mod loader;
-pub mod trace;
+pub mod metrics;
+#[cfg(feature = "with-otel")]
+pub mod otel;
+#[cfg(feature = "with-redis")]
+pub mod redis;
+pub mod reload;
+pub mod secret;
+pub mod servers;
 pub mod sql;
+pub mod storage;
+pub mod template;
+pub mod trace;
 
 use serde::{Deserialize, Serialize};
 
+pub use cache::CacheConfig;
+pub use control::ControlConfig;
+pub use metrics::MetricsConfig;
+#[cfg(feature = "with-otel")]
+pub use otel::{OtelBatchConfig, OtelConfig, OtelProtocol};
+#[cfg(feature = "with-redis")]
+pub use redis::RedisConfig;
+pub use reload::ReloadConfig;
+pub use secret::Secret;
 #[cfg(feature = "with-sql")]
 pub use sql::SqlConfig;
+pub use storage::StorageConfig;
 
 use self::loader::{Config, ConfigLoader};
+use crate::environment::Environment;
 use crate::{
     error::{Error, Result},
     hook::Hooks,
 };
-use crate::environment::Environment;
-pub use trace::TraceConfig;
+pub use servers::{ServersConfig, SupervisionConfig, SupervisionPolicy};
+pub use trace::{LogFileConfig, LogRotation, TraceConfig};
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct InsaneConfig {
@@ -21,16 +45,38 @@ pub struct InsaneConfig {
     #[cfg(feature = "with-sql")]
     pub sql: SqlConfig,
 
+    /// Redis connection, not connected when missing.
+    #[cfg(feature = "with-redis")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redis: Option<RedisConfig>,
+
+    #[serde(default)]
+    pub cache: CacheConfig,
+
+    #[serde(default)]
+    pub storage: StorageConfig,
+
     pub tracing: TraceConfig,
+
+    pub servers: ServersConfig,
+
+    #[serde(default)]
+    pub reload: ReloadConfig,
+
+    #[serde(default)]
+    pub control: ControlConfig,
+
+    #[serde(default)]
+    pub metrics: MetricsConfig,
 }
 
 impl InsaneConfig {
     pub fn new(env: &Environment, app_name: &str) -> Result<Self> {
-        Ok(InsaneConfig::from_folder(env, app_name, None)?)
+        InsaneConfig::from_folder(env, app_name, None)
     }
 
     pub fn load_from_env<H: Hooks>(environment: &Environment) -> Result<Self> {
-        InsaneConfig::new(&environment, H::app_name())
+        InsaneConfig::new(environment, H::app_name())
     }
 }
 
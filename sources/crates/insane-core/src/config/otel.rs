@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// OpenTelemetry export of the spans, and optionally of the metrics, to an
+/// OTLP collector. The service name and version are the ones of the
+/// application hooks.
+///
+/// Example:
+/// ```yaml
+/// tracing:
+///   otel:
+///     protocol: grpc
+///     endpoint: http://localhost:4317
+///     filter: info
+///     sampling_ratio: 0.25
+///     batch:
+///       scheduled_delay: 5000
+///     metrics: true
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OtelConfig {
+    #[serde(default)]
+    pub protocol: OtelProtocol,
+
+    /// Address of the collector, `http://localhost:4317` for `grpc` and
+    /// `http://localhost:4318` for `http` when not set. The `http` protocol
+    /// appends `/v1/traces` and `/v1/metrics` to it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+
+    /// Timeout in milliseconds of an export.
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+
+    /// Filter of the exported spans, the terminal `filter` when not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+
+    /// Ratio of the traces sampled, from 0 to 1. The traces started by a
+    /// caller follow the sampling decision of the caller.
+    #[serde(default = "default_sampling_ratio")]
+    pub sampling_ratio: f64,
+
+    #[serde(default)]
+    pub batch: OtelBatchConfig,
+
+    /// Export the metrics recorded through the OpenTelemetry global meter
+    /// provider as well.
+    #[serde(default)]
+    pub metrics: bool,
+
+    /// Interval in milliseconds between two exports of the metrics.
+    #[serde(default = "default_metrics_interval")]
+    pub metrics_interval: u64,
+}
+
+impl OtelConfig {
+    #[must_use]
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout)
+    }
+
+    #[must_use]
+    pub fn metrics_interval(&self) -> Duration {
+        Duration::from_millis(self.metrics_interval)
+    }
+}
+
+/// Transport of the OTLP export.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OtelProtocol {
+    /// OTLP over gRPC.
+    #[default]
+    Grpc,
+    /// OTLP over HTTP, protobuf encoded.
+    Http,
+}
+
+/// Batching of the exported spans.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OtelBatchConfig {
+    /// Maximum number of spans waiting to be exported, the spans ended while
+    /// the queue is full are dropped.
+    #[serde(default = "default_max_queue_size")]
+    pub max_queue_size: usize,
+
+    /// Maximum number of spans of an export.
+    #[serde(default = "default_max_export_batch_size")]
+    pub max_export_batch_size: usize,
+
+    /// Delay in milliseconds between two exports.
+    #[serde(default = "default_scheduled_delay")]
+    pub scheduled_delay: u64,
+}
+
+impl OtelBatchConfig {
+    #[must_use]
+    pub fn scheduled_delay(&self) -> Duration {
+        Duration::from_millis(self.scheduled_delay)
+    }
+}
+
+impl Default for OtelBatchConfig {
+    fn default() -> Self {
+        Self {
+            max_queue_size: default_max_queue_size(),
+            max_export_batch_size: default_max_export_batch_size(),
+            scheduled_delay: default_scheduled_delay(),
+        }
+    }
+}
+
+fn default_timeout() -> u64 {
+    10_000
+}
+
+fn default_sampling_ratio() -> f64 {
+    1.0
+}
+
+fn default_metrics_interval() -> u64 {
+    60_000
+}
+
+fn default_max_queue_size() -> usize {
+    2048
+}
+
+fn default_max_export_batch_size() -> usize {
+    512
+}
+
+fn default_scheduled_delay() -> u64 {
+    5000
+}
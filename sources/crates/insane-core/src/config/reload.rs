@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+fn default_debounce() -> u64 {
+    500
+}
+
+/// Live reload configuration, see [`crate::reload`].
+///
+/// The configuration is always reloaded on `SIGHUP`, `watch` also reloads it
+/// when a file of the configuration folder changes.
+///
+/// Example:
+/// ```yaml
+/// reload:
+///   watch: true
+///   debounce: 500
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReloadConfig {
+    /// Watch the configuration folder for changes.
+    #[serde(default)]
+    pub watch: bool,
+
+    /// Time in milliseconds to wait for more changes before reloading, editors
+    /// often write a file in several steps.
+    #[serde(default = "default_debounce")]
+    pub debounce: u64,
+}
+
+impl ReloadConfig {
+    #[must_use]
+    pub fn debounce(&self) -> Duration {
+        Duration::from_millis(self.debounce)
+    }
+}
+
+impl Default for ReloadConfig {
+    fn default() -> Self {
+        Self {
+            watch: false,
+            debounce: default_debounce(),
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+fn default_shutdown_timeout() -> u64 {
+    30_000
+}
+
+fn default_initial_backoff() -> u64 {
+    500
+}
+
+fn default_max_backoff() -> u64 {
+    30_000
+}
+
+/// Servers configuration
+///
+/// Controls how the servers returned by [`crate::hook::Hooks::servers`] are
+/// run by [`crate::boot_loader::boot_app`].
+///
+/// Example:
+/// ```yaml
+/// servers:
+///   shutdown_timeout: 30000
+///   supervision:
+///     policy: restart
+///     max_restarts: 5
+///   overrides:
+///     http_server:
+///       policy: fail_fast
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ServersConfig {
+    /// Time in milliseconds given to the servers to drain the in-flight work
+    /// once a shutdown signal is received. Servers still running after that
+    /// are aborted.
+    #[serde(default = "default_shutdown_timeout")]
+    pub shutdown_timeout: u64,
+
+    /// Supervision applied to every server without an entry in `overrides`.
+    #[serde(default)]
+    pub supervision: SupervisionConfig,
+
+    /// Supervision per server, keyed by [`crate::server::Server::name`].
+    #[serde(default)]
+    pub overrides: BTreeMap<String, SupervisionConfig>,
+}
+
+impl ServersConfig {
+    /// Get the supervision configuration of the given server.
+    #[must_use]
+    pub fn supervision_for(&self, server: &str) -> &SupervisionConfig {
+        self.overrides.get(server).unwrap_or(&self.supervision)
+    }
+}
+
+impl Default for ServersConfig {
+    fn default() -> Self {
+        Self {
+            shutdown_timeout: default_shutdown_timeout(),
+            supervision: SupervisionConfig::default(),
+            overrides: BTreeMap::new(),
+        }
+    }
+}
+
+/// What to do when a server returns an error or panics.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SupervisionPolicy {
+    /// Stop every server and exit the process with an error.
+    FailFast,
+    /// Start the server again after an exponential backoff.
+    Restart,
+    /// Log the failure and keep the other servers running.
+    #[default]
+    Ignore,
+}
+
+/// Supervision configuration of a server.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SupervisionConfig {
+    #[serde(default)]
+    pub policy: SupervisionPolicy,
+
+    /// Maximum number of consecutive restarts before failing the process. No
+    /// limit when not set. Only used by the `restart` policy.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_restarts: Option<u32>,
+
+    /// Delay in milliseconds before the first restart. Doubled after each
+    /// consecutive failure.
+    #[serde(default = "default_initial_backoff")]
+    pub initial_backoff: u64,
+
+    /// Upper bound in milliseconds of the restart delay. A server that ran
+    /// longer than this before failing starts again from `initial_backoff`.
+    #[serde(default = "default_max_backoff")]
+    pub max_backoff: u64,
+}
+
+impl SupervisionConfig {
+    #[must_use]
+    pub fn initial_backoff(&self) -> Duration {
+        Duration::from_millis(self.initial_backoff)
+    }
+
+    #[must_use]
+    pub fn max_backoff(&self) -> Duration {
+        Duration::from_millis(self.max_backoff)
+    }
+}
+
+impl Default for SupervisionConfig {
+    fn default() -> Self {
+        Self {
+            policy: SupervisionPolicy::default(),
+            max_restarts: None,
+            initial_backoff: default_initial_backoff(),
+            max_backoff: default_max_backoff(),
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "with-s3")]
+use super::Secret;
+
+fn default_root() -> PathBuf {
+    PathBuf::from("storage")
+}
+
+/// Storage configuration
+///
+/// Selects the driver of [`crate::context::Context::storage`]. Defaults to
+/// the local `storage` folder when the section is missing.
+///
+/// Example:
+/// ```yaml
+/// # development
+/// storage:
+///   driver: local
+///   root: ./storage
+///
+/// # test
+/// storage:
+///   driver: memory
+///
+/// # production, requires the `with-s3` feature
+/// storage:
+///   driver: s3
+///   bucket: myapp-uploads
+///   region: eu-west-1
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "driver", rename_all = "snake_case")]
+pub enum StorageConfig {
+    /// Files stored under a folder of the local disk.
+    Local {
+        #[serde(default = "default_root")]
+        root: PathBuf,
+    },
+
+    /// Files kept in memory, lost on restart.
+    Memory,
+
+    /// Files stored in an S3-compatible bucket.
+    #[cfg(feature = "with-s3")]
+    S3(S3Config),
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self::Local {
+            root: default_root(),
+        }
+    }
+}
+
+#[cfg(feature = "with-s3")]
+fn default_region() -> String {
+    "us-east-1".to_string()
+}
+
+/// S3 driver configuration
+///
+/// The credentials not set here are read from the standard `AWS_*`
+/// environment variables. For MinIO or another S3-compatible server, set the
+/// `endpoint` and `path_style`:
+///
+/// ```yaml
+/// storage:
+///   driver: s3
+///   bucket: uploads
+///   endpoint: http://127.0.0.1:9000
+///   path_style: true
+///   access_key_id: minioadmin
+///   secret_access_key: minioadmin
+/// ```
+#[cfg(feature = "with-s3")]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct S3Config {
+    pub bucket: String,
+
+    #[serde(default = "default_region")]
+    pub region: String,
+
+    /// Endpoint of an S3-compatible server, AWS when missing. `http://`
+    /// endpoints are allowed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub access_key_id: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret_access_key: Option<Secret>,
+
+    /// Address the bucket in the path (`endpoint/bucket/key`) instead of the
+    /// host name, as required by most S3-compatible servers.
+    #[serde(default)]
+    pub path_style: bool,
+
+    /// Folder of the bucket the files are stored under.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+}
@@ -0,0 +1,247 @@
+//! Rendering of the configuration files before they are parsed.
+//!
+//! Every configuration file, whatever its format, is a [minijinja](https://docs.rs/minijinja)
+//! template rendered with:
+//! * `get_env(name="VAR", default="...")`: the value of an environment
+//!   variable, or `default` when it is not set. Rendering fails when the
+//!   variable is not set and there is no default.
+//! * `environment` and `app_name`: the environment and the application the
+//!   configuration is loaded for.
+//!
+//! ```yaml
+//! sql:
+//!   uri: {{ get_env(name="DATABASE_URL", default="sqlite://db.sqlite?mode=rwc") }}
+//! {% if environment == "production" %}
+//! tracing:
+//!   format: json
+//! {% endif %}
+//! ```
+
+use std::path::Path;
+
+use config::{Config as ExtConfig, File, FileFormat};
+use minijinja::{context, value::Kwargs, ErrorKind, UndefinedBehavior};
+
+use super::loader::file_format;
+use crate::{
+    environment::Environment,
+    error::{Error, Result},
+};
+
+/// Read and render the configuration file `path`.
+///
+/// # Errors
+/// When the file can't be read, see [`render`].
+pub fn render_file(path: &Path, env: &Environment, app_name: &str) -> Result<String> {
+    let content = std::fs::read_to_string(path)?;
+    render(path, &content, env, app_name)
+}
+
+/// Render the configuration file `path` of `content`.
+///
+/// # Errors
+/// When the template is invalid, or the rendered file is not valid in the
+/// format of its extension. The error points to the file and line at fault,
+/// the line of the rendered file for YAML errors.
+pub fn render(path: &Path, content: &str, env: &Environment, app_name: &str) -> Result<String> {
+    let name = path.display().to_string();
+
+    let mut templates = minijinja::Environment::new();
+    templates.set_undefined_behavior(UndefinedBehavior::Strict);
+    templates.set_keep_trailing_newline(true);
+    templates.add_function("get_env", get_env);
+
+    let rendered = templates
+        .template_from_named_str(&name, content)
+        .and_then(|template| {
+            template.render(context! {
+                environment => env.to_string(),
+                app_name => app_name,
+            })
+        })
+        .map_err(|err| {
+            let detail = err
+                .detail()
+                .map(|detail| format!(": {detail}"))
+                .unwrap_or_default();
+            Error::Message(format!(
+                "{name}:{}: failed to render the configuration, {}{detail}",
+                err.line().unwrap_or_default(),
+                err.kind()
+            ))
+        })?;
+
+    // Parse once here, the `config` crate reports errors without the file.
+    if file_format(path) == FileFormat::Yaml {
+        if let Err(err) = serde_yaml::from_str::<serde_yaml::Value>(&rendered) {
+            let line = err
+                .location()
+                .map(|location| location.line())
+                .unwrap_or_default();
+            return Err(Error::Message(format!(
+                "{name}:{line}: invalid configuration after rendering, {err}"
+            )));
+        }
+    } else if let Err(err) = ExtConfig::builder()
+        .add_source(File::from_str(&rendered, file_format(path)))
+        .build()
+    {
+        return Err(Error::Message(format!(
+            "{name}: invalid configuration after rendering, {err}"
+        )));
+    }
+    Ok(rendered)
+}
+
+/// `get_env(name="VAR", default="...")`, the name can also be passed first.
+fn get_env(name: Option<String>, kwargs: Kwargs) -> std::result::Result<String, minijinja::Error> {
+    let name = match name {
+        Some(name) => name,
+        None => kwargs.get::<String>("name")?,
+    };
+    let default = kwargs.get::<Option<String>>("default")?;
+    kwargs.assert_all_used()?;
+
+    match std::env::var(&name) {
+        Ok(value) => Ok(value),
+        Err(_) => default.ok_or_else(|| {
+            minijinja::Error::new(
+                ErrorKind::InvalidOperation,
+                format!("environment variable `{name}` is not set and has no default"),
+            )
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::loader::env_lock;
+
+    fn render_as(file: &str, content: &str) -> Result<String> {
+        render(Path::new(file), content, &Environment::Production, "myapp")
+    }
+
+    #[test]
+    fn renders_the_environment_and_the_application() {
+        let content = "name: {{ app_name }}\n\
+                       {% if environment == \"production\" %}format: json\n{% endif %}";
+        assert_eq!(
+            render_as("app.yaml", content).unwrap(),
+            "name: myapp\nformat: json\n"
+        );
+    }
+
+    #[test]
+    fn reads_the_environment_variables() {
+        let _env = env_lock();
+        std::env::set_var("TEMPLATE_TEST_URL", "postgres://db");
+        std::env::remove_var("TEMPLATE_TEST_MISSING");
+
+        let rendered = render_as(
+            "app.yaml",
+            "uri: {{ get_env(name=\"TEMPLATE_TEST_URL\") }}\n\
+             positional: {{ get_env(\"TEMPLATE_TEST_URL\", default=\"x\") }}\n\
+             missing: {{ get_env(name=\"TEMPLATE_TEST_MISSING\", default=\"sqlite\") }}\n",
+        );
+        std::env::remove_var("TEMPLATE_TEST_URL");
+
+        assert_eq!(
+            rendered.unwrap(),
+            "uri: postgres://db\npositional: postgres://db\nmissing: sqlite\n"
+        );
+    }
+
+    #[test]
+    fn refuses_a_missing_variable_without_default() {
+        let _env = env_lock();
+        std::env::remove_var("TEMPLATE_TEST_MISSING");
+
+        let err = render_as(
+            "conf/app.yaml",
+            "name: app\nuri: {{ get_env(name=\"TEMPLATE_TEST_MISSING\") }}\n",
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(
+            err.starts_with("conf/app.yaml:2: failed to render the configuration"),
+            "{err}"
+        );
+        assert!(
+            err.contains("`TEMPLATE_TEST_MISSING` is not set and has no default"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn refuses_the_undefined_values() {
+        let err = render_as("app.yaml", "a: 1\nb: 2\nc: {{ port }}\n")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.starts_with("app.yaml:3: failed to render the configuration"),
+            "{err}"
+        );
+        assert!(err.contains("undefined"), "{err}");
+    }
+
+    #[test]
+    fn refuses_the_unknown_arguments() {
+        let err = render_as(
+            "app.yaml",
+            "uri: {{ get_env(name=\"A\", fallback=\"b\") }}\n",
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(
+            err.starts_with("app.yaml:1: failed to render the configuration"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn points_to_the_line_of_the_invalid_yaml() {
+        let err = render_as("app.yaml", "name: app\n{% if true %}port: [\n{% endif %}")
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("app.yaml:"), "{err}");
+        assert!(
+            err.contains("invalid configuration after rendering"),
+            "{err}"
+        );
+
+        let err = render_as("app.yaml", "name: app\nport: 1\n  bad: indent\n")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.starts_with("app.yaml:3: invalid configuration after rendering"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn checks_the_other_formats_after_rendering() {
+        assert_eq!(
+            render(
+                Path::new("app.toml"),
+                "port = {{ 40 + 2 }}\n",
+                &Environment::Test,
+                "a"
+            )
+            .unwrap(),
+            "port = 42\n"
+        );
+        let err = render(
+            Path::new("app.json"),
+            "{\"port\": }",
+            &Environment::Test,
+            "a",
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(
+            err.starts_with("app.json: invalid configuration after rendering"),
+            "{err}"
+        );
+    }
+}
@@ -1,11 +1,41 @@
 use crate::traces::TraceFormat;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
+/// Tracing configuration
+///
+/// `format` and `filter` apply to the terminal output, every entry of `files`
+/// writes the logs to a file as well, with its own format and filter. With
+/// the `with-otel` feature, `otel` exports the spans to a collector, see
+/// [`super::OtelConfig`].
+///
+/// Example:
+/// ```yaml
+/// tracing:
+///   format: pretty
+///   filter: info
+///   pretty_backtrace: false
+///   files:
+///     - path: logs/myapp.log
+///       format: json
+///       filter: debug
+///       rotation: daily
+///       retention: 7
+/// ```
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TraceConfig {
     format: TraceFormat,
     pub filter: String,
     pub pretty_backtrace: bool,
+
+    /// Files the logs are written to, in addition to the terminal.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<LogFileConfig>,
+
+    /// Export of the spans to an OpenTelemetry collector.
+    #[cfg(feature = "with-otel")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub otel: Option<super::OtelConfig>,
 }
 
 impl TraceConfig {
@@ -24,6 +54,58 @@ impl Default for TraceConfig {
             format: Default::default(),
             filter: "info".to_string(),
             pretty_backtrace: false,
+            files: Vec::new(),
+            #[cfg(feature = "with-otel")]
+            otel: None,
         }
     }
-}
\ No newline at end of file
+}
+
+/// When a log file is rotated, the current file is renamed `{path}.1`, the
+/// previous `{path}.1` becomes `{path}.2` and so on.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogRotation {
+    /// Rotate at the first write of each day.
+    #[default]
+    Daily,
+    /// Rotate at the first write of each hour.
+    Hourly,
+    /// Rotate when the file reaches `max_size` bytes.
+    Size,
+    /// Always write to the same file.
+    Never,
+}
+
+/// A file the logs are written to, see [`TraceConfig`].
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LogFileConfig {
+    /// Path of the current log file, its folder is created when missing.
+    pub path: PathBuf,
+
+    #[serde(default)]
+    pub format: TraceFormat,
+
+    /// Filter of the file, the terminal `filter` when not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+
+    #[serde(default)]
+    pub rotation: LogRotation,
+
+    /// Size in bytes rotating the file with the `size` rotation.
+    #[serde(default = "default_max_size")]
+    pub max_size: u64,
+
+    /// Number of rotated files kept, the older ones are deleted.
+    #[serde(default = "default_retention")]
+    pub retention: usize,
+}
+
+fn default_max_size() -> u64 {
+    10 * 1024 * 1024
+}
+
+fn default_retention() -> usize {
+    7
+}
@@ -1,8 +1,16 @@
-use crate::{config::InsaneConfig, environment::Environment};
+use std::sync::Arc;
+
+use crate::{
+    cache::Cache, config::InsaneConfig, environment::Environment, extensions::Extensions,
+    reload::ConfigReloader, storage::Storage, traces::LogFilter,
+};
 
 #[cfg(feature = "with-sql")]
 use sea_orm::DatabaseConnection;
 
+#[cfg(feature = "with-redis")]
+use crate::redis::RedisPool;
+
 #[async_trait::async_trait]
 pub trait Context: Sync + Send {
     /// Get the environment of the context.
@@ -14,6 +22,27 @@ pub trait Context: Sync + Send {
     /// Get the configuration of the context.
     #[cfg(feature = "with-sql")]
     fn sql(&self) -> &DatabaseConnection;
+
+    /// Get the Redis pool, when `redis` is configured.
+    #[cfg(feature = "with-redis")]
+    fn redis(&self) -> Option<&RedisPool>;
+
+    /// Get the cache selected by the `cache` configuration.
+    fn cache(&self) -> &Arc<dyn Cache>;
+
+    /// Get the storage selected by the `storage` configuration.
+    fn storage(&self) -> &Arc<dyn Storage>;
+
+    /// Get the application-owned services registered on the context.
+    fn extensions(&self) -> &Extensions;
+
+    /// Get the reloader publishing the configuration changes, see
+    /// [`crate::reload`].
+    fn reloader(&self) -> &Arc<ConfigReloader>;
+
+    /// The filters of the logs, `None` when the application installed its own
+    /// subscriber.
+    fn log_filter(&self) -> Option<&Arc<LogFilter>>;
 }
 
 // pub trait ServerContext<T> {
@@ -29,9 +58,23 @@ pub struct DefaultContext {
     /// A database connection used by the application.    
     pub sql: DatabaseConnection,
 
-    // #[cfg(feature = "with-redis")]
-    // /// An optional connection pool for Redis, for worker tasks
-    // pub redis: Option<Pool<RedisConnectionManager>>,
+    /// The cache of the application, see [`crate::cache`].
+    pub cache: Arc<dyn Cache>,
+
+    /// The file storage of the application, see [`crate::storage`].
+    pub storage: Arc<dyn Storage>,
+
+    /// Services registered by the application, see [`Extensions`].
+    pub extensions: Extensions,
+
+    /// The reloader of the configuration, see [`crate::reload`].
+    pub reloader: Arc<ConfigReloader>,
+
+    pub log_filter: Option<Arc<LogFilter>>,
+
+    #[cfg(feature = "with-redis")]
+    /// A connection pool for Redis, when configured.
+    pub redis: Option<RedisPool>,
 }
 
 #[async_trait::async_trait]
@@ -48,4 +91,70 @@ impl Context for DefaultContext {
     fn sql(&self) -> &DatabaseConnection {
         &self.sql
     }
+
+    #[cfg(feature = "with-redis")]
+    fn redis(&self) -> Option<&RedisPool> {
+        self.redis.as_ref()
+    }
+
+    fn cache(&self) -> &Arc<dyn Cache> {
+        &self.cache
+    }
+
+    fn storage(&self) -> &Arc<dyn Storage> {
+        &self.storage
+    }
+
+    fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    fn reloader(&self) -> &Arc<ConfigReloader> {
+        &self.reloader
+    }
+
+    fn log_filter(&self) -> Option<&Arc<LogFilter>> {
+        self.log_filter.as_ref()
+    }
+}
+
+/// A context without database nor Redis, with the default cache and storage,
+/// for the tests of the servers and initializers. Available to the other
+/// crates with the `testing` feature.
+///
+/// # Panics
+/// See [`test_default_context`].
+#[cfg(any(test, feature = "testing"))]
+#[must_use]
+pub fn test_context(config: InsaneConfig) -> Arc<Box<dyn Context>> {
+    Arc::new(Box::new(test_default_context(config)))
+}
+
+/// The context of [`test_context`], for the tests changing some of its
+/// services.
+///
+/// # Panics
+/// When the configuration cannot be watched for reloads.
+#[cfg(any(test, feature = "testing"))]
+#[must_use]
+pub fn test_default_context(config: InsaneConfig) -> DefaultContext {
+    let environment = Environment::Test;
+    DefaultContext {
+        environment: environment.clone(),
+        #[cfg(feature = "with-sql")]
+        sql: DatabaseConnection::Disconnected,
+        cache: crate::cache::create(
+            &config.cache,
+            #[cfg(feature = "with-redis")]
+            None,
+        )
+        .unwrap(),
+        storage: crate::storage::create(&config.storage).unwrap(),
+        extensions: Extensions::default(),
+        reloader: Arc::new(ConfigReloader::new(&environment, &config).unwrap()),
+        log_filter: None,
+        #[cfg(feature = "with-redis")]
+        redis: None,
+        config,
+    }
 }
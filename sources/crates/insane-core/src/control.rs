@@ -0,0 +1,296 @@
+//! Local control socket.
+//!
+//! With `control.socket`, the application listens on a Unix socket only
+//! readable by its user, and answers one JSON line for every command line:
+//!
+//! * `log-level`: the filters in use, see [`LogFilterStatus`].
+//! * `log-level set <directives> [ttl]`: add filter directives, like
+//!   `insane_http=debug`, reset after `ttl` seconds when given.
+//! * `log-level reset`: back to the configured filters.
+//!
+//! Failed commands answer `{"error": "..."}`. The `log-level` command of the
+//! CLI sends them with [`send`], or by hand:
+//!
+//! ```sh
+//! echo 'log-level set insane_http=debug 600' | socat - UNIX-CONNECT:/run/myapp/control.sock
+//! ```
+//!
+//! [`LogFilterStatus`]: crate::traces::LogFilterStatus
+
+use std::{path::Path, sync::Arc, time::Duration};
+
+use crate::{
+    context::Context,
+    error::{Error, Result},
+    shutdown::ShutdownToken,
+};
+
+/// Answer the commands sent on `control.socket` until `shutdown` is
+/// triggered. Does nothing when no socket is configured.
+pub async fn serve(context: Arc<Box<dyn Context>>, shutdown: ShutdownToken) {
+    let Some(path) = context.config().control.socket.clone() else {
+        return;
+    };
+
+    if let Err(err) = listen(&context, &path, &shutdown).await {
+        tracing::error!(err.msg = %err, socket = %path.display(), "control socket failed");
+    }
+}
+
+#[cfg(unix)]
+async fn listen(
+    context: &Arc<Box<dyn Context>>,
+    path: &Path,
+    shutdown: &ShutdownToken,
+) -> Result<()> {
+    let listener = bind(path)?;
+    tracing::info!(socket = %path.display(), "control socket listening");
+
+    loop {
+        tokio::select! {
+            () = shutdown.triggered() => break,
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => {
+                    tokio::spawn(answer(context.clone(), stream));
+                }
+                Err(err) => tracing::error!(err.msg = %err, "failed to accept a control connection"),
+            },
+        }
+    }
+
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+/// Bind the socket at `path`, only accessible by the user of the process.
+///
+/// The socket is bound in a private folder next to `path`, then moved in
+/// place once its permissions are restricted, so that it is never reachable
+/// by the other users. A socket left by a previous run that didn't stop
+/// cleanly is replaced, any other file is kept and refused.
+#[cfg(unix)]
+fn bind(path: &Path) -> Result<tokio::net::UnixListener> {
+    use std::{
+        fs::{DirBuilder, Permissions},
+        os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt},
+    };
+
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path)?,
+        Ok(_) => {
+            return Err(Error::Message(format!(
+                "`{}` already exists and is not a socket",
+                path.display()
+            )))
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err.into()),
+    }
+
+    let name = path
+        .file_name()
+        .ok_or_else(|| Error::Message(format!("invalid socket path `{}`", path.display())))?;
+    let private = path.with_file_name(format!(
+        ".{}.{}",
+        name.to_string_lossy(),
+        std::process::id()
+    ));
+    DirBuilder::new().mode(0o700).create(&private)?;
+
+    let bound = (|| -> Result<tokio::net::UnixListener> {
+        let socket = private.join(name);
+        let listener = tokio::net::UnixListener::bind(&socket)?;
+        std::fs::set_permissions(&socket, Permissions::from_mode(0o600))?;
+        std::fs::rename(&socket, path)?;
+        Ok(listener)
+    })();
+    std::fs::remove_dir_all(&private)?;
+    bound
+}
+
+#[cfg(not(unix))]
+async fn listen(
+    _context: &Arc<Box<dyn Context>>,
+    _path: &Path,
+    _shutdown: &ShutdownToken,
+) -> Result<()> {
+    Err(Error::string("control sockets are only available on Unix"))
+}
+
+/// Answer every line of `stream`.
+#[cfg(unix)]
+async fn answer(context: Arc<Box<dyn Context>>, stream: tokio::net::UnixStream) {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match execute(context.as_ref().as_ref(), &line) {
+            Ok(response) => response,
+            Err(err) => serde_json::json!({ "error": err.to_string() }),
+        };
+        if writer
+            .write_all(format!("{response}\n").as_bytes())
+            .await
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+fn execute(context: &dyn Context, line: &str) -> Result<serde_json::Value> {
+    let words = line.split_whitespace().collect::<Vec<_>>();
+    let Some((&"log-level", args)) = words.split_first() else {
+        return Err(Error::Message(format!("unknown command `{line}`")));
+    };
+    let log_filter = context
+        .log_filter()
+        .ok_or_else(|| Error::string("the logs are not filtered by insane"))?;
+
+    let status = match args {
+        [] => log_filter.status(),
+        ["set", directives] => log_filter.set(directives, None)?,
+        ["set", directives, ttl] => {
+            let ttl = ttl
+                .parse()
+                .map_err(|_| Error::Message(format!("invalid ttl `{ttl}`, expected seconds")))?;
+            log_filter.set(directives, Some(Duration::from_secs(ttl)))?
+        }
+        ["reset"] => log_filter.reset()?,
+        _ => return Err(Error::Message(format!("invalid command `{line}`"))),
+    };
+    serde_json::to_value(status).map_err(Error::JSON)
+}
+
+/// Send `command` to the control socket at `path` and return the answer.
+///
+/// # Errors
+/// When the socket can't be reached.
+#[cfg(unix)]
+pub async fn send(path: &Path, command: &str) -> Result<String> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::UnixStream;
+
+    let stream = UnixStream::connect(path).await.map_err(|err| {
+        Error::Message(format!(
+            "failed to connect to the control socket `{}`: {err}",
+            path.display()
+        ))
+    })?;
+    let (reader, mut writer) = stream.into_split();
+    writer.write_all(format!("{command}\n").as_bytes()).await?;
+
+    let mut answer = String::new();
+    BufReader::new(reader).read_line(&mut answer).await?;
+    Ok(answer.trim_end().to_string())
+}
+
+/// Send `command` to the control socket at `path` and return the answer.
+///
+/// # Errors
+/// Always, control sockets are only available on Unix.
+#[cfg(not(unix))]
+pub async fn send(_path: &Path, _command: &str) -> Result<String> {
+    Err(Error::string("control sockets are only available on Unix"))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{os::unix::fs::PermissionsExt, path::PathBuf};
+
+    use super::*;
+    use crate::{config::InsaneConfig, context::test_default_context, traces::LogFilter};
+
+    fn socket_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("insane-control-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn answers_the_commands() {
+        let (log_filter, _subscriber) = LogFilter::for_tests("info");
+        let dir = socket_dir("commands");
+        let path = dir.join("control.sock");
+        let mut config = InsaneConfig::default();
+        config.control.socket = Some(path.clone());
+        let mut context = test_default_context(config);
+        context.log_filter = Some(log_filter);
+        let context: Arc<Box<dyn Context>> = Arc::new(Box::new(context));
+
+        let shutdown = ShutdownToken::new();
+        let served = tokio::spawn(serve(context, shutdown.clone()));
+        while !path.exists() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let answer = |command: &'static str| {
+            let path = path.clone();
+            async move {
+                serde_json::from_str::<serde_json::Value>(&send(&path, command).await.unwrap())
+                    .unwrap()
+            }
+        };
+        assert_eq!(
+            answer("log-level set insane_http=debug 600").await["overrides"],
+            "insane_http=debug"
+        );
+        assert_eq!(answer("log-level").await["overrides"], "insane_http=debug");
+        assert_eq!(
+            answer("log-level reset").await["overrides"],
+            serde_json::Value::Null
+        );
+        assert_eq!(
+            answer("log-level set insane_http=debug soon").await["error"],
+            "invalid ttl `soon`, expected seconds"
+        );
+        assert_eq!(
+            answer("log-level up").await["error"],
+            "invalid command `log-level up`"
+        );
+        assert_eq!(
+            answer("restart").await["error"],
+            "unknown command `restart`"
+        );
+
+        shutdown.trigger();
+        served.await.unwrap();
+        assert!(!path.exists());
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn replaces_a_stale_socket() {
+        let dir = socket_dir("stale");
+        let path = dir.join("control.sock");
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+
+        drop(bind(&path).unwrap());
+        assert!(path.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn keeps_the_files_that_are_not_sockets() {
+        let dir = socket_dir("file");
+        let path = dir.join("control.sock");
+        std::fs::write(&path, "data").unwrap();
+
+        let err = bind(&path).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("`{}` already exists and is not a socket", path.display())
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "data");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -7,11 +7,11 @@
 //!
 //! ```rust
 //! use std::str::FromStr;
-//! use loco_rs::environment::Environment;
+//! use insane_core::{config::InsaneConfig, environment::Environment};
 //!
 //! pub fn load(environment: &str) {
 //!  let environment = Environment::from_str(environment).unwrap_or(Environment::Any(environment.to_string()));
-//!  let config = environment.load().expect("failed to load environment");
+//!  let config = InsaneConfig::new(&environment, "myapp").expect("failed to load environment");
 //! }
 //! ```
 use std::str::FromStr;
@@ -46,6 +46,15 @@ pub enum Environment {
     Any(String),
 }
 
+impl Environment {
+    /// `true` for [`Environment::Production`], where the destructive
+    /// operations are refused unless explicitly allowed.
+    #[must_use]
+    pub fn is_production(&self) -> bool {
+        matches!(self, Self::Production)
+    }
+}
+
 // impl Environment {
 //     /// Load environment variables from local configuration
 //     ///
@@ -1,70 +0,0 @@
-//! # Application Error Handling
-
-/// Application results options list
-pub type Result<T, E = Error> = std::result::Result<T, E>;
-
-#[derive(thiserror::Error, Debug)]
-pub enum Error {
-    #[error("{inner}\n{backtrace}")]
-    WithBacktrace {
-        inner: Box<Self>,
-        backtrace: Box<std::backtrace::Backtrace>,
-    },
-
-    #[error("{0}")]
-    Message(String),
-
-    #[error(transparent)]
-    JSON(serde_json::Error),
-
-    #[error(transparent)]
-    EnvVar(#[from] std::env::VarError),
-
-    #[error(transparent)]
-    IO(#[from] std::io::Error),
-
-    #[cfg(feature = "with-sql")]
-    #[error(transparent)]
-    DB(#[from] sea_orm::DbErr),
-
-    #[error(transparent)]
-    Any(#[from] Box<dyn std::error::Error + Send + Sync>),
-
-    #[error(transparent)]
-    Anyhow(#[from] eyre::Report),
-
-    #[error(transparent)]
-    YAML(#[from] serde_yaml::Error),
-
-    #[error(transparent)]
-    ConfigError(#[from] config::ConfigError),
-
-    #[error(transparent)]
-    Trace(#[from] tracing_subscriber::filter::ParseError),
-}
-
-impl Error {
-    pub fn wrap(err: impl std::error::Error + Send + Sync + 'static) -> Self {
-        Self::Any(Box::new(err)) //.bt()
-    }
-
-    pub fn msg(err: impl std::error::Error + Send + Sync + 'static) -> Self {
-        Self::Message(err.to_string()) //.bt()
-    }
-    #[must_use]
-    pub fn string(s: &str) -> Self {
-        Self::Message(s.to_string())
-    }
-    #[must_use]
-    pub fn bt(self) -> Self {
-        let backtrace = std::backtrace::Backtrace::capture();
-        match backtrace.status() {
-            std::backtrace::BacktraceStatus::Disabled
-            | std::backtrace::BacktraceStatus::Unsupported => self,
-            _ => Self::WithBacktrace {
-                inner: Box::new(self),
-                backtrace: Box::new(backtrace),
-            },
-        }
-    }
-}
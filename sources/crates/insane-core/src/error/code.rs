@@ -0,0 +1,88 @@
+use std::fmt;
+
+/// A stable, machine-readable code of an [`super::Error`], with the HTTP
+/// status the HTTP server answers and the exit code the CLI exits with.
+///
+/// The codes of the framework are the constants of this type, applications
+/// declare their own the same way:
+///
+/// ```rust
+/// use insane_core::error::{Error, ErrorCode};
+///
+/// const PLAN_EXPIRED: ErrorCode = ErrorCode::new("plan_expired", 402, 1);
+///
+/// let err = Error::new(PLAN_EXPIRED, "the plan expired on 2024-01-01");
+/// assert_eq!(err.code().as_str(), "plan_expired");
+/// assert_eq!(err.code().status(), 402);
+/// ```
+///
+/// The exit codes follow `sysexits.h`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ErrorCode {
+    code: &'static str,
+    status: u16,
+    exit_code: i32,
+}
+
+impl ErrorCode {
+    /// Invalid input, like a malformed request or command line.
+    pub const BAD_REQUEST: Self = Self::new("bad_request", 400, 64);
+    /// Missing or invalid credentials.
+    pub const UNAUTHORIZED: Self = Self::new("unauthorized", 401, 77);
+    /// Valid credentials, not allowed to do this.
+    pub const FORBIDDEN: Self = Self::new("forbidden", 403, 77);
+    /// The record, file or resource doesn't exist.
+    pub const NOT_FOUND: Self = Self::new("not_found", 404, 66);
+    /// The resource doesn't answer this method.
+    pub const METHOD_NOT_ALLOWED: Self = Self::new("method_not_allowed", 405, 64);
+    /// Conflicts with the current state, like a unique constraint.
+    pub const CONFLICT: Self = Self::new("conflict", 409, 65);
+    /// Well formed input failing the validation rules.
+    pub const VALIDATION: Self = Self::new("validation", 422, 65);
+    /// A dependency, like the database, can't be reached.
+    pub const UNAVAILABLE: Self = Self::new("unavailable", 503, 69);
+    /// An operation took too long.
+    pub const TIMEOUT: Self = Self::new("timeout", 504, 75);
+    /// Invalid or missing configuration.
+    pub const CONFIG: Self = Self::new("config", 500, 78);
+    /// Failed to read or write a file.
+    pub const IO: Self = Self::new("io", 500, 74);
+    /// Failed database query.
+    pub const DATABASE: Self = Self::new("database", 500, 70);
+    /// Anything else.
+    pub const INTERNAL: Self = Self::new("internal_server_error", 500, 70);
+
+    /// A code answered with the HTTP `status` and exiting the CLI with
+    /// `exit_code`.
+    #[must_use]
+    pub const fn new(code: &'static str, status: u16, exit_code: i32) -> Self {
+        Self {
+            code,
+            status,
+            exit_code,
+        }
+    }
+
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        self.code
+    }
+
+    /// The HTTP status of the errors with this code.
+    #[must_use]
+    pub const fn status(&self) -> u16 {
+        self.status
+    }
+
+    /// The exit code of the CLI failing with this code.
+    #[must_use]
+    pub const fn exit_code(&self) -> i32 {
+        self.exit_code
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code)
+    }
+}
@@ -0,0 +1,141 @@
+use std::sync::Arc;
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use colored::Colorize;
+use serde::Serialize;
+
+use super::{Error, ErrorCode, FieldErrors};
+
+impl IntoResponse for Error {
+    /// Convert an `Error` into an HTTP response, with the status of its
+    /// [`ErrorCode`]. The server errors don't tell the client more than their
+    /// code, their details are logged.
+    ///
+    /// The error is kept in the extensions of the response as an
+    /// `Arc<Error>`.
+    fn into_response(self) -> Response {
+        match &self {
+            Self::WithBacktrace {
+                inner,
+                backtrace: _,
+            } => {
+                tracing::error!(
+                error.msg = %inner,
+                error.details = ?inner,
+                "controller_error"
+                );
+            }
+            err => {
+                tracing::error!(
+                error.msg = %err,
+                error.details = ?err,
+                "controller_error"
+                );
+            }
+        }
+
+        if let Self::Unauthorized(err) = &self {
+            tracing::warn!(err);
+        }
+
+        let mut response = match &self {
+            Self::CustomError(status_code, data) => (*status_code, Json(data)).into_response(),
+            err => {
+                if let Self::WithBacktrace { inner, backtrace } = err {
+                    println!("\n{}", inner.to_string().red().underline());
+                    crate::backtrace::print_backtrace(backtrace).unwrap();
+                }
+                let mut detail = ErrorDetail::new(err.code().as_str(), err.detail().as_str());
+                detail.errors = err.field_errors().cloned();
+                (err.status(), Json(detail)).into_response()
+            }
+        };
+
+        // for the renderers of the HTTP server, to render it again
+        response.extensions_mut().insert(Arc::new(self));
+        response
+    }
+}
+
+impl Error {
+    /// The HTTP status of the error, from its [`ErrorCode`].
+    #[must_use]
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::WithBacktrace { inner, .. } => inner.status(),
+            Self::CustomError(status, _) => *status,
+            err => StatusCode::from_u16(err.code().status())
+                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    }
+
+    /// The description of the error the client can see: the message of the
+    /// client errors built with one, the reason of the status otherwise.
+    #[must_use]
+    pub fn detail(&self) -> String {
+        match self {
+            Self::WithBacktrace { inner, .. } => inner.detail(),
+            Self::NotFound => "Resource was not found".to_string(),
+            Self::Unauthorized(_) => {
+                "You do not have permission to access this resource".to_string()
+            }
+            Self::CustomError(
+                _,
+                ErrorDetail {
+                    description: Some(description),
+                    ..
+                },
+            ) => description.clone(),
+            Self::JsonRejection(rejection) => rejection.body_text(),
+            Self::Validation(_) => self.to_string(),
+            Self::BadRequest(message) | Self::WithCode { message, .. }
+                if self.status().is_client_error() =>
+            {
+                message.clone()
+            }
+            _ => self
+                .status()
+                .canonical_reason()
+                .unwrap_or(ErrorCode::INTERNAL.as_str())
+                .to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// Structure representing details about an error.
+pub struct ErrorDetail {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The errors of the fields of a [`Error::Validation`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<FieldErrors>,
+}
+
+impl ErrorDetail {
+    /// Create a new `ErrorDetail` with the specified error and description.
+    #[must_use]
+    pub fn new<T: Into<String>>(error: T, description: T) -> Self {
+        Self {
+            error: Some(error.into()),
+            description: Some(description.into()),
+            errors: None,
+        }
+    }
+
+    /// Create an `ErrorDetail` with only an error reason and no description.
+    #[must_use]
+    pub fn with_reason<T: Into<String>>(error: T) -> Self {
+        Self {
+            error: Some(error.into()),
+            description: None,
+            errors: None,
+        }
+    }
+}
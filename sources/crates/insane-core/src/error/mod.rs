@@ -0,0 +1,393 @@
+//! # Application Error Handling
+//!
+//! [`Error`] is the error of the whole framework: the HTTP handlers, the CLI
+//! commands, the jobs and the tasks all return it. Every error has an
+//! [`ErrorCode`], telling the HTTP status it is answered with and the exit
+//! code of the CLI. A `sea_orm::DbErr::RecordNotFound` is a `not_found`,
+//! answered with `404`, a unique constraint violation is a `conflict`, and so
+//! on, see [`Error::code`].
+//!
+//! The errors of the application carry their own code:
+//!
+//! ```rust
+//! use insane_core::error::{Error, ErrorCode, Result};
+//!
+//! fn withdraw(balance: u64, amount: u64) -> Result<u64> {
+//!     balance.checked_sub(amount).ok_or_else(|| {
+//!         Error::new(ErrorCode::CONFLICT, format!("insufficient balance: {balance}"))
+//!     })
+//! }
+//! ```
+//!
+//! With the `with-http` feature, [`Error`] answers the HTTP requests, see
+//! `insane_http::error`.
+
+mod code;
+#[cfg(feature = "with-http")]
+mod http;
+mod validation;
+
+pub use code::ErrorCode;
+#[cfg(feature = "with-http")]
+pub use http::ErrorDetail;
+pub use validation::{FieldError, FieldErrors};
+
+/// Application results options list
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("{inner}\n{backtrace}")]
+    WithBacktrace {
+        inner: Box<Self>,
+        backtrace: Box<std::backtrace::Backtrace>,
+    },
+
+    /// An error with an explicit code, see [`Error::new`].
+    #[error("{message}")]
+    WithCode {
+        code: ErrorCode,
+        message: String,
+        #[source]
+        source: Option<Box<dyn std::error::Error + Send + Sync>>,
+    },
+
+    /// A failure of the framework or the application, a `500`. The client
+    /// errors are [`Error::BadRequest`] or have their code, see [`Error::new`].
+    #[error("{0}")]
+    Message(String),
+
+    // API
+    #[error("{0}")]
+    Unauthorized(String),
+
+    #[error("not found")]
+    NotFound,
+
+    #[error("{0}")]
+    BadRequest(String),
+
+    /// Invalid fields, answered with `422` and the errors of the fields.
+    #[error("invalid fields: {}", .0.keys().map(String::as_str).collect::<Vec<_>>().join(", "))]
+    Validation(FieldErrors),
+
+    #[error("internal server error")]
+    InternalServerError,
+
+    #[cfg(feature = "with-http")]
+    #[error("")]
+    CustomError(axum::http::StatusCode, ErrorDetail),
+
+    #[error(transparent)]
+    JSON(serde_json::Error),
+
+    #[error(transparent)]
+    EnvVar(#[from] std::env::VarError),
+
+    #[error(transparent)]
+    IO(#[from] std::io::Error),
+
+    #[cfg(feature = "with-sql")]
+    #[error(transparent)]
+    DB(#[from] sea_orm::DbErr),
+
+    #[cfg(feature = "with-redis")]
+    #[error(transparent)]
+    Redis(#[from] redis::RedisError),
+
+    #[cfg(feature = "with-redis")]
+    #[error(transparent)]
+    RedisPool(#[from] bb8::RunError<redis::RedisError>),
+
+    #[cfg(feature = "with-s3")]
+    #[error(transparent)]
+    ObjectStore(#[from] object_store::Error),
+
+    #[cfg(feature = "with-http")]
+    #[error(transparent)]
+    Axum(#[from] axum::http::Error),
+
+    #[cfg(feature = "with-http")]
+    #[error(transparent)]
+    JsonRejection(#[from] axum::extract::rejection::JsonRejection),
+
+    #[cfg(feature = "with-http")]
+    #[error(transparent)]
+    InvalidHeaderValue(#[from] axum::http::header::InvalidHeaderValue),
+
+    #[cfg(feature = "with-http")]
+    #[error(transparent)]
+    InvalidHeaderName(#[from] axum::http::header::InvalidHeaderName),
+
+    #[cfg(feature = "with-http")]
+    #[error(transparent)]
+    InvalidMethod(#[from] axum::http::method::InvalidMethod),
+
+    #[error(transparent)]
+    Any(#[from] Box<dyn std::error::Error + Send + Sync>),
+
+    #[error(transparent)]
+    Anyhow(#[from] eyre::Report),
+
+    #[error(transparent)]
+    YAML(#[from] serde_yaml::Error),
+
+    #[error(transparent)]
+    ConfigError(#[from] config::ConfigError),
+
+    #[error(transparent)]
+    Trace(#[from] tracing_subscriber::filter::ParseError),
+}
+
+/*
+backtrace principles:
+- use a plan warapper variant with no 'from' conversion
+- hand-code "From" conversion and force capture there with 'bt', which
+  will wrap and create backtrace only if RUST_BACKTRACE=1.
+costs:
+- when RUST_BACKTRACE is not set, we don't pay for the capture and we dont pay for printing.
+
+ */
+impl From<serde_json::Error> for Error {
+    fn from(val: serde_json::Error) -> Self {
+        Self::JSON(val).bt()
+    }
+}
+
+impl Error {
+    /// An error with `code`, see [`ErrorCode`].
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self::WithCode {
+            code,
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// Keep `source` as the cause of the error, reported by
+    /// [`std::error::Error::source`]. Errors without an explicit code become
+    /// errors with their code and message, caused by `source`.
+    #[must_use]
+    pub fn with_source(self, source: impl std::error::Error + Send + Sync + 'static) -> Self {
+        match self {
+            Self::WithBacktrace { inner, backtrace } => Self::WithBacktrace {
+                inner: Box::new(inner.with_source(source)),
+                backtrace,
+            },
+            Self::WithCode { code, message, .. } => Self::WithCode {
+                code,
+                message,
+                source: Some(Box::new(source)),
+            },
+            err => Self::WithCode {
+                code: err.code(),
+                message: err.to_string(),
+                source: Some(Box::new(source)),
+            },
+        }
+    }
+
+    pub fn wrap(err: impl std::error::Error + Send + Sync + 'static) -> Self {
+        Self::Any(Box::new(err)) //.bt()
+    }
+
+    pub fn msg(err: impl std::error::Error + Send + Sync + 'static) -> Self {
+        Self::Message(err.to_string()) //.bt()
+    }
+    #[must_use]
+    pub fn string(s: &str) -> Self {
+        Self::Message(s.to_string())
+    }
+    #[must_use]
+    pub fn bt(self) -> Self {
+        let backtrace = std::backtrace::Backtrace::capture();
+        match backtrace.status() {
+            std::backtrace::BacktraceStatus::Disabled
+            | std::backtrace::BacktraceStatus::Unsupported => self,
+            _ => Self::WithBacktrace {
+                inner: Box::new(self),
+                backtrace: Box::new(backtrace),
+            },
+        }
+    }
+
+    /// The errors of the fields of a [`Error::Validation`].
+    #[must_use]
+    pub fn field_errors(&self) -> Option<&FieldErrors> {
+        match self {
+            Self::WithBacktrace { inner, .. } => inner.field_errors(),
+            Self::Validation(errors) => Some(errors),
+            _ => None,
+        }
+    }
+
+    /// The code of the error, from the error it wraps when it has no
+    /// explicit code.
+    #[must_use]
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::WithBacktrace { inner, .. } => inner.code(),
+            Self::WithCode { code, .. } => *code,
+            Self::Message(_) | Self::InternalServerError => ErrorCode::INTERNAL,
+            Self::Unauthorized(_) => ErrorCode::UNAUTHORIZED,
+            Self::NotFound => ErrorCode::NOT_FOUND,
+            Self::BadRequest(_) => ErrorCode::BAD_REQUEST,
+            Self::Validation(_) => ErrorCode::VALIDATION,
+            Self::EnvVar(_) | Self::YAML(_) | Self::ConfigError(_) | Self::Trace(_) => {
+                ErrorCode::CONFIG
+            }
+            Self::IO(err) => match err.kind() {
+                std::io::ErrorKind::NotFound => ErrorCode::NOT_FOUND,
+                std::io::ErrorKind::PermissionDenied => ErrorCode::FORBIDDEN,
+                std::io::ErrorKind::TimedOut => ErrorCode::TIMEOUT,
+                _ => ErrorCode::IO,
+            },
+            #[cfg(feature = "with-sql")]
+            Self::DB(err) => db_code(err),
+            #[cfg(feature = "with-redis")]
+            Self::RedisPool(_) => ErrorCode::UNAVAILABLE,
+            #[cfg(feature = "with-s3")]
+            Self::ObjectStore(object_store::Error::NotFound { .. }) => ErrorCode::NOT_FOUND,
+            #[cfg(feature = "with-s3")]
+            Self::ObjectStore(_) => ErrorCode::IO,
+            #[cfg(feature = "with-http")]
+            Self::CustomError(status, _) => ErrorCode::new(
+                "custom_error",
+                status.as_u16(),
+                ErrorCode::INTERNAL.exit_code(),
+            ),
+            #[cfg(feature = "with-http")]
+            Self::JsonRejection(_) => ErrorCode::BAD_REQUEST,
+            Self::Any(err) => err
+                .downcast_ref::<Self>()
+                .map_or(ErrorCode::INTERNAL, Self::code),
+            _ => ErrorCode::INTERNAL,
+        }
+    }
+}
+
+#[cfg(feature = "with-sql")]
+fn db_code(err: &sea_orm::DbErr) -> ErrorCode {
+    use sea_orm::{DbErr, SqlErr};
+
+    match err {
+        DbErr::RecordNotFound(_) | DbErr::RecordNotUpdated => ErrorCode::NOT_FOUND,
+        DbErr::ConnectionAcquire(_) | DbErr::Conn(_) => ErrorCode::UNAVAILABLE,
+        _ => match err.sql_err() {
+            Some(
+                SqlErr::UniqueConstraintViolation(_) | SqlErr::ForeignKeyConstraintViolation(_),
+            ) => ErrorCode::CONFLICT,
+            _ => ErrorCode::DATABASE,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+
+    use super::*;
+
+    fn io(kind: std::io::ErrorKind) -> Error {
+        Error::IO(std::io::Error::new(kind, "io"))
+    }
+
+    #[test]
+    fn maps_the_io_errors_by_kind() {
+        assert_eq!(
+            io(std::io::ErrorKind::NotFound).code(),
+            ErrorCode::NOT_FOUND
+        );
+        assert_eq!(
+            io(std::io::ErrorKind::PermissionDenied).code(),
+            ErrorCode::FORBIDDEN
+        );
+        assert_eq!(io(std::io::ErrorKind::TimedOut).code(), ErrorCode::TIMEOUT);
+        assert_eq!(io(std::io::ErrorKind::BrokenPipe).code(), ErrorCode::IO);
+    }
+
+    #[cfg(feature = "with-sql")]
+    #[test]
+    fn maps_the_database_errors() {
+        use sea_orm::{DbErr, RuntimeErr};
+
+        let code = |err: DbErr| Error::DB(err).code();
+        assert_eq!(
+            code(DbErr::RecordNotFound("user".to_string())),
+            ErrorCode::NOT_FOUND
+        );
+        assert_eq!(code(DbErr::RecordNotUpdated), ErrorCode::NOT_FOUND);
+        assert_eq!(
+            code(DbErr::Conn(RuntimeErr::Internal("refused".to_string()))),
+            ErrorCode::UNAVAILABLE
+        );
+        assert_eq!(
+            code(DbErr::Custom("syntax".to_string())),
+            ErrorCode::DATABASE
+        );
+        assert_eq!(ErrorCode::NOT_FOUND.status(), 404);
+    }
+
+    #[test]
+    fn maps_the_messages_to_server_errors() {
+        assert_eq!(Error::string("boom").code(), ErrorCode::INTERNAL);
+        assert_eq!(Error::InternalServerError.code(), ErrorCode::INTERNAL);
+        assert_eq!(
+            Error::BadRequest("no".to_string()).code(),
+            ErrorCode::BAD_REQUEST
+        );
+        assert_eq!(Error::NotFound.code(), ErrorCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn finds_the_code_of_the_wrapped_errors() {
+        let err = Error::wrap(Error::new(ErrorCode::CONFLICT, "taken"));
+        assert_eq!(err.code(), ErrorCode::CONFLICT);
+        assert_eq!(Error::wrap(std::fmt::Error).code(), ErrorCode::INTERNAL);
+
+        let err = Error::WithBacktrace {
+            inner: Box::new(Error::NotFound),
+            backtrace: Box::new(std::backtrace::Backtrace::disabled()),
+        };
+        assert_eq!(err.code(), ErrorCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn keeps_the_source_of_the_errors() {
+        let err = Error::new(ErrorCode::UNAVAILABLE, "mailer down")
+            .with_source(std::io::Error::other("connection refused"));
+        assert_eq!(err.to_string(), "mailer down");
+        assert_eq!(err.code(), ErrorCode::UNAVAILABLE);
+        assert_eq!(err.source().unwrap().to_string(), "connection refused");
+
+        // keeps the code and the message of the errors without one
+        let err = Error::NotFound.with_source(std::io::Error::other("no row"));
+        assert_eq!(err.code(), ErrorCode::NOT_FOUND);
+        assert_eq!(err.to_string(), "not found");
+        assert_eq!(err.source().unwrap().to_string(), "no row");
+
+        // and their backtrace
+        let err = Error::WithBacktrace {
+            inner: Box::new(Error::string("boom")),
+            backtrace: Box::new(std::backtrace::Backtrace::disabled()),
+        }
+        .with_source(std::io::Error::other("cause"));
+        let Error::WithBacktrace { inner, .. } = &err else {
+            panic!("backtrace lost");
+        };
+        assert_eq!(inner.source().unwrap().to_string(), "cause");
+    }
+
+    #[test]
+    fn exits_with_the_code_of_the_error() {
+        assert_eq!(Error::BadRequest("no".to_string()).code().exit_code(), 64);
+        assert_eq!(io(std::io::ErrorKind::NotFound).code().exit_code(), 66);
+        assert_eq!(
+            Error::EnvVar(std::env::VarError::NotPresent)
+                .code()
+                .exit_code(),
+            78
+        );
+        assert_eq!(Error::string("boom").code().exit_code(), 70);
+    }
+}
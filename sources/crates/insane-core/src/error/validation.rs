@@ -0,0 +1,39 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+/// The errors of the invalid fields of an input, by path of the field, like
+/// `email`, `address.city` or `items[0].name`. See
+/// [`super::Error::Validation`].
+pub type FieldErrors = BTreeMap<String, Vec<FieldError>>;
+
+/// A failed validation rule of a field.
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    /// The rule, like `length`, `email` or the code of a custom rule.
+    pub code: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+
+    /// The parameters of the rule, like the `min` and `max` of a `length`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, serde_json::Value>,
+}
+
+impl FieldError {
+    #[must_use]
+    pub fn new(code: &str) -> Self {
+        Self {
+            code: code.to_string(),
+            message: None,
+            params: BTreeMap::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn message(mut self, message: &str) -> Self {
+        self.message = Some(message.to_string());
+        self
+    }
+}
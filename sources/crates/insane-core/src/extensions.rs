@@ -0,0 +1,151 @@
+//! A type map holding the application-owned services shared through the
+//! [`crate::context::Context`].
+//!
+//! Services are usually registered in [`crate::hook::Hooks::before_run`] or by
+//! an initializer, and read back from any server.
+//!
+//! # Example:
+//!
+//! ```rust
+//! use insane_core::extensions::Extensions;
+//!
+//! struct Greeter {
+//!     greeting: String,
+//! }
+//!
+//! let extensions = Extensions::default();
+//! extensions.insert(Greeter { greeting: "hello".to_string() });
+//!
+//! let greeter = extensions.get::<Greeter>().expect("greeter is registered");
+//! assert_eq!(greeter.greeting, "hello");
+//! ```
+
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+type AnyService = Arc<dyn Any + Send + Sync>;
+
+/// Shared, cloneable type map. Clones share the same underlying storage.
+#[derive(Clone, Default)]
+pub struct Extensions {
+    map: Arc<RwLock<HashMap<TypeId, AnyService>>>,
+}
+
+impl Extensions {
+    /// Register a service, replacing and returning the previous one of the
+    /// same type.
+    pub fn insert<T: Send + Sync + 'static>(&self, value: T) -> Option<Arc<T>> {
+        self.insert_arc(Arc::new(value))
+    }
+
+    /// Register a service that is already shared.
+    pub fn insert_arc<T: Send + Sync + 'static>(&self, value: Arc<T>) -> Option<Arc<T>> {
+        self.map
+            .write()
+            .expect("extensions lock poisoned")
+            .insert(TypeId::of::<T>(), value)
+            .and_then(|previous| previous.downcast::<T>().ok())
+    }
+
+    /// Get the service of the given type.
+    #[must_use]
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        self.map
+            .read()
+            .expect("extensions lock poisoned")
+            .get(&TypeId::of::<T>())
+            .cloned()
+            .and_then(|value| value.downcast::<T>().ok())
+    }
+
+    /// Get the service of the given type, registering the one built by `f`
+    /// when there is none. The check and the registration are atomic.
+    pub fn get_or_insert_with<T: Send + Sync + 'static>(&self, f: impl FnOnce() -> T) -> Arc<T> {
+        self.map
+            .write()
+            .expect("extensions lock poisoned")
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Arc::new(f()))
+            .clone()
+            .downcast::<T>()
+            .expect("extensions are keyed by type")
+    }
+
+    /// Return `true` when a service of the given type is registered.
+    #[must_use]
+    pub fn contains<T: Send + Sync + 'static>(&self) -> bool {
+        self.map
+            .read()
+            .expect("extensions lock poisoned")
+            .contains_key(&TypeId::of::<T>())
+    }
+
+    /// Unregister the service of the given type.
+    pub fn remove<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        self.map
+            .write()
+            .expect("extensions lock poisoned")
+            .remove(&TypeId::of::<T>())
+            .and_then(|value| value.downcast::<T>().ok())
+    }
+}
+
+impl std::fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let len = self.map.read().map_or(0, |map| map.len());
+        f.debug_struct("Extensions").field("len", &len).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Greeter(&'static str);
+
+    #[test]
+    fn shares_the_services_between_the_clones() {
+        let extensions = Extensions::default();
+        let clone = extensions.clone();
+        assert!(extensions.get::<Greeter>().is_none());
+
+        assert!(clone.insert(Greeter("hello")).is_none());
+        assert!(extensions.contains::<Greeter>());
+        assert_eq!(*extensions.get::<Greeter>().unwrap(), Greeter("hello"));
+        assert!(extensions.get::<String>().is_none());
+    }
+
+    #[test]
+    fn replaces_the_service_of_the_same_type() {
+        let extensions = Extensions::default();
+        extensions.insert(Greeter("hello"));
+
+        let previous = extensions.insert(Greeter("hi")).unwrap();
+        assert_eq!(*previous, Greeter("hello"));
+        assert_eq!(*extensions.get::<Greeter>().unwrap(), Greeter("hi"));
+    }
+
+    #[test]
+    fn registers_the_service_built_when_missing() {
+        let extensions = Extensions::default();
+
+        let first = extensions.get_or_insert_with(|| Greeter("hello"));
+        let second = extensions.get_or_insert_with(|| -> Greeter { unreachable!() });
+        assert_eq!(*first, Greeter("hello"));
+        assert!(Arc::ptr_eq(&first, &second));
+    }
+
+    #[test]
+    fn removes_the_service() {
+        let extensions = Extensions::default();
+        extensions.insert(Greeter("hello"));
+
+        assert_eq!(*extensions.remove::<Greeter>().unwrap(), Greeter("hello"));
+        assert!(!extensions.contains::<Greeter>());
+        assert!(extensions.remove::<Greeter>().is_none());
+    }
+}
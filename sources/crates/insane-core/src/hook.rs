@@ -7,7 +7,6 @@ use sea_orm::DatabaseConnection;
 #[cfg(feature = "with-sql")]
 use std::path::Path;
 
-
 #[async_trait::async_trait]
 pub trait Hooks: Sync + Send {
     /// Defines the composite app version
@@ -38,7 +37,8 @@ pub trait Hooks: Sync + Send {
 
     /// Calling the function before run the app
     /// You can now code some custom loading of resources or other things before
-    /// the app runs
+    /// the app runs. Shared services can be registered here with
+    /// `app_context.extensions().insert(..)`.
     async fn before_run(_app_context: Arc<Box<dyn Context>>) -> Result<()> {
         Ok(())
     }
@@ -59,7 +59,7 @@ pub trait Hooks: Sync + Send {
     /// by changing dangerously_truncate to true (default false).
     /// Truncate can be useful when you want to truncate the database before any
     /// test.
-    #[cfg(feature = "with-sql")]     
+    #[cfg(feature = "with-sql")]
     async fn truncate(db: &DatabaseConnection) -> Result<()>;
 
     /// Seeds the database with initial data.    
@@ -69,6 +69,9 @@ pub trait Hooks: Sync + Send {
 
 /// An initializer.
 /// Initializers should be kept in `src/initializers/`
+///
+/// Initializers are run in dependency order (see [`Initializer::dependencies`])
+/// by [`crate::initializers::InitializerChain`].
 #[async_trait::async_trait]
 pub trait Initializer: Sync + Send {
     /// Associated type for the context
@@ -77,6 +80,11 @@ pub trait Initializer: Sync + Send {
     /// The initializer name or identifier
     fn name(&self) -> String;
 
+    /// Names of the initializers that must run before this one.
+    fn dependencies(&self) -> Vec<String> {
+        vec![]
+    }
+
     /// Occurs after the app's `before_run`.
     /// Use this to for one-time initializations, load caches, perform web
     /// hooks, etc.
@@ -87,4 +95,26 @@ pub trait Initializer: Sync + Send {
     ) -> Result<()> {
         Ok(())
     }
+
+    /// Occurs once every server reported it is ready, see
+    /// [`crate::server::Readiness`].
+    /// Use this to start background tasks that rely on the running servers.
+    async fn after_start(
+        &self,
+        _app_context: Arc<Box<dyn Context>>,
+        _context: Option<Self::Context>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Occurs during the shutdown, once the servers stopped. Initializers are
+    /// shut down in the reverse order they were started.
+    /// Use this to release the resources owned by the initializer.
+    async fn before_shutdown(
+        &self,
+        _app_context: Arc<Box<dyn Context>>,
+        _context: Option<Self::Context>,
+    ) -> Result<()> {
+        Ok(())
+    }
 }
@@ -0,0 +1,357 @@
+//! Ordering and lifecycle of the [`Initializer`]s.
+//!
+//! Initializers declare the names of the initializers they depend on with
+//! [`Initializer::dependencies`]. [`InitializerChain`] orders them
+//! topologically, keeping the declaration order between independent ones, and
+//! logs how long each lifecycle callback took.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+    time::Instant,
+};
+
+use crate::{
+    context::Context,
+    error::{Error, ErrorCode, Result},
+    hook::Initializer,
+};
+
+/// Initializers sorted by their dependencies.
+pub struct InitializerChain<C> {
+    initializers: Vec<Box<dyn Initializer<Context = C>>>,
+}
+
+impl<C: Clone + Send + Sync> InitializerChain<C> {
+    /// Sort the given initializers by their dependencies.
+    ///
+    /// # Errors
+    /// A [`ErrorCode::CONFIG`] error when two initializers share the same
+    /// name, an initializer depends on an unknown one, or the dependencies
+    /// contain a cycle.
+    pub fn new(initializers: Vec<Box<dyn Initializer<Context = C>>>) -> Result<Self> {
+        Ok(Self {
+            initializers: sort(initializers)?,
+        })
+    }
+
+    /// The initializer names, in the order they run.
+    #[must_use]
+    pub fn names(&self) -> Vec<String> {
+        self.initializers.iter().map(|init| init.name()).collect()
+    }
+
+    /// Run [`Initializer::before_run`] of every initializer.
+    ///
+    /// # Errors
+    /// Stops at the first initializer that fails and returns its error, once
+    /// the initializers that already ran `before_run` ran `before_shutdown`.
+    pub async fn before_run(
+        &self,
+        app_context: &Arc<Box<dyn Context>>,
+        context: Option<C>,
+    ) -> Result<()> {
+        for (position, initializer) in self.initializers.iter().enumerate() {
+            let started = Instant::now();
+            if let Err(err) = initializer
+                .before_run(app_context.clone(), context.clone())
+                .await
+            {
+                tracing::error!(
+                    initializer = initializer.name(),
+                    err.msg = %err,
+                    "initializer before_run failed"
+                );
+                shut_down(&self.initializers[..position], app_context, context).await;
+                return Err(err);
+            }
+            log_timing(initializer.name(), "before_run", started);
+        }
+        Ok(())
+    }
+
+    /// Run [`Initializer::after_start`] of every initializer.
+    ///
+    /// # Errors
+    /// Stops at the first initializer that fails and returns its error.
+    pub async fn after_start(
+        &self,
+        app_context: &Arc<Box<dyn Context>>,
+        context: Option<C>,
+    ) -> Result<()> {
+        for initializer in &self.initializers {
+            let started = Instant::now();
+            initializer
+                .after_start(app_context.clone(), context.clone())
+                .await?;
+            log_timing(initializer.name(), "after_start", started);
+        }
+        Ok(())
+    }
+
+    /// Run [`Initializer::before_shutdown`] of every initializer, in reverse
+    /// order. Failures are logged and do not prevent the other initializers
+    /// from shutting down.
+    pub async fn before_shutdown(&self, app_context: &Arc<Box<dyn Context>>, context: Option<C>) {
+        shut_down(&self.initializers, app_context, context).await;
+    }
+}
+
+/// Run [`Initializer::before_shutdown`] of `initializers`, in reverse order.
+async fn shut_down<C: Clone + Send + Sync>(
+    initializers: &[Box<dyn Initializer<Context = C>>],
+    app_context: &Arc<Box<dyn Context>>,
+    context: Option<C>,
+) {
+    for initializer in initializers.iter().rev() {
+        let started = Instant::now();
+        match initializer
+            .before_shutdown(app_context.clone(), context.clone())
+            .await
+        {
+            Ok(()) => log_timing(initializer.name(), "before_shutdown", started),
+            Err(err) => {
+                tracing::error!(
+                    initializer = initializer.name(),
+                    err.msg = %err,
+                    "initializer before_shutdown failed"
+                );
+            }
+        }
+    }
+}
+
+fn log_timing(initializer: String, phase: &str, started: Instant) {
+    tracing::info!(initializer, phase, elapsed = ?started.elapsed(), "initializer completed");
+}
+
+/// Topologically sort the initializers (Kahn's algorithm). Among the
+/// initializers ready to run, the one declared first goes first.
+fn sort<C: Send + Sync>(
+    initializers: Vec<Box<dyn Initializer<Context = C>>>,
+) -> Result<Vec<Box<dyn Initializer<Context = C>>>> {
+    let names = initializers
+        .iter()
+        .map(|init| init.name())
+        .collect::<Vec<_>>();
+
+    let mut index = HashMap::new();
+    for (position, name) in names.iter().enumerate() {
+        if index.insert(name.as_str(), position).is_some() {
+            return Err(Error::new(
+                ErrorCode::CONFIG,
+                format!("initializer `{name}` is registered more than once"),
+            ));
+        }
+    }
+
+    let mut pending_dependencies = vec![0; names.len()];
+    let mut dependents = vec![vec![]; names.len()];
+    for (position, initializer) in initializers.iter().enumerate() {
+        for dependency in initializer.dependencies() {
+            let dependency_position = *index.get(dependency.as_str()).ok_or_else(|| {
+                Error::new(
+                    ErrorCode::CONFIG,
+                    format!(
+                        "initializer `{}` depends on unknown initializer `{dependency}`",
+                        names[position]
+                    ),
+                )
+            })?;
+            pending_dependencies[position] += 1;
+            dependents[dependency_position].push(position);
+        }
+    }
+
+    let mut ready = (0..names.len())
+        .filter(|position| pending_dependencies[*position] == 0)
+        .collect::<BTreeSet<_>>();
+    let mut order = Vec::with_capacity(names.len());
+    while let Some(position) = ready.pop_first() {
+        order.push(position);
+        for dependent in &dependents[position] {
+            pending_dependencies[*dependent] -= 1;
+            if pending_dependencies[*dependent] == 0 {
+                ready.insert(*dependent);
+            }
+        }
+    }
+
+    if order.len() != names.len() {
+        let cycle = (0..names.len())
+            .filter(|position| pending_dependencies[*position] > 0)
+            .map(|position| names[position].as_str())
+            .collect::<Vec<_>>();
+        return Err(Error::new(
+            ErrorCode::CONFIG,
+            format!(
+                "initializer dependency cycle detected between: {}",
+                cycle.join(", ")
+            ),
+        ));
+    }
+
+    let mut initializers = initializers.into_iter().map(Some).collect::<Vec<_>>();
+    Ok(order
+        .into_iter()
+        .filter_map(|position| initializers[position].take())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Named(&'static str, &'static [&'static str]);
+
+    #[async_trait::async_trait]
+    impl Initializer for Named {
+        type Context = ();
+
+        fn name(&self) -> String {
+            self.0.to_string()
+        }
+
+        fn dependencies(&self) -> Vec<String> {
+            self.1.iter().map(ToString::to_string).collect()
+        }
+    }
+
+    fn chain(initializers: Vec<Named>) -> Result<Vec<String>> {
+        let initializers = initializers
+            .into_iter()
+            .map(|init| Box::new(init) as Box<dyn Initializer<Context = ()>>)
+            .collect();
+        InitializerChain::new(initializers).map(|chain| chain.names())
+    }
+
+    #[test]
+    fn keeps_the_declaration_order_of_independent_initializers() {
+        let names = chain(vec![Named("c", &[]), Named("a", &[]), Named("b", &[])]).unwrap();
+        assert_eq!(names, ["c", "a", "b"]);
+    }
+
+    #[test]
+    fn runs_the_dependencies_first() {
+        let names = chain(vec![
+            Named("mailer", &["jobs", "templates"]),
+            Named("jobs", &["db"]),
+            Named("metrics", &[]),
+            Named("templates", &[]),
+            Named("db", &[]),
+        ])
+        .unwrap();
+        assert_eq!(names, ["metrics", "templates", "db", "jobs", "mailer"]);
+    }
+
+    #[test]
+    fn reports_the_cycles() {
+        let err = chain(vec![
+            Named("a", &["c"]),
+            Named("b", &["a"]),
+            Named("c", &["b"]),
+            Named("d", &[]),
+        ])
+        .unwrap_err();
+        assert_eq!(err.code(), ErrorCode::CONFIG);
+        assert_eq!(
+            err.to_string(),
+            "initializer dependency cycle detected between: a, b, c"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_and_duplicate_initializers() {
+        let err = chain(vec![Named("a", &["missing"])]).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::CONFIG);
+        assert_eq!(
+            err.to_string(),
+            "initializer `a` depends on unknown initializer `missing`"
+        );
+
+        let err = chain(vec![Named("a", &[]), Named("a", &[])]).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::CONFIG);
+        assert_eq!(
+            err.to_string(),
+            "initializer `a` is registered more than once"
+        );
+    }
+
+    /// Records its callbacks in the shared log, failing `before_run` when
+    /// told to.
+    struct Recording {
+        name: &'static str,
+        fails: bool,
+        log: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Initializer for Recording {
+        type Context = ();
+
+        fn name(&self) -> String {
+            self.name.to_string()
+        }
+
+        async fn before_run(
+            &self,
+            _app_context: Arc<Box<dyn Context>>,
+            _context: Option<()>,
+        ) -> Result<()> {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("{}.before_run", self.name));
+            if self.fails {
+                return Err(Error::string("no connection"));
+            }
+            Ok(())
+        }
+
+        async fn before_shutdown(
+            &self,
+            _app_context: Arc<Box<dyn Context>>,
+            _context: Option<()>,
+        ) -> Result<()> {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("{}.before_shutdown", self.name));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn shuts_down_the_started_initializers_when_one_fails() {
+        let log = Arc::default();
+        let recording = |name, fails| {
+            Box::new(Recording {
+                name,
+                fails,
+                log: Arc::clone(&log),
+            }) as Box<dyn Initializer<Context = ()>>
+        };
+        let chain = InitializerChain::new(vec![
+            recording("db", false),
+            recording("cache", false),
+            recording("jobs", true),
+            recording("mailer", false),
+        ])
+        .unwrap();
+
+        let context = crate::context::test_context(crate::config::InsaneConfig::default());
+        let err = chain.before_run(&context, None).await.unwrap_err();
+
+        assert_eq!(err.to_string(), "no connection");
+        assert_eq!(
+            *log.lock().unwrap(),
+            [
+                "db.before_run",
+                "cache.before_run",
+                "jobs.before_run",
+                "cache.before_shutdown",
+                "db.before_shutdown",
+            ]
+        );
+    }
+}
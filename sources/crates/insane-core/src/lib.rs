@@ -1,29 +1,52 @@
 pub mod backtrace;
+pub mod cache;
 pub mod config;
 pub mod context;
+pub mod control;
 pub mod environment;
 pub mod error;
+pub mod extensions;
 pub mod hook;
+pub mod initializers;
+pub mod metrics;
+pub mod reload;
 pub mod server;
+pub mod shutdown;
+pub mod storage;
+pub mod supervisor;
 pub mod traces;
 
 #[cfg(feature = "with-sql")]
 pub mod sql;
 
+#[cfg(feature = "with-redis")]
+pub mod redis;
+
+#[cfg(feature = "with-otel")]
+pub(crate) mod otel;
+
 pub(crate) mod banner;
 pub mod boot_loader;
 
 pub mod prelude {
+    pub use crate::cache::Cache;
     pub use crate::config::InsaneConfig;
     pub use crate::context::Context;
     pub use crate::environment::Environment;
     pub use crate::error::Result;
+    pub use crate::extensions::Extensions;
     pub use crate::hook::Hooks;
+    pub use crate::reload::ConfigReloader;
     pub use crate::server::Server;
+    pub use crate::shutdown::ShutdownToken;
+    pub use crate::storage::Storage;
 
     #[cfg(feature = "with-sql")]
     pub use sea_orm_migration::MigratorTrait;
 
     #[cfg(feature = "with-sql")]
     pub use sea_orm::DatabaseConnection;
+
+    #[cfg(feature = "with-redis")]
+    pub use crate::redis::RedisPool;
 }
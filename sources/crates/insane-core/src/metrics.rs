@@ -0,0 +1,300 @@
+//! Prometheus metrics.
+//!
+//! The framework records its metrics in a process-wide registry, served on
+//! `/_metrics` with `metrics.enable`:
+//!
+//! * `http_requests_total` and `http_request_duration_seconds`, by method,
+//!   route template and status.
+//! * `db_pool_connections` and `db_pool_max_connections`, the SQL pool.
+//! * `jobs_total` and `job_duration_seconds`, by job and outcome.
+//! * `scheduled_tasks_total` and `scheduled_task_duration_seconds`, by task
+//!   and outcome.
+//!
+//! The application registers its own metrics the same way. Registering a name
+//! again, with the same labels, returns the metric registered first, so they
+//! can be created where they are used:
+//!
+//! ```rust
+//! use insane_core::{error::Result, metrics};
+//!
+//! fn signed_up(plan: &str) -> Result<()> {
+//!     metrics::counter("signups_total", "Users signed up", &["plan"])?
+//!         .with_label_values(&[plan])
+//!         .inc();
+//!     Ok(())
+//! }
+//! ```
+
+use std::{
+    any::Any,
+    collections::BTreeMap,
+    sync::{Mutex, RwLock},
+};
+
+use lazy_static::lazy_static;
+use prometheus::{core::Collector, Encoder, HistogramOpts, Opts, TextEncoder};
+pub use prometheus::{GaugeVec, HistogramVec, IntCounterVec, IntGaugeVec, Registry};
+
+use crate::error::{Error, Result};
+
+/// Content type of [`render`].
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+type ScrapeHook = Box<dyn Fn() + Send + Sync>;
+
+/// A registered metric and the names of its labels.
+struct Registered {
+    metric: Box<dyn Any + Send>,
+    labels: Vec<String>,
+}
+
+lazy_static! {
+    static ref REGISTRY: Registry = Registry::new();
+    /// The metrics registered by name, to return them when registered again.
+    static ref METRICS: Mutex<BTreeMap<String, Registered>> = Mutex::new(BTreeMap::new());
+    static ref SCRAPE_HOOKS: RwLock<Vec<ScrapeHook>> = RwLock::new(Vec::new());
+}
+
+/// The registry of the metrics.
+#[must_use]
+pub fn registry() -> &'static Registry {
+    &REGISTRY
+}
+
+/// Register `metric` under `name`, or get the metric registered first.
+fn register<M>(
+    name: &str,
+    labels: &[&str],
+    create: impl FnOnce() -> prometheus::Result<M>,
+) -> Result<M>
+where
+    M: Collector + Clone + Send + 'static,
+{
+    let mut metrics = METRICS.lock().unwrap_or_else(|err| err.into_inner());
+    if let Some(registered) = metrics.get(name) {
+        if registered.labels != labels {
+            return Err(Error::Message(format!(
+                "metric `{name}` is registered with the labels {:?}, not {labels:?}",
+                registered.labels
+            )));
+        }
+        return registered
+            .metric
+            .downcast_ref::<M>()
+            .cloned()
+            .ok_or_else(|| {
+                Error::Message(format!("metric `{name}` is registered with another type"))
+            });
+    }
+
+    let metric = create().map_err(Error::wrap)?;
+    REGISTRY
+        .register(Box::new(metric.clone()))
+        .map_err(Error::wrap)?;
+    metrics.insert(
+        name.to_string(),
+        Registered {
+            metric: Box::new(metric.clone()),
+            labels: labels.iter().map(ToString::to_string).collect(),
+        },
+    );
+    Ok(metric)
+}
+
+/// A counter, by `labels`.
+///
+/// # Errors
+/// When the name or a label is invalid, or the name is registered with
+/// another type or other labels.
+pub fn counter(name: &str, help: &str, labels: &[&str]) -> Result<IntCounterVec> {
+    register(name, labels, || {
+        IntCounterVec::new(Opts::new(name, help), labels)
+    })
+}
+
+/// A gauge, by `labels`.
+///
+/// # Errors
+/// When the name or a label is invalid, or the name is registered with
+/// another type or other labels.
+pub fn gauge(name: &str, help: &str, labels: &[&str]) -> Result<IntGaugeVec> {
+    register(name, labels, || {
+        IntGaugeVec::new(Opts::new(name, help), labels)
+    })
+}
+
+/// A histogram, by `labels`, with the default buckets (5ms to 10s).
+///
+/// # Errors
+/// When the name or a label is invalid, or the name is registered with
+/// another type or other labels.
+pub fn histogram(name: &str, help: &str, labels: &[&str]) -> Result<HistogramVec> {
+    register(name, labels, || {
+        HistogramVec::new(HistogramOpts::new(name, help), labels)
+    })
+}
+
+/// Run `hook` before each rendering of the metrics, to update the gauges
+/// read from elsewhere.
+pub fn on_scrape(hook: impl Fn() + Send + Sync + 'static) {
+    SCRAPE_HOOKS
+        .write()
+        .unwrap_or_else(|err| err.into_inner())
+        .push(Box::new(hook));
+}
+
+/// The metrics in the Prometheus text format.
+///
+/// # Errors
+/// When the metrics can't be encoded.
+pub fn render() -> Result<String> {
+    for hook in SCRAPE_HOOKS
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+        .iter()
+    {
+        hook();
+    }
+
+    let mut buffer = Vec::new();
+    TextEncoder::new()
+        .encode(&REGISTRY.gather(), &mut buffer)
+        .map_err(Error::wrap)?;
+    String::from_utf8(buffer).map_err(Error::wrap)
+}
+
+#[cfg(feature = "with-sql")]
+lazy_static! {
+    /// The pool reported by [`track_pool`], replaced when tracked again.
+    static ref POOL: Mutex<Option<sea_orm::DatabaseConnection>> = Mutex::new(None);
+}
+
+/// Report the connections of the SQL pool of `db`, in place of the pool
+/// tracked before. Only the Postgres and SQLite pools are reported, the other
+/// connections are skipped with a warning.
+///
+/// # Errors
+/// When the gauges can't be registered.
+#[cfg(feature = "with-sql")]
+pub fn track_pool(db: &sea_orm::DatabaseConnection) -> Result<()> {
+    use sea_orm::DatabaseConnection;
+
+    if !matches!(
+        db,
+        DatabaseConnection::SqlxPostgresPoolConnection(_)
+            | DatabaseConnection::SqlxSqlitePoolConnection(_)
+    ) {
+        tracing::warn!("the database pool metrics support only Postgres and SQLite, skipping them");
+        return Ok(());
+    }
+
+    let connections = gauge(
+        "db_pool_connections",
+        "Connections of the database pool",
+        &["state"],
+    )?;
+    let max_connections = gauge(
+        "db_pool_max_connections",
+        "Maximum number of connections of the database pool",
+        &[],
+    )?;
+
+    let previous = POOL
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .replace(db.clone());
+    if previous.is_some() {
+        return Ok(());
+    }
+
+    on_scrape(move || {
+        let pool = POOL.lock().unwrap_or_else(|err| err.into_inner());
+        let (size, idle, max) = match pool.as_ref() {
+            Some(db @ DatabaseConnection::SqlxPostgresPoolConnection(_)) => {
+                let pool = db.get_postgres_connection_pool();
+                (
+                    pool.size(),
+                    pool.num_idle(),
+                    pool.options().get_max_connections(),
+                )
+            }
+            Some(db @ DatabaseConnection::SqlxSqlitePoolConnection(_)) => {
+                let pool = db.get_sqlite_connection_pool();
+                (
+                    pool.size(),
+                    pool.num_idle(),
+                    pool.options().get_max_connections(),
+                )
+            }
+            _ => return,
+        };
+        let idle = i64::try_from(idle).unwrap_or(i64::MAX);
+        connections
+            .with_label_values(&["active"])
+            .set(i64::from(size) - idle);
+        connections.with_label_values(&["idle"]).set(idle);
+        max_connections.with_label_values(&[]).set(i64::from(max));
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn returns_the_metric_registered_first() {
+        let first = counter("test_returned_total", "Returned", &["kind"]).unwrap();
+        let again = counter("test_returned_total", "Returned again", &["kind"]).unwrap();
+        again.with_label_values(&["a"]).inc();
+
+        assert_eq!(first.with_label_values(&["a"]).get(), 1);
+        assert!(render()
+            .unwrap()
+            .contains("test_returned_total{kind=\"a\"} 1"));
+    }
+
+    #[test]
+    fn rejects_other_labels() {
+        gauge("test_labels", "Labels", &["kind"]).unwrap();
+
+        let err = gauge("test_labels", "Labels", &["kind", "state"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "metric `test_labels` is registered with the labels [\"kind\"], \
+             not [\"kind\", \"state\"]"
+        );
+        assert!(gauge("test_labels", "Labels", &[]).is_err());
+    }
+
+    #[test]
+    fn rejects_another_type() {
+        counter("test_typed", "Typed", &[]).unwrap();
+
+        let err = histogram("test_typed", "Typed", &[]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "metric `test_typed` is registered with another type"
+        );
+    }
+
+    #[cfg(feature = "with-sql")]
+    #[tokio::test]
+    async fn tracks_the_last_pool_once() {
+        async fn connect(max_connections: u32) -> sea_orm::DatabaseConnection {
+            let mut options = sea_orm::ConnectOptions::new("sqlite::memory:");
+            options.max_connections(max_connections).sqlx_logging(false);
+            sea_orm::Database::connect(options).await.unwrap()
+        }
+        let hooks = || SCRAPE_HOOKS.read().unwrap().len();
+        let before = hooks();
+
+        track_pool(&sea_orm::DatabaseConnection::Disconnected).unwrap();
+        assert_eq!(hooks(), before);
+
+        track_pool(&connect(2).await).unwrap();
+        track_pool(&connect(3).await).unwrap();
+        assert_eq!(hooks(), before + 1);
+        assert!(render().unwrap().contains("db_pool_max_connections 3"));
+    }
+}
@@ -0,0 +1,279 @@
+//! OpenTelemetry export, installed by [`crate::traces::init`] when
+//! `tracing.otel` is set.
+//!
+//! The spans go through the `tracing` subscriber like the logs, and are
+//! exported by batches to the OTLP collector. The W3C trace context
+//! propagator is installed globally, the HTTP server uses it to continue the
+//! traces of the incoming `traceparent` headers.
+
+use std::sync::Mutex;
+
+use opentelemetry::{global, trace::TracerProvider as _, KeyValue};
+use opentelemetry_otlp::{MetricExporter, Protocol, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{
+    metrics::{PeriodicReader, SdkMeterProvider},
+    propagation::TraceContextPropagator,
+    runtime,
+    trace::{BatchConfigBuilder, BatchSpanProcessor, Sampler, TracerProvider},
+    Resource,
+};
+use tracing_subscriber::{Layer, Registry};
+
+use crate::{
+    config::{OtelConfig, OtelProtocol},
+    error::{Error, Result},
+};
+
+/// Providers installed by [`layer`], shut down by [`shutdown`].
+static PROVIDERS: Mutex<Option<(TracerProvider, Option<SdkMeterProvider>)>> = Mutex::new(None);
+
+/// Create the layer exporting the spans of `service` as configured by
+/// `config`, and the meter provider when `config.metrics` is set.
+///
+/// Must be called in a Tokio runtime, the exports run on it.
+///
+/// # Errors
+/// When an exporter can't be created.
+pub(crate) fn layer(
+    config: &OtelConfig,
+    service: &str,
+    version: &str,
+) -> Result<Box<dyn Layer<Registry> + Send + Sync>> {
+    let resource = Resource::new([
+        KeyValue::new("service.name", service.to_string()),
+        KeyValue::new("service.version", version.to_string()),
+    ]);
+
+    let batch = BatchConfigBuilder::default()
+        .with_max_queue_size(config.batch.max_queue_size)
+        .with_max_export_batch_size(config.batch.max_export_batch_size)
+        .with_scheduled_delay(config.batch.scheduled_delay())
+        .with_max_export_timeout(config.timeout())
+        .build();
+    let processor = BatchSpanProcessor::builder(span_exporter(config)?, runtime::Tokio)
+        .with_batch_config(batch)
+        .build();
+    let sampler = Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(config.sampling_ratio)));
+    let tracer_provider = TracerProvider::builder()
+        .with_span_processor(processor)
+        .with_sampler(sampler)
+        .with_resource(resource.clone())
+        .build();
+
+    let meter_provider = if config.metrics {
+        let reader = PeriodicReader::builder(metric_exporter(config)?, runtime::Tokio)
+            .with_interval(config.metrics_interval())
+            .with_timeout(config.timeout())
+            .build();
+        let provider = SdkMeterProvider::builder()
+            .with_reader(reader)
+            .with_resource(resource)
+            .build();
+        global::set_meter_provider(provider.clone());
+        Some(provider)
+    } else {
+        None
+    };
+
+    let tracer = tracer_provider.tracer(service.to_string());
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    global::set_tracer_provider(tracer_provider.clone());
+    *PROVIDERS.lock().unwrap_or_else(|err| err.into_inner()) =
+        Some((tracer_provider, meter_provider));
+
+    Ok(Box::new(tracing_opentelemetry::layer().with_tracer(tracer)))
+}
+
+fn span_exporter(config: &OtelConfig) -> Result<SpanExporter> {
+    let exporter = match config.protocol {
+        OtelProtocol::Grpc => {
+            let builder = SpanExporter::builder()
+                .with_tonic()
+                .with_timeout(config.timeout());
+            match &config.endpoint {
+                Some(endpoint) => builder.with_endpoint(endpoint).build(),
+                None => builder.build(),
+            }
+        }
+        OtelProtocol::Http => {
+            let builder = SpanExporter::builder()
+                .with_http()
+                .with_protocol(Protocol::HttpBinary)
+                .with_timeout(config.timeout());
+            match &config.endpoint {
+                Some(endpoint) => builder
+                    .with_endpoint(http_endpoint(endpoint, "traces"))
+                    .build(),
+                None => builder.build(),
+            }
+        }
+    };
+    exporter.map_err(|err| Error::Message(format!("failed to create the span exporter: {err}")))
+}
+
+fn metric_exporter(config: &OtelConfig) -> Result<MetricExporter> {
+    let exporter = match config.protocol {
+        OtelProtocol::Grpc => {
+            let builder = MetricExporter::builder()
+                .with_tonic()
+                .with_timeout(config.timeout());
+            match &config.endpoint {
+                Some(endpoint) => builder.with_endpoint(endpoint).build(),
+                None => builder.build(),
+            }
+        }
+        OtelProtocol::Http => {
+            let builder = MetricExporter::builder()
+                .with_http()
+                .with_protocol(Protocol::HttpBinary)
+                .with_timeout(config.timeout());
+            match &config.endpoint {
+                Some(endpoint) => builder
+                    .with_endpoint(http_endpoint(endpoint, "metrics"))
+                    .build(),
+                None => builder.build(),
+            }
+        }
+    };
+    exporter.map_err(|err| Error::Message(format!("failed to create the metric exporter: {err}")))
+}
+
+/// The HTTP exporters take the URL of each signal, `{endpoint}/v1/traces`.
+fn http_endpoint(endpoint: &str, signal: &str) -> String {
+    format!("{}/v1/{signal}", endpoint.trim_end_matches('/'))
+}
+
+/// Export the spans and metrics not exported yet, and stop the exporters.
+pub(crate) fn shutdown() {
+    let providers = PROVIDERS
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .take();
+    let Some((tracer_provider, meter_provider)) = providers else {
+        return;
+    };
+
+    if let Err(err) = tracer_provider.shutdown() {
+        tracing::error!(err.msg = %err, "failed to export the remaining spans");
+    }
+    if let Some(Err(err)) = meter_provider.map(|provider| provider.shutdown()) {
+        tracing::error!(err.msg = %err, "failed to export the remaining metrics");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+        net::{TcpListener, TcpStream},
+    };
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+    use crate::config::OtelBatchConfig;
+
+    type Requests = Arc<Mutex<Vec<(String, Vec<u8>)>>>;
+
+    /// A collector stub, recording the path and body of the OTLP requests.
+    async fn collector() -> (String, Requests) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Requests::default();
+        tokio::spawn({
+            let requests = requests.clone();
+            async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    tokio::spawn(receive(stream, requests.clone()));
+                }
+            }
+        });
+        (endpoint, requests)
+    }
+
+    async fn receive(stream: TcpStream, requests: Requests) {
+        let mut stream = BufReader::new(stream);
+        loop {
+            let mut line = String::new();
+            if stream.read_line(&mut line).await.unwrap_or(0) == 0 {
+                return;
+            }
+            let path = line
+                .split_whitespace()
+                .nth(1)
+                .unwrap_or_default()
+                .to_string();
+
+            let mut length = 0;
+            loop {
+                let mut header = String::new();
+                stream.read_line(&mut header).await.unwrap();
+                match header.trim_end().split_once(':') {
+                    Some((name, value)) if name.eq_ignore_ascii_case("content-length") => {
+                        length = value.trim().parse().unwrap();
+                    }
+                    Some(_) => {}
+                    None => break,
+                }
+            }
+            let mut body = vec![0; length];
+            stream.read_exact(&mut body).await.unwrap();
+            requests.lock().unwrap().push((path, body));
+
+            let response = "HTTP/1.1 200 OK\r\ncontent-type: application/x-protobuf\r\n\
+                            content-length: 0\r\n\r\n";
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+    }
+
+    fn exported(requests: &Requests, path: &str) -> Vec<u8> {
+        requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(requested, _)| requested == path)
+            .flat_map(|(_, body)| body.clone())
+            .collect()
+    }
+
+    fn contains(body: &[u8], text: &str) -> bool {
+        body.windows(text.len())
+            .any(|window| window == text.as_bytes())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn exports_the_spans_and_metrics_to_the_collector() {
+        let (endpoint, requests) = collector().await;
+        let config = OtelConfig {
+            protocol: OtelProtocol::Http,
+            endpoint: Some(format!("{endpoint}/")),
+            timeout: 5_000,
+            filter: None,
+            sampling_ratio: 1.0,
+            batch: OtelBatchConfig::default(),
+            metrics: true,
+            metrics_interval: 60_000,
+        };
+
+        let subscriber = Registry::default().with(layer(&config, "insane-test", "1.2.3").unwrap());
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("checkout", order = 42).in_scope(|| tracing::info!("paid"));
+        });
+        global::meter("insane-test")
+            .u64_counter("orders_total")
+            .build()
+            .add(1, &[]);
+        // exported on shutdown, before the batch delay and metrics interval
+        tokio::task::spawn_blocking(shutdown).await.unwrap();
+
+        let traces = exported(&requests, "/v1/traces");
+        assert!(contains(&traces, "checkout"));
+        assert!(contains(&traces, "insane-test"));
+        assert!(contains(&traces, "1.2.3"));
+        assert!(contains(
+            &exported(&requests, "/v1/metrics"),
+            "orders_total"
+        ));
+    }
+}
@@ -0,0 +1,201 @@
+//! Redis connection pool, enabled with the `with-redis` feature.
+
+use bb8::ManageConnection;
+use redis::{aio::MultiplexedConnection, Client, ErrorKind, IntoConnectionInfo, RedisError};
+
+use crate::{
+    config::RedisConfig,
+    error::{Error, Result},
+};
+
+/// The pool of Redis connections shared by the application.
+pub type RedisPool = bb8::Pool<RedisConnectionManager>;
+
+/// Opens the connections of the [`RedisPool`] and checks them with a `PING`.
+#[derive(Debug, Clone)]
+pub struct RedisConnectionManager {
+    client: Client,
+}
+
+impl RedisConnectionManager {
+    /// Create a manager connecting to `info`, e.g. a `redis://` URI.
+    ///
+    /// # Errors
+    /// When the connection information is invalid.
+    pub fn new<T: IntoConnectionInfo>(info: T) -> Result<Self, RedisError> {
+        Ok(Self {
+            client: Client::open(info)?,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl ManageConnection for RedisConnectionManager {
+    type Connection = MultiplexedConnection;
+    type Error = RedisError;
+
+    async fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        self.client.get_multiplexed_async_connection().await
+    }
+
+    async fn is_valid(&self, conn: &mut Self::Connection) -> Result<(), Self::Error> {
+        let pong: String = redis::cmd("PING").query_async(conn).await?;
+        match pong.as_str() {
+            "PONG" => Ok(()),
+            _ => Err((ErrorKind::ResponseError, "unexpected PING reply").into()),
+        }
+    }
+
+    fn has_broken(&self, _conn: &mut Self::Connection) -> bool {
+        false
+    }
+}
+
+/// Create the connection pool and check it can reach the server, retrying
+/// `connect_retries` times with an exponential backoff.
+///
+/// # Errors
+/// When the URI is invalid, or the server is still unreachable after the
+/// last retry.
+pub async fn connect(config: &RedisConfig) -> Result<RedisPool> {
+    let manager = RedisConnectionManager::new(config.uri.expose())?;
+
+    let mut backoff = config.retry_backoff();
+    let mut attempt = 0;
+    // Check a direct connection first: the pool only reports a timeout and
+    // hides the reason the server could not be reached.
+    while let Err(err) = check(&manager, config).await {
+        if attempt >= config.connect_retries {
+            return Err(err);
+        }
+        attempt += 1;
+        tracing::warn!(
+            err.msg = %err,
+            attempt,
+            backoff = ?backoff,
+            "redis connection failed, retrying"
+        );
+        tokio::time::sleep(backoff).await;
+        backoff *= 2;
+    }
+
+    let mut builder = bb8::Pool::builder().connection_timeout(config.connect_timeout());
+    if let Some(min) = config.min_connections {
+        builder = builder.min_idle(Some(min));
+    }
+    if let Some(max) = config.max_connections {
+        builder = builder.max_size(max);
+    }
+    Ok(builder.build(manager).await?)
+}
+
+async fn check(manager: &RedisConnectionManager, config: &RedisConfig) -> Result<()> {
+    tokio::time::timeout(config.connect_timeout(), async {
+        let mut conn = manager.connect().await?;
+        manager.is_valid(&mut conn).await
+    })
+    .await
+    .map_err(|_| Error::Message("timed out connecting to redis".to_string()))?
+    .map_err(Error::from)
+}
+
+/// Send a `PING` to the server.
+///
+/// # Errors
+/// When no connection can be acquired or the server does not answer.
+pub async fn ping(pool: &RedisPool) -> Result<()> {
+    let mut conn = pool.get().await?;
+    let pong: String = redis::cmd("PING").query_async(&mut *conn).await?;
+    if pong == "PONG" {
+        Ok(())
+    } else {
+        Err(Error::Message(format!(
+            "unexpected redis PING reply: {pong}"
+        )))
+    }
+}
+
+/// Serve a Redis server stub on `listener`, answering `PING` with `PONG` and
+/// the other commands with `OK`, for the tests. Available to the other crates
+/// with the `testing` feature.
+#[cfg(any(test, feature = "testing"))]
+pub fn test_server(listener: std::net::TcpListener) {
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            std::thread::spawn(move || stub_connection(stream));
+        }
+    });
+}
+
+/// Answer the commands of a connection, each an array of bulk strings: `*2`,
+/// then `$4` `PING` and so on.
+#[cfg(any(test, feature = "testing"))]
+fn stub_connection(stream: std::net::TcpStream) -> std::io::Result<()> {
+    use std::io::{BufRead, BufReader, Write};
+
+    let mut writer = stream.try_clone()?;
+    let mut lines = BufReader::new(stream).lines();
+    while let Some(header) = lines.next().transpose()? {
+        let args = header.trim_start_matches('*').parse::<usize>().unwrap_or(0);
+        let mut command = Vec::with_capacity(args);
+        for _ in 0..args {
+            lines.next().transpose()?;
+            command.push(lines.next().transpose()?.unwrap_or_default());
+        }
+        let reply = match command.first() {
+            Some(name) if name.eq_ignore_ascii_case("PING") => "+PONG\r\n",
+            _ => "+OK\r\n",
+        };
+        writer.write_all(reply.as_bytes())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::TcpListener,
+        time::{Duration, Instant},
+    };
+
+    use super::*;
+
+    fn config(addr: std::net::SocketAddr, connect_retries: u32) -> RedisConfig {
+        RedisConfig {
+            uri: format!("redis://{addr}").into(),
+            connect_timeout: 1_000,
+            connect_retries,
+            retry_backoff: 20,
+            ..RedisConfig::default()
+        }
+    }
+
+    /// An address nothing listens on.
+    fn closed() -> std::net::SocketAddr {
+        TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn retries_until_the_server_answers() {
+        let addr = closed();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            test_server(TcpListener::bind(addr).unwrap());
+        });
+
+        let pool = connect(&config(addr, 5)).await.unwrap();
+        ping(&pool).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_the_last_retry() {
+        let started = Instant::now();
+
+        assert!(connect(&config(closed(), 2)).await.is_err());
+        // 20ms, then 40ms
+        assert!(started.elapsed() >= Duration::from_millis(60));
+    }
+}
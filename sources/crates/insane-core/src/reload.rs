@@ -0,0 +1,362 @@
+//! Live reload of the configuration.
+//!
+//! The [`ConfigReloader`] of the context loads the configuration files again
+//! through [`ConfigLoader`] on `SIGHUP` or, with `reload.watch`, when a file of
+//! the configuration folder changes. A configuration failing to load is logged
+//! and ignored, the previous one stays in use.
+//!
+//! The tracing filter is applied by the reloader itself. Servers subscribe to
+//! the changes and apply the parts they can change while running, the other
+//! parts need a restart.
+//!
+//! ```rust
+//! use insane_core::{context::Context, error::Result};
+//! use std::sync::Arc;
+//!
+//! #[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+//! struct MyConfig {
+//!     limit: u64,
+//! }
+//! impl insane_core::config::loader::Config for MyConfig {}
+//!
+//! async fn follow(context: Arc<Box<dyn Context>>) -> Result<()> {
+//!     let mut changes = context.reloader().subscribe();
+//!     while changes.changed().await.is_ok() {
+//!         let config = changes.borrow_and_update().section::<MyConfig>("my")?;
+//!         tracing::info!(limit = config.limit, "limit changed");
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use std::sync::{Arc, Mutex};
+
+use config::Config as ExtConfig;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::{mpsc, watch};
+
+use crate::{
+    config::{
+        loader::{self, Config, ConfigLoader},
+        InsaneConfig, ReloadConfig,
+    },
+    environment::Environment,
+    error::{Error, Result},
+    shutdown::ShutdownToken,
+    traces,
+};
+
+/// A configuration loaded by the [`ConfigReloader`].
+#[derive(Debug)]
+pub struct LoadedConfig {
+    /// The core configuration.
+    pub config: InsaneConfig,
+    raw: ExtConfig,
+    values: serde_json::Value,
+}
+
+impl LoadedConfig {
+    /// Get the section `key` of the configuration, like
+    /// [`ConfigLoader::from_key`].
+    ///
+    /// # Errors
+    /// When the section is missing or invalid.
+    pub fn section<C: Config>(&self, key: &str) -> Result<C> {
+        Ok(self.raw.get::<C>(key)?)
+    }
+}
+
+/// Checks a section of a loaded configuration, see
+/// [`ConfigReloader::check_section`].
+type SectionCheck = fn(&ExtConfig, &str) -> Result<()>;
+
+/// Loads the configuration again and publishes it to the subscribers when it
+/// changed.
+#[derive(Debug)]
+pub struct ConfigReloader {
+    environment: Environment,
+    app_name: String,
+    sender: watch::Sender<Arc<LoadedConfig>>,
+    sections: Mutex<Vec<(String, SectionCheck)>>,
+}
+
+impl ConfigReloader {
+    /// Create the reloader of the application, starting from `config`.
+    ///
+    /// # Errors
+    /// When the configuration files can't be loaded.
+    pub fn new(environment: &Environment, config: &InsaneConfig) -> Result<Self> {
+        let loaded = LoadedConfig {
+            config: config.clone(),
+            ..load(environment, &config.application_name)?
+        };
+        Ok(Self {
+            environment: environment.clone(),
+            app_name: config.application_name.clone(),
+            sender: watch::Sender::new(Arc::new(loaded)),
+            sections: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Refuse the configurations where the section `key` is not a valid `C`,
+    /// like the core configuration, so that a server reading it keeps the
+    /// previous configuration with the others.
+    pub fn check_section<C: Config>(&self, key: &str) {
+        let check: SectionCheck = |raw, key| {
+            raw.get::<C>(key)?;
+            Ok(())
+        };
+        self.sections
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .push((key.to_string(), check));
+    }
+
+    /// The last configuration loaded.
+    #[must_use]
+    pub fn current(&self) -> Arc<LoadedConfig> {
+        self.sender.borrow().clone()
+    }
+
+    /// Receive the configurations loaded from now on.
+    #[must_use]
+    pub fn subscribe(&self) -> watch::Receiver<Arc<LoadedConfig>> {
+        self.sender.subscribe()
+    }
+
+    /// Load the configuration and publish it when it changed. Returns `true`
+    /// when it changed.
+    ///
+    /// # Errors
+    /// When the configuration can't be loaded or a section checked with
+    /// [`ConfigReloader::check_section`] is invalid, nothing is published
+    /// then.
+    pub fn reload(&self) -> Result<bool> {
+        let loaded = load(&self.environment, &self.app_name)?;
+        let sections = self
+            .sections
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone();
+        for (key, check) in sections {
+            check(&loaded.raw, &key)
+                .map_err(|err| Error::Message(format!("invalid `{key}` configuration: {err}")))?;
+        }
+
+        let previous = self.current();
+        if previous.values == loaded.values {
+            return Ok(false);
+        }
+
+        if traces::filters(&previous.config.tracing) != traces::filters(&loaded.config.tracing) {
+            traces::reload_filter(&loaded.config.tracing)?;
+        }
+        self.sender.send_replace(Arc::new(loaded));
+        Ok(true)
+    }
+}
+
+fn load(environment: &Environment, app_name: &str) -> Result<LoadedConfig> {
+    let raw = InsaneConfig::raw_from_folder(environment, app_name, None)?;
+    Ok(LoadedConfig {
+        config: raw.clone().try_deserialize()?,
+        values: raw.clone().try_deserialize()?,
+        raw,
+    })
+}
+
+/// Reload the configuration on `SIGHUP` and, with `reload.watch`, when a file
+/// of the configuration folder changes, until `shutdown` is triggered.
+pub async fn watch(reloader: Arc<ConfigReloader>, config: ReloadConfig, shutdown: ShutdownToken) {
+    let (sender, mut changes) = mpsc::unbounded_channel();
+
+    let _watcher = if config.watch {
+        match watch_files(sender.clone()) {
+            Ok(watcher) => Some(watcher),
+            Err(err) => {
+                tracing::error!(err.msg = %err, "failed to watch the configuration files");
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let hangups = tokio::spawn(hangups(sender));
+
+    loop {
+        tokio::select! {
+            () = shutdown.triggered() => break,
+            change = changes.recv() => if change.is_none() { break },
+        }
+
+        // wait for the other writes of the same change
+        tokio::time::sleep(config.debounce()).await;
+        while changes.try_recv().is_ok() {}
+
+        match reloader.reload() {
+            Ok(true) => tracing::info!("configuration reloaded"),
+            Ok(false) => tracing::debug!("configuration unchanged"),
+            Err(err) => {
+                tracing::error!(err.msg = %err, "failed to reload the configuration, keeping the previous one");
+            }
+        }
+    }
+
+    hangups.abort();
+}
+
+/// Send a change for every event on the configuration folder.
+fn watch_files(sender: mpsc::UnboundedSender<()>) -> Result<RecommendedWatcher> {
+    let folder = loader::config_dir();
+
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        if event.is_ok_and(|event| !event.kind.is_access()) {
+            let _ = sender.send(());
+        }
+    })
+    .map_err(Error::wrap)?;
+    watcher
+        .watch(&folder, RecursiveMode::NonRecursive)
+        .map_err(Error::wrap)?;
+
+    tracing::info!(folder = %folder.display(), "watching the configuration files");
+    Ok(watcher)
+}
+
+/// Send a change for every `SIGHUP`.
+#[cfg(unix)]
+async fn hangups(sender: mpsc::UnboundedSender<()>) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    match signal(SignalKind::hangup()) {
+        Ok(mut signal) => {
+            while signal.recv().await.is_some() {
+                tracing::info!("SIGHUP received, reloading the configuration");
+                if sender.send(()).is_err() {
+                    break;
+                }
+            }
+        }
+        Err(err) => tracing::error!(err.msg = %err, "failed to install SIGHUP handler"),
+    }
+}
+
+#[cfg(not(unix))]
+async fn hangups(_sender: mpsc::UnboundedSender<()>) {}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::*;
+    use crate::config::{loader::TestConfigDir, CacheConfig};
+
+    #[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+    struct MyConfig {
+        limit: u64,
+    }
+
+    impl Config for MyConfig {}
+
+    struct Folder(PathBuf);
+
+    impl Folder {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("insane-reload-{name}-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&path);
+            std::fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+
+        fn write(&self, content: &str) {
+            std::fs::write(self.0.join("test-reload_app.yaml"), content).unwrap();
+        }
+    }
+
+    impl Drop for Folder {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn reloader(folder: &Path) -> ConfigReloader {
+        let config =
+            InsaneConfig::from_folder(&Environment::Test, "reload_app", Some(folder)).unwrap();
+        ConfigReloader::new(&Environment::Test, &config).unwrap()
+    }
+
+    fn capacity(reloader: &ConfigReloader) -> usize {
+        match reloader.current().config.cache {
+            CacheConfig::Memory { capacity } => capacity,
+            _ => panic!("not a memory cache"),
+        }
+    }
+
+    const CACHE: &str = "cache:\n  backend: memory\n  capacity: 10\nmy:\n  limit: 1\n";
+
+    #[test]
+    fn publishes_the_changes() {
+        let folder = Folder::new("changes");
+        folder.write(CACHE);
+        let _config_dir = TestConfigDir::new(&folder.0);
+        let reloader = reloader(&folder.0);
+        let mut changes = reloader.subscribe();
+
+        assert!(!reloader.reload().unwrap());
+        assert!(!changes.has_changed().unwrap());
+
+        folder.write(&CACHE.replace("capacity: 10", "capacity: 20"));
+        assert!(reloader.reload().unwrap());
+        assert!(changes.has_changed().unwrap());
+        assert_eq!(capacity(&reloader), 20);
+        assert_eq!(
+            changes
+                .borrow_and_update()
+                .section::<MyConfig>("my")
+                .unwrap()
+                .limit,
+            1
+        );
+    }
+
+    #[test]
+    fn keeps_the_previous_configuration_when_invalid() {
+        let folder = Folder::new("invalid");
+        folder.write(CACHE);
+        let _config_dir = TestConfigDir::new(&folder.0);
+        let reloader = reloader(&folder.0);
+        let changes = reloader.subscribe();
+
+        folder.write("cache: [\n");
+        assert!(reloader.reload().is_err());
+        folder.write("cache:\n  backend: disk\n");
+        assert!(reloader.reload().is_err());
+
+        assert!(!changes.has_changed().unwrap());
+        assert_eq!(capacity(&reloader), 10);
+    }
+
+    #[test]
+    fn checks_the_registered_sections() {
+        let folder = Folder::new("sections");
+        folder.write(CACHE);
+        let _config_dir = TestConfigDir::new(&folder.0);
+        let reloader = reloader(&folder.0);
+        reloader.check_section::<MyConfig>("my");
+        let changes = reloader.subscribe();
+
+        folder.write(
+            &CACHE
+                .replace("capacity: 10", "capacity: 20")
+                .replace("limit: 1", "limit: x"),
+        );
+        let err = reloader.reload().unwrap_err();
+        assert!(
+            err.to_string().starts_with("invalid `my` configuration"),
+            "{err}"
+        );
+        assert!(!changes.has_changed().unwrap());
+        assert_eq!(capacity(&reloader), 10);
+    }
+}
@@ -1,7 +1,39 @@
 use std::sync::Arc;
 
+use tokio_util::sync::CancellationToken;
+
 // use crate::context::ServerContext;
-use crate::{context::Context, error::Result, hook::Initializer};
+use crate::{context::Context, error::Result, hook::Initializer, shutdown::ShutdownToken};
+
+/// Tells [`crate::boot_loader::boot_app`] a server is ready, e.g. listening.
+/// The `after_start` initializers run once every server is ready.
+#[derive(Debug, Clone, Default)]
+pub struct Readiness {
+    inner: CancellationToken,
+}
+
+impl Readiness {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Report the server as ready. Calling it again, e.g. after a restart,
+    /// does nothing.
+    pub fn ready(&self) {
+        self.inner.cancel();
+    }
+
+    #[must_use]
+    pub fn is_ready(&self) -> bool {
+        self.inner.is_cancelled()
+    }
+
+    /// Wait until the server is ready.
+    pub async fn wait(&self) {
+        self.inner.cancelled().await;
+    }
+}
 
 #[async_trait::async_trait]
 pub trait Server: Sync + Send {
@@ -11,8 +43,25 @@ pub trait Server: Sync + Send {
     /// Occurs after the app's `before_run`.
     /// Use this to for one-time initializations, load caches, perform web
     /// hooks, etc.
-    async fn serve(&self, _app_context: Arc<Box<dyn Context>>) -> Result<()>;
+    ///
+    /// The server calls [`Readiness::ready`] once it accepts work, e.g. when
+    /// its listener is bound.
+    ///
+    /// The server must stop accepting new work and return once `shutdown` is
+    /// triggered. Work still running after the configured
+    /// `servers.shutdown_timeout` is aborted.
+    async fn serve(
+        &self,
+        _app_context: Arc<Box<dyn Context>>,
+        shutdown: ShutdownToken,
+        ready: Readiness,
+    ) -> Result<()>;
 
+    /// Occurs after `serve` returned (or was aborted) during the application
+    /// shutdown. Use this to release resources owned by the server.
+    async fn shutdown(&self, _app_context: Arc<Box<dyn Context>>) -> Result<()> {
+        Ok(())
+    }
 
     async fn enable(&self, _context: Arc<Box<dyn Context>>) -> Result<bool> {
         Ok(false)
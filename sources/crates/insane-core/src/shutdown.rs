@@ -0,0 +1,80 @@
+//! Coordinated shutdown for the servers started by
+//! [`crate::boot_loader::boot_app`].
+//!
+//! A single [`ShutdownToken`] is created at boot and handed to every
+//! [`crate::server::Server::serve`] call. Once SIGTERM/SIGINT is received the
+//! token is triggered and each server is expected to stop accepting new work
+//! and drain the in-flight one.
+
+use tokio_util::sync::CancellationToken;
+
+/// A cloneable handle that signals servers to stop.
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownToken {
+    inner: CancellationToken,
+}
+
+impl ShutdownToken {
+    /// Create a new, not yet triggered, token.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request the shutdown of every holder of this token.
+    pub fn trigger(&self) {
+        self.inner.cancel();
+    }
+
+    /// Return `true` when the shutdown was requested.
+    #[must_use]
+    pub fn is_triggered(&self) -> bool {
+        self.inner.is_cancelled()
+    }
+
+    /// Wait until the shutdown is requested.
+    pub async fn triggered(&self) {
+        self.inner.cancelled().await;
+    }
+
+    /// Create a token that is triggered with this one, but can also be
+    /// triggered on its own without affecting the parent.
+    #[must_use]
+    pub fn child(&self) -> Self {
+        Self {
+            inner: self.inner.child_token(),
+        }
+    }
+}
+
+/// Wait for a termination signal (`SIGINT` or `SIGTERM` on unix, `Ctrl+C`
+/// elsewhere).
+pub async fn signal() {
+    let ctrl_c = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            tracing::error!(err.msg = %err, "failed to install Ctrl+C handler");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(err) => {
+                tracing::error!(err.msg = %err, "failed to install SIGTERM handler");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {},
+        () = terminate => {},
+    }
+}
@@ -8,9 +8,10 @@ use std::{fs::File, path::Path, time::Duration};
 
 use sea_orm_migration::MigratorTrait;
 
-use super::error::Error;
 use super::error::Result as InsaneResult;
+use super::error::{Error, ErrorCode};
 use crate::config::SqlConfig;
+use crate::environment::Environment;
 use crate::hook::Hooks;
 
 lazy_static! {
@@ -151,7 +152,7 @@ async fn create_postgres_database(
     db: &DatabaseConnection,
 ) -> Result<(), sea_orm::DbErr> {
     let with_options = std::env::var("LOCO_POSTGRES_TABLE_OPTIONS")
-        .map_or_else(|_| "ENCODING='UTF8'".to_string(), |options| options);
+        .unwrap_or_else(|_| "ENCODING='UTF8'".to_string());
 
     let query = format!("CREATE DATABASE {table_name} WITH {with_options}");
     tracing::info!(query, "creating postgres table");
@@ -165,7 +166,7 @@ async fn create_postgres_database(
 }
 
 pub async fn connect(config: &SqlConfig) -> InsaneResult<sea_orm::DatabaseConnection> {
-    let application_url = config.uri.to_string();
+    let application_url = config.uri.expose().to_string();
 
     let mut application_options = ConnectOptions::new(application_url);
 
@@ -220,29 +221,87 @@ pub async fn verify_access(db: &DatabaseConnection) -> InsaneResult<()> {
 
 /// converge database logic
 ///
+/// The `dangerously_*` flags are refused in production, unless
+/// `allow_dangerous_in_production` is set.
+///
 /// # Errors
 ///
 ///  an `AppResult`, which is an alias for `Result<(), AppError>`. It may
 /// return an `AppError` variant representing different database operation
 /// failures.
 pub async fn prepare<H: Hooks, M: MigratorTrait>(
-  db: &DatabaseConnection,
-  config: &SqlConfig,
+    db: &DatabaseConnection,
+    config: &SqlConfig,
+    environment: &Environment,
 ) -> InsaneResult<()> {
-  if config.dangerously_recreate.unwrap_or(false) {
-      tracing::info!("recreating schema");
-      reset::<M>(db).await?;
-      return Ok(());
-  }
-
-  if config.auto_migrate.unwrap_or(false) {
-    tracing::info!("auto migrating");
-      migrate::<M>(db).await?;
-  }
-
-  if config.dangerously_truncate.unwrap_or(false) {
-    tracing::info!("truncating tables");
-      H::truncate(db).await?;
-  }
-  Ok(())
+    if config.dangerously_recreate.unwrap_or(false) {
+        allow_dangerous(config, environment, "dangerously_recreate")?;
+        tracing::info!("recreating schema");
+        reset::<M>(db).await?;
+        return Ok(());
+    }
+
+    if config.auto_migrate.unwrap_or(false) {
+        tracing::info!("auto migrating");
+        migrate::<M>(db).await?;
+    }
+
+    if config.dangerously_truncate.unwrap_or(false) {
+        allow_dangerous(config, environment, "dangerously_truncate")?;
+        tracing::info!("truncating tables");
+        H::truncate(db).await?;
+    }
+    Ok(())
+}
+
+/// Check the dangerous `flag` may run in `environment`, logging its use.
+fn allow_dangerous(config: &SqlConfig, environment: &Environment, flag: &str) -> InsaneResult<()> {
+    if environment.is_production() && !config.allow_dangerous_in_production.unwrap_or(false) {
+        return Err(Error::new(
+            ErrorCode::CONFIG,
+            format!(
+                "`sql.{flag}` is refused in {environment}, set `sql.allow_dangerous_in_production` to allow it"
+            ),
+        ));
+    }
+    tracing::warn!(%environment, flag, "running a dangerous database operation");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowed(config: &SqlConfig, environment: &Environment) -> bool {
+        allow_dangerous(config, environment, "dangerously_truncate").is_ok()
+    }
+
+    #[test]
+    fn refuses_the_dangerous_flags_in_production() {
+        let config = SqlConfig::default();
+        let err =
+            allow_dangerous(&config, &Environment::Production, "dangerously_truncate").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("`sql.dangerously_truncate` is refused"));
+        assert_eq!(err.code(), ErrorCode::CONFIG);
+
+        assert!(allowed(&config, &Environment::Development));
+        assert!(allowed(&config, &Environment::Test));
+    }
+
+    #[test]
+    fn allows_the_dangerous_flags_in_production_when_overridden() {
+        let config = SqlConfig {
+            allow_dangerous_in_production: Some(true),
+            ..SqlConfig::default()
+        };
+        assert!(allowed(&config, &Environment::Production));
+
+        let config = SqlConfig {
+            allow_dangerous_in_production: Some(false),
+            ..SqlConfig::default()
+        };
+        assert!(!allowed(&config, &Environment::Production));
+    }
 }
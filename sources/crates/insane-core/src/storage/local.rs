@@ -0,0 +1,290 @@
+use std::{
+    ffi::OsString,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use bytes::Bytes;
+use futures_util::StreamExt;
+use tokio::{fs, io::AsyncWriteExt};
+use tokio_util::io::ReaderStream;
+
+use super::{normalize, ByteStream, Storage};
+use crate::error::{Error, Result};
+
+/// Suffix of the files being written, hidden from [`LocalStorage::list`].
+const PARTIAL_SUFFIX: &str = ".partial";
+
+/// Tells apart the files written at the same time by this process.
+static WRITES: AtomicU64 = AtomicU64::new(0);
+
+/// A storage keeping the files under a folder of the local disk.
+#[derive(Debug, Clone)]
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    /// Create a storage under `root`. The folder is created with the first
+    /// file written.
+    #[must_use]
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path(&self, path: &str) -> Result<PathBuf> {
+        Ok(self.root.join(normalize(path)?))
+    }
+}
+
+#[async_trait::async_trait]
+impl Storage for LocalStorage {
+    /// Write to a temporary file renamed once complete, so readers never see
+    /// a partial file.
+    async fn put(&self, path: &str, content: Bytes) -> Result<()> {
+        let path = self.path(path)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let tmp = partial_path(&path);
+        let written = async {
+            let mut file = fs::File::create(&tmp).await?;
+            file.write_all(&content).await?;
+            file.sync_all().await?;
+            fs::rename(&tmp, &path).await
+        }
+        .await;
+        if let Err(err) = written {
+            let _ = fs::remove_file(&tmp).await;
+            return Err(err.into());
+        }
+        Ok(())
+    }
+
+    async fn get(&self, path: &str) -> Result<Option<Bytes>> {
+        match fs::read(self.path(path)?).await {
+            Ok(content) => Ok(Some(content.into())),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn stream(&self, path: &str) -> Result<Option<ByteStream>> {
+        match fs::File::open(self.path(path)?).await {
+            Ok(file) => Ok(Some(
+                ReaderStream::new(file)
+                    .map(|chunk| chunk.map_err(Error::from))
+                    .boxed(),
+            )),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn delete(&self, path: &str) -> Result<()> {
+        match fs::remove_file(self.path(path)?).await {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let prefix = prefix.trim_start_matches('/');
+        let mut paths = Vec::new();
+        let mut folders = vec![self.root.clone()];
+        while let Some(folder) = folders.pop() {
+            let mut entries = match fs::read_dir(&folder).await {
+                Ok(entries) => entries,
+                Err(err) if err.kind() == ErrorKind::NotFound => continue,
+                Err(err) => return Err(err.into()),
+            };
+            while let Some(entry) = entries.next_entry().await? {
+                if entry.file_type().await?.is_dir() {
+                    folders.push(entry.path());
+                } else if let Some(path) = relative(&self.root, &entry.path()) {
+                    if path.starts_with(prefix) && !is_partial(&entry.file_name()) {
+                        paths.push(path);
+                    }
+                }
+            }
+        }
+        paths.sort();
+        Ok(paths)
+    }
+}
+
+/// The temporary file written before being renamed to `path`:
+/// `.<name>.<pid>-<write>.partial`, unique to each write.
+fn partial_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(format!(
+        ".{}-{}{PARTIAL_SUFFIX}",
+        std::process::id(),
+        WRITES.fetch_add(1, Ordering::Relaxed)
+    ));
+    path.with_file_name(name)
+}
+
+/// Whether `name` is a temporary file of [`partial_path`].
+fn is_partial(name: &std::ffi::OsStr) -> bool {
+    let Some(name) = name.to_str() else {
+        return false;
+    };
+    let Some(name) = name
+        .strip_prefix('.')
+        .and_then(|name| name.strip_suffix(PARTIAL_SUFFIX))
+    else {
+        return false;
+    };
+    let Some((_, write)) = name.rsplit_once('.') else {
+        return false;
+    };
+    write.split_once('-').is_some_and(|(pid, n)| {
+        !pid.is_empty()
+            && !n.is_empty()
+            && pid.bytes().all(|b| b.is_ascii_digit())
+            && n.bytes().all(|b| b.is_ascii_digit())
+    })
+}
+
+/// The storage path of a file under `root`, `/` separated.
+fn relative(root: &Path, path: &Path) -> Option<String> {
+    let segments = path
+        .strip_prefix(root)
+        .ok()?
+        .components()
+        .map(|component| component.as_os_str().to_str())
+        .collect::<Option<Vec<_>>>()?;
+    Some(segments.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn storage(name: &str) -> (LocalStorage, PathBuf) {
+        let root =
+            std::env::temp_dir().join(format!("insane-storage-{name}-{}", std::process::id()));
+        (LocalStorage::new(&root), root)
+    }
+
+    #[tokio::test]
+    async fn writes_reads_and_deletes_the_files() {
+        let (storage, root) = storage("files");
+
+        assert_eq!(storage.get("reports/2024-01.csv").await.unwrap(), None);
+        storage
+            .put("/reports/2024-01.csv", Bytes::from("a,b"))
+            .await
+            .unwrap();
+        storage
+            .put("reports/2024-01.csv", Bytes::from("a,b,c"))
+            .await
+            .unwrap();
+        storage
+            .put("avatars/42.png", Bytes::from("png"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            storage.get("reports/2024-01.csv").await.unwrap(),
+            Some(Bytes::from("a,b,c"))
+        );
+        let chunks = storage
+            .stream("avatars/42.png")
+            .await
+            .unwrap()
+            .unwrap()
+            .map(|chunk| chunk.unwrap())
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(chunks.concat(), b"png");
+        assert_eq!(
+            storage.list("").await.unwrap(),
+            ["avatars/42.png", "reports/2024-01.csv"]
+        );
+        assert_eq!(
+            storage.list("/reports/").await.unwrap(),
+            ["reports/2024-01.csv"]
+        );
+
+        storage.delete("avatars/42.png").await.unwrap();
+        storage.delete("avatars/42.png").await.unwrap();
+        assert!(storage.stream("avatars/42.png").await.unwrap().is_none());
+        fs::remove_dir_all(&root).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn renames_the_partial_files_once_written() {
+        let (storage, root) = storage("partial");
+
+        storage
+            .put("reports/2024-01.csv", Bytes::from("a,b"))
+            .await
+            .unwrap();
+        storage
+            .put("reports/draft.partial", Bytes::from("a"))
+            .await
+            .unwrap();
+        assert_eq!(
+            storage.list("reports/").await.unwrap(),
+            ["reports/2024-01.csv", "reports/draft.partial"]
+        );
+
+        // left behind by a write that didn't complete
+        fs::write(root.join("reports/.2024-02.csv.12-3.partial"), "a,")
+            .await
+            .unwrap();
+        assert_eq!(
+            storage.list("reports/").await.unwrap(),
+            ["reports/2024-01.csv", "reports/draft.partial"]
+        );
+        assert_eq!(storage.get("reports/2024-02.csv").await.unwrap(), None);
+        fs::remove_dir_all(&root).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn writes_the_same_file_concurrently() {
+        let (storage, root) = storage("concurrent");
+        let contents = (0..16u8).map(|i| vec![i; 64 * 1024]).collect::<Vec<_>>();
+
+        let puts = contents
+            .iter()
+            .map(|content| storage.put("reports/2024-01.csv", Bytes::from(content.clone())));
+        for put in futures_util::future::join_all(puts).await {
+            put.unwrap();
+        }
+
+        // one of the writes, whole
+        let written = storage.get("reports/2024-01.csv").await.unwrap().unwrap();
+        assert!(contents.iter().any(|content| written == content.as_slice()));
+        assert_eq!(storage.list("").await.unwrap(), ["reports/2024-01.csv"]);
+        let mut entries = fs::read_dir(root.join("reports")).await.unwrap();
+        let mut files = 0;
+        while entries.next_entry().await.unwrap().is_some() {
+            files += 1;
+        }
+        assert_eq!(files, 1);
+        fs::remove_dir_all(&root).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn stays_under_the_root() {
+        let (storage, root) = storage("root");
+
+        for path in [
+            "../escape.txt",
+            "reports/../../escape.txt",
+            "./escape.txt",
+            "..\\escape",
+        ] {
+            assert!(storage.put(path, Bytes::from("x")).await.is_err());
+            assert!(storage.get(path).await.is_err());
+            assert!(storage.delete(path).await.is_err());
+        }
+        assert!(!root.exists());
+        assert!(!root.parent().unwrap().join("escape.txt").exists());
+    }
+}
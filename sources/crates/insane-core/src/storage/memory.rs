@@ -0,0 +1,135 @@
+use std::{
+    collections::BTreeMap,
+    sync::{PoisonError, RwLock},
+};
+
+use bytes::Bytes;
+use futures_util::{stream, StreamExt};
+
+use super::{normalize, ByteStream, Storage};
+use crate::error::Result;
+
+/// A storage keeping the files in memory, for tests.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    files: RwLock<BTreeMap<String, Bytes>>,
+}
+
+impl MemoryStorage {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl Storage for MemoryStorage {
+    async fn put(&self, path: &str, content: Bytes) -> Result<()> {
+        let path = normalize(path)?;
+        self.files
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(path, content);
+        Ok(())
+    }
+
+    async fn get(&self, path: &str) -> Result<Option<Bytes>> {
+        let path = normalize(path)?;
+        Ok(self
+            .files
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&path)
+            .cloned())
+    }
+
+    async fn stream(&self, path: &str) -> Result<Option<ByteStream>> {
+        Ok(self
+            .get(path)
+            .await?
+            .map(|content| stream::once(async { Ok(content) }).boxed()))
+    }
+
+    async fn delete(&self, path: &str) -> Result<()> {
+        let path = normalize(path)?;
+        self.files
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&path);
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let prefix = prefix.trim_start_matches('/');
+        Ok(self
+            .files
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .keys()
+            .filter(|path| path.starts_with(prefix))
+            .cloned()
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn writes_reads_and_deletes_the_files() {
+        let storage = MemoryStorage::new();
+
+        assert_eq!(storage.get("reports/2024-01.csv").await.unwrap(), None);
+        storage
+            .put("/reports/2024-01.csv", Bytes::from("a,b"))
+            .await
+            .unwrap();
+        storage
+            .put("reports/2024-01.csv", Bytes::from("a,b,c"))
+            .await
+            .unwrap();
+        storage
+            .put("avatars/42.png", Bytes::from("png"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            storage.get("reports//2024-01.csv").await.unwrap(),
+            Some(Bytes::from("a,b,c"))
+        );
+        let chunks = storage
+            .stream("avatars/42.png")
+            .await
+            .unwrap()
+            .unwrap()
+            .map(|chunk| chunk.unwrap())
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(chunks.concat(), b"png");
+        assert_eq!(
+            storage.list("").await.unwrap(),
+            ["avatars/42.png", "reports/2024-01.csv"]
+        );
+        assert_eq!(
+            storage.list("/reports/").await.unwrap(),
+            ["reports/2024-01.csv"]
+        );
+
+        storage.delete("avatars/42.png").await.unwrap();
+        storage.delete("avatars/42.png").await.unwrap();
+        assert!(storage.stream("avatars/42.png").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn rejects_the_invalid_paths() {
+        let storage = MemoryStorage::new();
+
+        for path in ["", "../escape.txt", "./escape.txt", "..\\escape"] {
+            assert!(storage.put(path, Bytes::from("x")).await.is_err());
+            assert!(storage.get(path).await.is_err());
+            assert!(storage.delete(path).await.is_err());
+        }
+        assert!(storage.list("").await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,118 @@
+//! File storage shared by the application, see [`Context::storage`].
+//!
+//! The driver is selected by the `storage` configuration, see
+//! [`StorageConfig`]. Files are addressed by `/` separated paths relative to
+//! the root of the storage, such as `avatars/42.png`.
+//!
+//! ```rust
+//! use insane_core::{error::Result, storage::Storage};
+//!
+//! async fn archive(storage: &dyn Storage, report: Vec<u8>) -> Result<Vec<String>> {
+//!     storage.put("reports/2024-01.csv", report.into()).await?;
+//!     storage.list("reports/").await
+//! }
+//! ```
+//!
+//! [`Context::storage`]: crate::context::Context::storage
+
+mod local;
+mod memory;
+#[cfg(feature = "with-s3")]
+mod s3;
+
+use std::{fmt::Debug, sync::Arc};
+
+use bytes::Bytes;
+use futures_util::stream::BoxStream;
+
+pub use self::local::LocalStorage;
+pub use self::memory::MemoryStorage;
+#[cfg(feature = "with-s3")]
+pub use self::s3::S3Storage;
+use crate::{
+    config::StorageConfig,
+    error::{Error, Result},
+};
+
+/// The content of a file, read in chunks.
+pub type ByteStream = BoxStream<'static, Result<Bytes>>;
+
+/// A storage driver.
+#[async_trait::async_trait]
+pub trait Storage: Send + Sync + Debug {
+    /// Write `content` to `path`, replacing the existing file.
+    async fn put(&self, path: &str, content: Bytes) -> Result<()>;
+
+    /// Read the file at `path`, `None` when missing.
+    async fn get(&self, path: &str) -> Result<Option<Bytes>>;
+
+    /// Read the file at `path` in chunks, `None` when missing.
+    async fn stream(&self, path: &str) -> Result<Option<ByteStream>>;
+
+    /// Remove the file at `path`. Removing a missing file is not an error.
+    async fn delete(&self, path: &str) -> Result<()>;
+
+    /// Paths of the files starting with `prefix`, sorted.
+    async fn list(&self, prefix: &str) -> Result<Vec<String>>;
+}
+
+/// Create the storage selected by the configuration.
+///
+/// # Errors
+/// When the driver cannot be configured.
+pub fn create(config: &StorageConfig) -> Result<Arc<dyn Storage>> {
+    Ok(match config {
+        StorageConfig::Local { root } => Arc::new(LocalStorage::new(root)),
+        StorageConfig::Memory => Arc::new(MemoryStorage::new()),
+        #[cfg(feature = "with-s3")]
+        StorageConfig::S3(config) => Arc::new(S3Storage::new(config)?),
+    })
+}
+
+/// Check `path` is relative and stays inside the storage, and remove the
+/// leading `/` and empty segments.
+///
+/// # Errors
+/// When `path` is empty or has `.` or `..` segments.
+pub fn normalize(path: &str) -> Result<String> {
+    let mut segments = Vec::new();
+    for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+        if segment == "." || segment == ".." || segment.contains('\\') {
+            return Err(Error::Message(format!("invalid storage path `{path}`")));
+        }
+        segments.push(segment);
+    }
+    if segments.is_empty() {
+        return Err(Error::Message(format!("invalid storage path `{path}`")));
+    }
+    Ok(segments.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_the_paths() {
+        assert_eq!(normalize("avatars/42.png").unwrap(), "avatars/42.png");
+        assert_eq!(normalize("/avatars//42.png/").unwrap(), "avatars/42.png");
+        assert_eq!(normalize("..avatars/42..png").unwrap(), "..avatars/42..png");
+    }
+
+    #[test]
+    fn rejects_the_paths_leaving_the_storage() {
+        for path in [
+            "",
+            "/",
+            "../secret",
+            "avatars/../../secret",
+            "./42.png",
+            "a\\..\\b",
+        ] {
+            assert_eq!(
+                normalize(path).unwrap_err().to_string(),
+                format!("invalid storage path `{path}`")
+            );
+        }
+    }
+}
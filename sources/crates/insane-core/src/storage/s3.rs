@@ -0,0 +1,330 @@
+use bytes::Bytes;
+use futures_util::{StreamExt, TryStreamExt};
+use object_store::{
+    aws::{AmazonS3, AmazonS3Builder},
+    path::Path,
+    ObjectStore,
+};
+
+use super::{normalize, ByteStream, Storage};
+use crate::{
+    config::storage::S3Config,
+    error::{Error, Result},
+};
+
+/// A storage keeping the files in an S3-compatible bucket.
+#[derive(Debug)]
+pub struct S3Storage {
+    store: AmazonS3,
+    prefix: String,
+}
+
+impl S3Storage {
+    /// Create the client of the configured bucket.
+    ///
+    /// # Errors
+    /// When the configuration is invalid.
+    pub fn new(config: &S3Config) -> Result<Self> {
+        let mut builder = AmazonS3Builder::from_env()
+            .with_bucket_name(&config.bucket)
+            .with_region(&config.region)
+            .with_virtual_hosted_style_request(!config.path_style);
+        if let Some(endpoint) = &config.endpoint {
+            builder = builder
+                .with_endpoint(endpoint)
+                .with_allow_http(endpoint.starts_with("http://"));
+        }
+        if let Some(access_key_id) = &config.access_key_id {
+            builder = builder.with_access_key_id(access_key_id);
+        }
+        if let Some(secret_access_key) = &config.secret_access_key {
+            builder = builder.with_secret_access_key(secret_access_key.expose());
+        }
+
+        let prefix = config
+            .prefix
+            .as_deref()
+            .map(|prefix| prefix.trim_matches('/'))
+            .filter(|prefix| !prefix.is_empty())
+            .map(|prefix| format!("{prefix}/"))
+            .unwrap_or_default();
+
+        Ok(Self {
+            store: builder.build()?,
+            prefix,
+        })
+    }
+
+    fn path(&self, path: &str) -> Result<Path> {
+        Ok(Path::from(format!("{}{}", self.prefix, normalize(path)?)))
+    }
+}
+
+#[async_trait::async_trait]
+impl Storage for S3Storage {
+    async fn put(&self, path: &str, content: Bytes) -> Result<()> {
+        self.store.put(&self.path(path)?, content.into()).await?;
+        Ok(())
+    }
+
+    async fn get(&self, path: &str) -> Result<Option<Bytes>> {
+        match self.store.get(&self.path(path)?).await {
+            Ok(result) => Ok(Some(result.bytes().await?)),
+            Err(object_store::Error::NotFound { .. }) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn stream(&self, path: &str) -> Result<Option<ByteStream>> {
+        match self.store.get(&self.path(path)?).await {
+            Ok(result) => Ok(Some(result.into_stream().map_err(Error::from).boxed())),
+            Err(object_store::Error::NotFound { .. }) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn delete(&self, path: &str) -> Result<()> {
+        match self.store.delete(&self.path(path)?).await {
+            Ok(()) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// S3 lists by folder, so list the folder of `prefix` and keep the paths
+    /// starting with it.
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let full = format!("{}{}", self.prefix, prefix.trim_start_matches('/'));
+        let folder = full.rsplit_once('/').map(|(folder, _)| Path::from(folder));
+
+        let mut paths = self
+            .store
+            .list(folder.as_ref())
+            .map_ok(|meta| meta.location.to_string())
+            .try_filter(|path| std::future::ready(path.starts_with(&full)))
+            .map_ok(|path| path[self.prefix.len()..].to_string())
+            .try_collect::<Vec<_>>()
+            .await?;
+        paths.sort();
+        Ok(paths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeMap,
+        fmt::Write as _,
+        sync::{Arc, Mutex},
+    };
+
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+        net::{TcpListener, TcpStream},
+    };
+
+    use super::*;
+
+    const BUCKET: &str = "uploads";
+
+    type Objects = Arc<Mutex<BTreeMap<String, Vec<u8>>>>;
+
+    /// A MinIO stand-in keeping the objects of [`BUCKET`] in memory, for the
+    /// path-style requests of the storage: put, get, delete and list.
+    async fn minio() -> (S3Storage, Objects) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let objects = Objects::default();
+        tokio::spawn({
+            let objects = objects.clone();
+            async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    tokio::spawn(handle(stream, objects.clone()));
+                }
+            }
+        });
+
+        let storage = S3Storage::new(&S3Config {
+            bucket: BUCKET.to_string(),
+            region: "us-east-1".to_string(),
+            endpoint: Some(endpoint),
+            access_key_id: Some("minioadmin".to_string()),
+            secret_access_key: Some("minioadmin".into()),
+            path_style: true,
+            prefix: Some("/app/".to_string()),
+        })
+        .unwrap();
+        (storage, objects)
+    }
+
+    async fn handle(stream: TcpStream, objects: Objects) {
+        let mut stream = BufReader::new(stream);
+        loop {
+            let mut line = String::new();
+            if stream.read_line(&mut line).await.unwrap_or(0) == 0 {
+                return;
+            }
+            let mut request = line.split_whitespace();
+            let method = request.next().unwrap_or_default().to_string();
+            let target = request.next().unwrap_or_default().to_string();
+
+            let mut length = 0;
+            loop {
+                let mut header = String::new();
+                stream.read_line(&mut header).await.unwrap();
+                match header.trim_end().split_once(':') {
+                    Some((name, value)) if name.eq_ignore_ascii_case("content-length") => {
+                        length = value.trim().parse().unwrap();
+                    }
+                    Some(_) => {}
+                    None => break,
+                }
+            }
+            let mut body = vec![0; length];
+            stream.read_exact(&mut body).await.unwrap();
+
+            let (status, body) = respond(&method, &target, body, &objects);
+            let head = format!(
+                "HTTP/1.1 {status}\r\ncontent-length: {}\r\netag: \"1\"\r\n\
+                 last-modified: Mon, 01 Jan 2024 00:00:00 GMT\r\n\r\n",
+                body.len()
+            );
+            stream.write_all(head.as_bytes()).await.unwrap();
+            stream.write_all(&body).await.unwrap();
+        }
+    }
+
+    fn respond(
+        method: &str,
+        target: &str,
+        body: Vec<u8>,
+        objects: &Objects,
+    ) -> (&'static str, Vec<u8>) {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let key = decode(
+            path.trim_start_matches(&format!("/{BUCKET}"))
+                .trim_start_matches('/'),
+        );
+        let mut objects = objects.lock().unwrap();
+        match method {
+            "PUT" => {
+                objects.insert(key, body);
+                ("200 OK", Vec::new())
+            }
+            "DELETE" => {
+                objects.remove(&key);
+                ("204 No Content", Vec::new())
+            }
+            "GET" if key.is_empty() => {
+                let prefix = query
+                    .split('&')
+                    .find_map(|param| param.strip_prefix("prefix="))
+                    .map(decode)
+                    .unwrap_or_default();
+                let mut list = String::from("<ListBucketResult>");
+                for (key, content) in objects.iter().filter(|(key, _)| key.starts_with(&prefix)) {
+                    write!(
+                        list,
+                        "<Contents><Key>{key}</Key><Size>{}</Size>\
+                         <LastModified>2024-01-01T00:00:00.000Z</LastModified></Contents>",
+                        content.len()
+                    )
+                    .unwrap();
+                }
+                list.push_str("</ListBucketResult>");
+                ("200 OK", list.into_bytes())
+            }
+            "GET" => match objects.get(&key) {
+                Some(content) => ("200 OK", content.clone()),
+                None => (
+                    "404 Not Found",
+                    b"<Error><Code>NoSuchKey</Code></Error>".to_vec(),
+                ),
+            },
+            _ => ("405 Method Not Allowed", Vec::new()),
+        }
+    }
+
+    fn decode(value: &str) -> String {
+        let mut decoded = Vec::new();
+        let mut bytes = value.bytes();
+        while let Some(byte) = bytes.next() {
+            if byte == b'%' {
+                let hex = [bytes.next().unwrap(), bytes.next().unwrap()];
+                decoded.push(u8::from_str_radix(std::str::from_utf8(&hex).unwrap(), 16).unwrap());
+            } else {
+                decoded.push(byte);
+            }
+        }
+        String::from_utf8(decoded).unwrap()
+    }
+
+    #[tokio::test]
+    async fn stores_the_files_under_the_prefix() {
+        let (storage, objects) = minio().await;
+
+        storage
+            .put("/avatars/42.png", Bytes::from("png"))
+            .await
+            .unwrap();
+        assert_eq!(
+            objects.lock().unwrap().keys().collect::<Vec<_>>(),
+            ["app/avatars/42.png"]
+        );
+
+        assert_eq!(
+            storage.get("avatars/42.png").await.unwrap(),
+            Some(Bytes::from("png"))
+        );
+        let chunks = storage
+            .stream("avatars/42.png")
+            .await
+            .unwrap()
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(chunks.concat(), b"png");
+
+        storage.delete("avatars/42.png").await.unwrap();
+        assert_eq!(storage.get("avatars/42.png").await.unwrap(), None);
+        assert!(storage.stream("avatars/42.png").await.unwrap().is_none());
+        // removing a missing file is not an error
+        storage.delete("avatars/42.png").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn lists_the_files_by_prefix() {
+        let (storage, _) = minio().await;
+        for path in [
+            "reports/2024-02.csv",
+            "reports/2024-01.csv",
+            "reports-old.csv",
+            "a.png",
+        ] {
+            storage.put(path, Bytes::from("x")).await.unwrap();
+        }
+
+        assert_eq!(
+            storage.list("reports/").await.unwrap(),
+            ["reports/2024-01.csv", "reports/2024-02.csv"]
+        );
+        assert_eq!(
+            storage.list("/reports").await.unwrap(),
+            [
+                "reports-old.csv",
+                "reports/2024-01.csv",
+                "reports/2024-02.csv"
+            ]
+        );
+        assert!(storage.list("avatars/").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn rejects_the_paths_outside_the_storage() {
+        let (storage, objects) = minio().await;
+
+        assert!(storage.put("../secrets", Bytes::from("x")).await.is_err());
+        assert!(storage.get("avatars/../../secrets").await.is_err());
+        assert!(objects.lock().unwrap().is_empty());
+    }
+}
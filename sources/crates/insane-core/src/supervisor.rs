@@ -0,0 +1,433 @@
+//! Supervision of the servers started by [`crate::boot_loader::boot_app`].
+//!
+//! Each server runs under a supervisor that applies the
+//! [`SupervisionPolicy`] configured for it when `serve` returns an error or
+//! panics, and reports every state change through tracing.
+
+use std::{sync::Arc, time::Instant};
+
+use tokio::task::{JoinError, JoinHandle};
+
+use crate::{
+    config::servers::{SupervisionConfig, SupervisionPolicy},
+    context::Context,
+    error::{Error, ErrorCode, Result},
+    server::{Readiness, Server},
+    shutdown::ShutdownToken,
+};
+
+/// The state of a supervised server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerState {
+    Disabled,
+    Running,
+    Failed,
+    Restarting,
+    Stopped,
+    GaveUp,
+}
+
+impl std::fmt::Display for ServerState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = match self {
+            Self::Disabled => "disabled",
+            Self::Running => "running",
+            Self::Failed => "failed",
+            Self::Restarting => "restarting",
+            Self::Stopped => "stopped",
+            Self::GaveUp => "gave_up",
+        };
+        state.fmt(f)
+    }
+}
+
+fn transition(server: &str, state: ServerState) {
+    tracing::info!(server, state = %state, "server state changed");
+}
+
+/// Run the server until it stops, applying the supervision policy on
+/// failures.
+///
+/// `ready` is handed to the server, and reported when the server stops
+/// without stopping the application (disabled, stopped or ignored failure),
+/// so that nothing waits for it.
+///
+/// A `required` server, asked for on the command line, may not be disabled.
+///
+/// # Errors
+/// When the failure of the server must stop the whole application: the policy
+/// is `fail_fast`, or the `restart` policy ran out of `max_restarts`. When a
+/// `required` server is disabled by the configuration.
+pub async fn supervise(
+    server: Arc<dyn Server>,
+    context: Arc<Box<dyn Context>>,
+    shutdown: ShutdownToken,
+    config: SupervisionConfig,
+    ready: Readiness,
+    required: bool,
+) -> Result<()> {
+    let supervised =
+        supervise_server(server, context, shutdown, config, ready.clone(), required).await;
+    if supervised.is_ok() {
+        ready.ready();
+    }
+    supervised
+}
+
+async fn supervise_server(
+    server: Arc<dyn Server>,
+    context: Arc<Box<dyn Context>>,
+    shutdown: ShutdownToken,
+    config: SupervisionConfig,
+    ready: Readiness,
+    required: bool,
+) -> Result<()> {
+    let name = server.name();
+
+    match server.enable(context.clone()).await {
+        Ok(true) => {}
+        Ok(false) if required => {
+            return Err(Error::new(
+                ErrorCode::CONFIG,
+                format!("server `{name}` is disabled by the configuration"),
+            ));
+        }
+        Ok(false) => {
+            transition(&name, ServerState::Disabled);
+            return Ok(());
+        }
+        Err(err) => {
+            tracing::error!(server = name, state = %ServerState::Failed, err.msg = %err, "server state changed");
+            return match config.policy {
+                SupervisionPolicy::Ignore => Ok(()),
+                _ => Err(err),
+            };
+        }
+    }
+
+    let mut restarts = 0;
+    let mut backoff = config.initial_backoff();
+    loop {
+        transition(&name, ServerState::Running);
+        let started = Instant::now();
+
+        let served = run(
+            server.clone(),
+            context.clone(),
+            shutdown.clone(),
+            ready.clone(),
+        )
+        .await;
+        let err = match served {
+            Ok(()) => {
+                transition(&name, ServerState::Stopped);
+                return Ok(());
+            }
+            Err(err) => err,
+        };
+
+        tracing::error!(server = name, state = %ServerState::Failed, err.msg = %err, "server state changed");
+        if shutdown.is_triggered() {
+            return Ok(());
+        }
+
+        match config.policy {
+            SupervisionPolicy::Ignore => return Ok(()),
+            SupervisionPolicy::FailFast => return Err(err),
+            SupervisionPolicy::Restart => {
+                if started.elapsed() >= config.max_backoff() {
+                    restarts = 0;
+                    backoff = config.initial_backoff();
+                }
+                if config.max_restarts.is_some_and(|max| restarts >= max) {
+                    transition(&name, ServerState::GaveUp);
+                    return Err(err);
+                }
+                restarts += 1;
+
+                tracing::warn!(
+                    server = name,
+                    state = %ServerState::Restarting,
+                    attempt = restarts,
+                    backoff = ?backoff,
+                    "server state changed"
+                );
+                tokio::select! {
+                    () = tokio::time::sleep(backoff) => {},
+                    () = shutdown.triggered() => {
+                        transition(&name, ServerState::Stopped);
+                        return Ok(());
+                    }
+                }
+                backoff = (backoff * 2).min(config.max_backoff());
+            }
+        }
+    }
+}
+
+/// Aborts the task when dropped, so aborting the supervisor also aborts the
+/// server it runs.
+struct AbortOnDrop<T>(JoinHandle<T>);
+
+impl<T> Drop for AbortOnDrop<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Run `serve` once on its own task, turning a panic into an error.
+async fn run(
+    server: Arc<dyn Server>,
+    context: Arc<Box<dyn Context>>,
+    shutdown: ShutdownToken,
+    ready: Readiness,
+) -> Result<()> {
+    let mut task = AbortOnDrop(tokio::spawn(async move {
+        server.serve(context, shutdown, ready).await
+    }));
+
+    match (&mut task.0).await {
+        Ok(result) => result,
+        Err(err) => Err(join_error(err)),
+    }
+}
+
+fn join_error(err: JoinError) -> Error {
+    if err.is_panic() {
+        let panic = err.into_panic();
+        let msg = panic.downcast_ref::<String>().map_or_else(
+            || {
+                panic
+                    .downcast_ref::<&str>()
+                    .map_or("no error details", |s| s)
+            },
+            |s| s.as_str(),
+        );
+        Error::Message(format!("server panicked: {msg}"))
+    } else {
+        Error::Message(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicU32, Ordering},
+        time::Duration,
+    };
+
+    use super::*;
+    use crate::{config::InsaneConfig, context::test_context};
+
+    /// Fails `failures` times, by returning an error or panicking, then stops.
+    struct FlakyServer {
+        failures: u32,
+        panics: bool,
+        attempts: Arc<AtomicU32>,
+    }
+
+    impl FlakyServer {
+        /// The server and the count of its attempts.
+        fn failing(failures: u32) -> (Arc<dyn Server>, Arc<AtomicU32>) {
+            let attempts = Arc::new(AtomicU32::new(0));
+            let server = Self {
+                failures,
+                panics: false,
+                attempts: attempts.clone(),
+            };
+            (Arc::new(server), attempts)
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Server for FlakyServer {
+        fn name(&self) -> String {
+            "flaky".to_string()
+        }
+
+        async fn enable(&self, _context: Arc<Box<dyn Context>>) -> Result<bool> {
+            Ok(true)
+        }
+
+        async fn serve(
+            &self,
+            _context: Arc<Box<dyn Context>>,
+            _shutdown: ShutdownToken,
+            ready: Readiness,
+        ) -> Result<()> {
+            let attempt = self.attempts.fetch_add(1, Ordering::SeqCst) + 1;
+            if attempt > self.failures {
+                ready.ready();
+                return Ok(());
+            }
+            assert!(!self.panics, "boom");
+            Err(Error::string("address already in use"))
+        }
+    }
+
+    fn supervision(policy: SupervisionPolicy, max_restarts: Option<u32>) -> SupervisionConfig {
+        SupervisionConfig {
+            policy,
+            max_restarts,
+            initial_backoff: 10,
+            max_backoff: 1_000,
+        }
+    }
+
+    async fn run_supervised(server: Arc<dyn Server>, config: SupervisionConfig) -> Result<()> {
+        let context = test_context(InsaneConfig::default());
+        supervise(
+            server,
+            context,
+            ShutdownToken::new(),
+            config,
+            Readiness::new(),
+            false,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn restarts_with_a_growing_backoff() {
+        let (server, attempts) = FlakyServer::failing(3);
+        let config = supervision(SupervisionPolicy::Restart, None);
+        let started = Instant::now();
+
+        let supervised = run_supervised(server, config).await;
+
+        assert!(supervised.is_ok());
+        assert_eq!(attempts.load(Ordering::SeqCst), 4);
+        // 10ms, 20ms then 40ms
+        assert!(started.elapsed() >= Duration::from_millis(70));
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_restarts() {
+        let (server, attempts) = FlakyServer::failing(u32::MAX);
+        let config = supervision(SupervisionPolicy::Restart, Some(2));
+
+        let err = run_supervised(server, config).await.unwrap_err();
+
+        assert_eq!(err.to_string(), "address already in use");
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn fails_fast() {
+        let (server, attempts) = FlakyServer::failing(1);
+
+        let config = supervision(SupervisionPolicy::FailFast, None);
+
+        let supervised = run_supervised(server, config).await;
+
+        assert!(supervised.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn ignores_the_failure_by_default() {
+        let (server, attempts) = FlakyServer::failing(1);
+        let ready = Readiness::new();
+
+        let supervised = supervise(
+            server,
+            test_context(InsaneConfig::default()),
+            ShutdownToken::new(),
+            SupervisionConfig::default(),
+            ready.clone(),
+            false,
+        )
+        .await;
+
+        assert!(supervised.is_ok());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+        // nothing waits for the server that won't start
+        assert!(ready.is_ready());
+    }
+
+    struct DisabledServer;
+
+    #[async_trait::async_trait]
+    impl Server for DisabledServer {
+        fn name(&self) -> String {
+            "disabled".to_string()
+        }
+
+        async fn enable(&self, _context: Arc<Box<dyn Context>>) -> Result<bool> {
+            Ok(false)
+        }
+
+        async fn serve(
+            &self,
+            _context: Arc<Box<dyn Context>>,
+            _shutdown: ShutdownToken,
+            _ready: Readiness,
+        ) -> Result<()> {
+            unreachable!("a disabled server is not served")
+        }
+    }
+
+    #[tokio::test]
+    async fn refuses_a_required_server_that_is_disabled() {
+        let supervise_disabled = |required| {
+            supervise(
+                Arc::new(DisabledServer),
+                test_context(InsaneConfig::default()),
+                ShutdownToken::new(),
+                SupervisionConfig::default(),
+                Readiness::new(),
+                required,
+            )
+        };
+
+        assert!(supervise_disabled(false).await.is_ok());
+        let err = supervise_disabled(true).await.unwrap_err();
+        assert_eq!(err.code(), ErrorCode::CONFIG);
+    }
+
+    #[tokio::test]
+    async fn turns_panics_into_errors() {
+        let attempts = Arc::new(AtomicU32::new(0));
+        let server = Arc::new(FlakyServer {
+            failures: 1,
+            panics: true,
+            attempts: attempts.clone(),
+        });
+
+        let err = run_supervised(server, supervision(SupervisionPolicy::FailFast, None))
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), "server panicked: boom");
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn stops_restarting_on_shutdown() {
+        let (server, attempts) = FlakyServer::failing(u32::MAX);
+        let mut config = supervision(SupervisionPolicy::Restart, None);
+        config.initial_backoff = 60_000;
+        config.max_backoff = 60_000;
+        let shutdown = ShutdownToken::new();
+
+        let supervised = supervise(
+            server,
+            test_context(InsaneConfig::default()),
+            shutdown.clone(),
+            config,
+            Readiness::new(),
+            false,
+        );
+        let trigger = async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            shutdown.trigger();
+        };
+        let joined = async { tokio::join!(supervised, trigger) };
+        let (supervised, ()) = tokio::time::timeout(Duration::from_secs(5), joined)
+            .await
+            .unwrap();
+
+        assert!(supervised.is_ok());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+}
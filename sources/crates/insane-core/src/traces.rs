@@ -1,59 +1,520 @@
 #![allow(non_camel_case_types)]
 
 use clap::ValueEnum;
+use rolling_file::{BasicRollingFileAppender, RollingConditionBasic};
 use serde::{Deserialize, Serialize};
-use std::env;
+use std::{
+    env,
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+use tracing_appender::non_blocking::{NonBlocking, WorkerGuard};
 use tracing_subscriber::prelude::*;
-use tracing_subscriber::{fmt, EnvFilter};
+use tracing_subscriber::{filter::Directive, fmt, reload, EnvFilter, Layer, Registry};
 
-use crate::error::Result;
-use crate::config::TraceConfig;
+use crate::config::{LogFileConfig, LogRotation, TraceConfig};
+use crate::error::{Error, Result};
+use crate::hook::Hooks;
 
-#[derive(Copy, Clone, Debug, ValueEnum, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, Default, ValueEnum, Serialize, Deserialize)]
 pub enum TraceFormat {
+    #[default]
     standard,
     json,
     pretty,
 }
 
-impl Default for TraceFormat {
-    fn default() -> Self {
-        TraceFormat::standard
-    }
-}
+/// The filters of the outputs installed by [`init`].
+static LOG_FILTER: OnceLock<Arc<LogFilter>> = OnceLock::new();
 
-pub fn init(config: &TraceConfig) -> Result<()> {
-    let mut filter = EnvFilter::new(config.filter());
+/// Guards of the non-blocking file writers, dropped by [`flush`].
+static GUARDS: Mutex<Vec<WorkerGuard>> = Mutex::new(Vec::new());
 
-    // because tokio_util is too verbose
-    filter = filter
-        .add_directive("tokio_util=info".parse().expect("Failed to parse filter directive"));
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
 
+fn filter(directives: &str) -> Result<EnvFilter> {
+    let mut filter = EnvFilter::new(directives);
+
+    // because tokio_util is too verbose
+    filter = filter.add_directive(
+        "tokio_util=info"
+            .parse()
+            .expect("Failed to parse filter directive"),
+    );
 
     if let Ok(rust_log) = env::var(EnvFilter::DEFAULT_ENV) {
         filter = filter.add_directive(rust_log.parse()?);
     }
 
-    match config.format() {
-        TraceFormat::standard => {
-            tracing_subscriber::registry()
-                .with(fmt::layer().with_ansi(atty::is(atty::Stream::Stdout)))
-                .with(filter)
-                .init();
+    Ok(filter)
+}
+
+/// The filter of every output, the terminal first, then the files and the
+/// OpenTelemetry export.
+pub(crate) fn filters(config: &TraceConfig) -> Vec<&str> {
+    let mut filters = vec![config.filter()];
+    filters.extend(
+        config
+            .files
+            .iter()
+            .map(|file| file.filter.as_deref().unwrap_or(config.filter())),
+    );
+    #[cfg(feature = "with-otel")]
+    if let Some(otel) = &config.otel {
+        filters.push(otel.filter.as_deref().unwrap_or(config.filter()));
+    }
+    filters
+}
+
+fn format_layer<W>(format: TraceFormat, writer: W, ansi: bool) -> BoxedLayer
+where
+    W: for<'writer> fmt::MakeWriter<'writer> + Send + Sync + 'static,
+{
+    match format {
+        TraceFormat::standard => fmt::layer().with_writer(writer).with_ansi(ansi).boxed(),
+        TraceFormat::json => fmt::layer()
+            .with_writer(writer)
+            .json()
+            .flatten_event(true)
+            .boxed(),
+        TraceFormat::pretty => fmt::layer()
+            .with_writer(writer)
+            .pretty()
+            .with_ansi(ansi)
+            .boxed(),
+    }
+}
+
+/// Open the rolling file of `config`, written by a background thread.
+fn file_writer(config: &LogFileConfig) -> Result<NonBlocking> {
+    if let Some(folder) = config
+        .path
+        .parent()
+        .filter(|folder| !folder.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(folder)?;
+    }
+
+    let condition = match config.rotation {
+        LogRotation::Daily => RollingConditionBasic::new().daily(),
+        LogRotation::Hourly => RollingConditionBasic::new().hourly(),
+        LogRotation::Size => RollingConditionBasic::new().max_size(config.max_size),
+        LogRotation::Never => RollingConditionBasic::new(),
+    };
+    let file = BasicRollingFileAppender::new(&config.path, condition, config.retention).map_err(
+        |err| {
+            Error::Message(format!(
+                "failed to open the log file `{}`: {err}",
+                config.path.display()
+            ))
+        },
+    )?;
+
+    let (writer, guard) = tracing_appender::non_blocking(file);
+    GUARDS
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .push(guard);
+    Ok(writer)
+}
+
+/// Install the subscriber writing to the terminal and to the files of
+/// `config`, and exporting the spans of the application `H` with the
+/// `with-otel` feature.
+///
+/// # Errors
+/// When a filter is invalid, a log file can't be opened or the OpenTelemetry
+/// exporter can't be created.
+pub fn init<H: Hooks>(config: &TraceConfig) -> Result<()> {
+    let ansi = atty::is(atty::Stream::Stdout);
+    let mut outputs = vec![format_layer(*config.format(), std::io::stdout, ansi)];
+    for file in &config.files {
+        outputs.push(format_layer(file.format, file_writer(file)?, false));
+    }
+    #[cfg(feature = "with-otel")]
+    if let Some(otel) = &config.otel {
+        outputs.push(crate::otel::layer(otel, H::app_name(), &H::app_version())?);
+    }
+
+    let mut layers = Vec::with_capacity(outputs.len());
+    let mut handles = Vec::with_capacity(outputs.len());
+    for (output, directives) in outputs.into_iter().zip(filters(config)) {
+        let (filter, handle) = reload::Layer::new(filter(directives)?);
+        layers.push(output.with_filter(filter).boxed());
+        handles.push(handle);
+    }
+    let _ = LOG_FILTER.set(Arc::new(LogFilter {
+        handles,
+        state: Mutex::new(FilterState {
+            configured: filters(config).into_iter().map(String::from).collect(),
+            overrides: None,
+            generation: 0,
+        }),
+    }));
+
+    tracing_subscriber::registry().with(layers).init();
+
+    Ok(())
+}
+
+/// Export the spans not exported yet and write the logs waiting in the
+/// non-blocking writers to their files. The logs emitted afterwards are
+/// dropped, call it once the application is done.
+pub fn flush() {
+    #[cfg(feature = "with-otel")]
+    crate::otel::shutdown();
+
+    GUARDS.lock().unwrap_or_else(|err| err.into_inner()).clear();
+}
+
+/// Replace the filters installed by [`init`] with the ones of `config`, the
+/// formats and the files can't change without a restart.
+///
+/// # Errors
+/// When the filter is invalid.
+pub fn reload_filter(config: &TraceConfig) -> Result<()> {
+    match LOG_FILTER.get() {
+        Some(log_filter) => log_filter.reload(config),
+        None => Ok(()),
+    }
+}
+
+/// The filters installed by [`init`], `None` when the application installed
+/// its own subscriber with [`Hooks::init_logger`].
+#[must_use]
+pub fn log_filter() -> Option<Arc<LogFilter>> {
+    LOG_FILTER.get().cloned()
+}
+
+/// Changes the filters of the running application, available from
+/// [`crate::context::Context::log_filter`].
+///
+/// Overrides are directives added to the configured filter of every output,
+/// like `insane_http=debug` to debug a single crate, until they are reset or
+/// their time to live is over. Reloading the configuration keeps them.
+///
+/// ```rust
+/// use insane_core::{context::Context, error::Result};
+/// use std::time::Duration;
+///
+/// fn debug_http(context: &dyn Context) -> Result<()> {
+///     if let Some(log_filter) = context.log_filter() {
+///         log_filter.set("insane_http=debug", Some(Duration::from_secs(600)))?;
+///     }
+///     Ok(())
+/// }
+/// ```
+pub struct LogFilter {
+    /// The terminal first, then the files and the OpenTelemetry export.
+    handles: Vec<reload::Handle<EnvFilter, Registry>>,
+    state: Mutex<FilterState>,
+}
+
+struct FilterState {
+    configured: Vec<String>,
+    overrides: Option<Overrides>,
+    /// Incremented by every change of the overrides, so that a revert only
+    /// resets the overrides it was started for.
+    generation: u64,
+}
+
+struct Overrides {
+    directives: String,
+    expires: Option<Instant>,
+}
+
+/// The filters in use, see [`LogFilter::status`].
+#[derive(Debug, Clone, Serialize)]
+pub struct LogFilterStatus {
+    /// The configured filter of the terminal, `tracing.filter`.
+    pub filter: String,
+
+    /// The directives added to the configured filters.
+    pub overrides: Option<String>,
+
+    /// Seconds before the overrides are reset.
+    pub expires_in: Option<u64>,
+}
+
+impl std::fmt::Debug for LogFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LogFilter")
+            .field("status", &self.status())
+            .finish_non_exhaustive()
+    }
+}
+
+impl LogFilter {
+    fn state(&self) -> std::sync::MutexGuard<'_, FilterState> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// The filters in use.
+    #[must_use]
+    pub fn status(&self) -> LogFilterStatus {
+        let state = self.state();
+        let overrides = state.overrides.as_ref();
+        LogFilterStatus {
+            filter: state.configured.first().cloned().unwrap_or_default(),
+            overrides: overrides.map(|overrides| overrides.directives.clone()),
+            expires_in: overrides
+                .and_then(|overrides| overrides.expires)
+                .map(|expires| expires.saturating_duration_since(Instant::now()).as_secs()),
         }
-        TraceFormat::json => {
-            tracing_subscriber::registry()
-                .with(fmt::layer().json().flatten_event(true))
-                .with(filter)
-                .init();
+    }
+
+    /// Add `directives`, like `insane_http=debug,sqlx=trace`, to the filters
+    /// of every output, replacing the previous overrides. They are reset after
+    /// `ttl`, or stay until [`LogFilter::reset`] without one.
+    ///
+    /// # Errors
+    /// When a directive is invalid, or a `ttl` is given outside of a Tokio
+    /// runtime.
+    pub fn set(
+        self: &Arc<Self>,
+        directives: &str,
+        ttl: Option<Duration>,
+    ) -> Result<LogFilterStatus> {
+        let directives = parse_directives(directives)?;
+        let runtime = ttl
+            .map(|_| tokio::runtime::Handle::try_current().map_err(Error::wrap))
+            .transpose()?;
+
+        let generation = {
+            let mut state = self.state();
+            state.generation += 1;
+            state.overrides = Some(Overrides {
+                directives: directives.clone(),
+                expires: ttl.map(|ttl| Instant::now() + ttl),
+            });
+            self.apply(&state)?;
+            state.generation
+        };
+        tracing::warn!(
+            directives,
+            ttl = ttl.map(|ttl| ttl.as_secs()),
+            "log filter overridden"
+        );
+
+        if let (Some(ttl), Some(runtime)) = (ttl, runtime) {
+            let log_filter = self.clone();
+            runtime.spawn(async move {
+                tokio::time::sleep(ttl).await;
+                log_filter.revert(generation);
+            });
         }
-        TraceFormat::pretty => {
-            tracing_subscriber::registry()
-                .with(fmt::layer().pretty().with_ansi(atty::is(atty::Stream::Stdout)))
-                .with(filter)
-                .init();
+        Ok(self.status())
+    }
+
+    /// Remove the overrides, back to the configured filters.
+    ///
+    /// # Errors
+    /// When a configured filter is invalid.
+    pub fn reset(&self) -> Result<LogFilterStatus> {
+        {
+            let mut state = self.state();
+            state.generation += 1;
+            if state.overrides.take().is_some() {
+                self.apply(&state)?;
+                tracing::warn!("log filter overrides reset");
+            }
         }
-    };
+        Ok(self.status())
+    }
 
-    Ok(())
+    /// Reset the overrides when they didn't change since `generation`.
+    fn revert(&self, generation: u64) {
+        let mut state = self.state();
+        if state.generation != generation {
+            return;
+        }
+        state.overrides = None;
+        match self.apply(&state) {
+            Ok(()) => tracing::warn!("log filter overrides expired"),
+            Err(err) => tracing::error!(err.msg = %err, "failed to reset the log filter"),
+        }
+    }
+
+    fn reload(&self, config: &TraceConfig) -> Result<()> {
+        let mut state = self.state();
+        let previous = std::mem::replace(
+            &mut state.configured,
+            filters(config).into_iter().map(String::from).collect(),
+        );
+        if let Err(err) = self.apply(&state) {
+            state.configured = previous;
+            return Err(err);
+        }
+        tracing::info!(filter = config.filter(), "tracing filter reloaded");
+        Ok(())
+    }
+
+    /// Install the configured filters with the overrides of `state`. Nothing
+    /// changes when one is invalid.
+    fn apply(&self, state: &FilterState) -> Result<()> {
+        let mut filters = Vec::with_capacity(state.configured.len());
+        for configured in &state.configured {
+            let mut filter = filter(configured)?;
+            if let Some(overrides) = &state.overrides {
+                for directive in overrides.directives.split(',') {
+                    filter = filter.add_directive(directive.parse()?);
+                }
+            }
+            filters.push(filter);
+        }
+        for (handle, filter) in self.handles.iter().zip(filters) {
+            handle.reload(filter).map_err(Error::msg)?;
+        }
+        Ok(())
+    }
+}
+
+/// Check and normalize comma separated directives.
+fn parse_directives(directives: &str) -> Result<String> {
+    let directives = directives
+        .split(',')
+        .map(str::trim)
+        .filter(|directive| !directive.is_empty())
+        .map(|directive| {
+            directive
+                .parse::<Directive>()
+                .map(|_| directive)
+                .map_err(|err| {
+                    Error::Message(format!("invalid filter directive `{directive}`: {err}"))
+                })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if directives.is_empty() {
+        return Err(Error::string("no filter directive given"));
+    }
+    Ok(directives.join(","))
+}
+
+#[cfg(test)]
+impl LogFilter {
+    /// A filter of a single output, kept alive by the returned subscriber.
+    pub(crate) fn for_tests(directives: &str) -> (Arc<Self>, impl tracing::Subscriber) {
+        let (filter, handle) = reload::Layer::new(filter(directives).unwrap());
+        let log_filter = Self {
+            handles: vec![handle],
+            state: Mutex::new(FilterState {
+                configured: vec![directives.to_string()],
+                overrides: None,
+                generation: 0,
+            }),
+        };
+        (
+            Arc::new(log_filter),
+            tracing_subscriber::registry().with(filter),
+        )
+    }
+
+    /// The filter installed on the output.
+    fn installed(&self) -> String {
+        self.handles[0].with_current(ToString::to_string).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn adds_and_resets_the_overrides() {
+        let (log_filter, _subscriber) = LogFilter::for_tests("info");
+
+        let status = log_filter
+            .set(" insane_http=debug, sqlx=trace ", None)
+            .unwrap();
+        assert_eq!(status.filter, "info");
+        assert_eq!(
+            status.overrides.as_deref(),
+            Some("insane_http=debug,sqlx=trace")
+        );
+        assert_eq!(status.expires_in, None);
+        assert!(log_filter.installed().contains("insane_http=debug"));
+
+        let status = log_filter.reset().unwrap();
+        assert_eq!(status.overrides, None);
+        assert!(!log_filter.installed().contains("insane_http=debug"));
+    }
+
+    #[tokio::test]
+    async fn keeps_the_filters_on_invalid_directives() {
+        let (log_filter, _subscriber) = LogFilter::for_tests("info");
+        log_filter.set("insane_http=debug", None).unwrap();
+
+        let err = log_filter.set("insane_http=loud", None).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("invalid filter directive `insane_http=loud`"));
+        assert_eq!(
+            log_filter.set(" , ", None).unwrap_err().to_string(),
+            "no filter directive given"
+        );
+        assert_eq!(
+            log_filter.status().overrides.as_deref(),
+            Some("insane_http=debug")
+        );
+    }
+
+    #[tokio::test]
+    async fn reverts_the_overrides_after_their_ttl() {
+        let (log_filter, _subscriber) = LogFilter::for_tests("info");
+
+        let status = log_filter
+            .set("insane_http=debug", Some(Duration::from_secs(600)))
+            .unwrap();
+        assert!(status.expires_in.is_some_and(|expires_in| expires_in > 590));
+
+        log_filter
+            .set("insane_http=debug", Some(Duration::from_millis(50)))
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(log_filter.status().overrides, None);
+        assert!(!log_filter.installed().contains("insane_http=debug"));
+    }
+
+    #[tokio::test]
+    async fn keeps_the_overrides_changed_before_the_ttl() {
+        let (log_filter, _subscriber) = LogFilter::for_tests("info");
+
+        log_filter
+            .set("insane_http=debug", Some(Duration::from_millis(50)))
+            .unwrap();
+        log_filter.set("sqlx=trace", None).unwrap();
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(log_filter.status().overrides.as_deref(), Some("sqlx=trace"));
+    }
+
+    #[test]
+    fn rotates_the_files_by_size_and_keeps_the_retention() {
+        let folder = std::env::temp_dir().join(format!("insane-logs-{}", std::process::id()));
+        let config = LogFileConfig {
+            path: folder.join("app.log"),
+            format: TraceFormat::standard,
+            filter: None,
+            rotation: LogRotation::Size,
+            max_size: 100,
+            retention: 2,
+        };
+        let writer = file_writer(&config).unwrap();
+        let subscriber =
+            tracing_subscriber::registry().with(format_layer(TraceFormat::standard, writer, false));
+        tracing::subscriber::with_default(subscriber, || {
+            for line in 0..5 {
+                tracing::info!("line {line} long enough to fill the file before the next line");
+            }
+        });
+        // written out like `flush` does, without shutting down the exporters
+        GUARDS.lock().unwrap().clear();
+
+        let read = |name: &str| std::fs::read_to_string(folder.join(name)).unwrap();
+        assert!(read("app.log").contains("line 4"));
+        assert!(read("app.log.1").contains("line 3"));
+        assert!(read("app.log.2").contains("line 2"));
+        assert!(!folder.join("app.log.3").exists());
+        assert_eq!(std::fs::read_dir(&folder).unwrap().count(), 3);
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
 }
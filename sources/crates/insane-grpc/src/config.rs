@@ -0,0 +1,112 @@
+use insane_core::{
+    config::loader::{Config, ConfigLoader},
+    environment::Environment,
+    error::{Error, Result},
+};
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_SERVER_BINDING: &str = "[::]";
+
+fn default_binding() -> String {
+    DEFAULT_SERVER_BINDING.to_string()
+}
+
+fn default_port() -> i32 {
+    50051
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Server middleware configuration structure.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct Middlewares {
+    /// Middleware that improve the tracing logger and adding a request id for
+    /// each call.
+    pub logger: Option<EnableMiddleware>,
+    /// Setting a global timeout for the calls
+    pub timeout_request: Option<TimeoutRequestMiddleware>,
+}
+
+/// Timeout middleware configuration
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct TimeoutRequestMiddleware {
+    pub enable: bool,
+    // Timeout request in milliseconds
+    pub timeout: u64,
+}
+
+/// A generic middleware configuration that can be enabled or
+/// disabled.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct EnableMiddleware {
+    pub enable: bool,
+}
+
+/// gRPC server configuration
+///
+/// Example:
+/// ```yaml
+/// grpc:
+///   enable: true
+///   port: 50051
+///   reflection: false
+///   middlewares:
+///     logger:
+///       enable: true
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GrpcServerConfig {
+    #[serde(default = "default_binding")]
+    pub binding: String,
+    /// The port on which the server should listen for incoming connections.
+    #[serde(default = "default_port")]
+    pub port: i32,
+    /// Middleware configurations for the server.
+    #[serde(default)]
+    pub middlewares: Middlewares,
+
+    /// Serve the standard `grpc.health.v1.Health` service.
+    #[serde(default = "default_true")]
+    pub health: bool,
+
+    /// Serve the gRPC reflection service, used by tools like `grpcurl`. It
+    /// describes every service, so it is off unless enabled.
+    #[serde(default)]
+    pub reflection: bool,
+
+    /// Enable the server
+    #[serde(default)]
+    pub enable: bool,
+}
+
+impl Default for GrpcServerConfig {
+    fn default() -> Self {
+        Self {
+            binding: default_binding(),
+            port: default_port(),
+            middlewares: Middlewares::default(),
+            health: true,
+            reflection: false,
+            enable: false,
+        }
+    }
+}
+
+impl GrpcServerConfig {
+    pub fn new(env: &Environment, app_name: &str) -> Result<Self> {
+        Self::from_key("grpc", env, app_name)
+    }
+}
+
+impl Config for GrpcServerConfig {
+    fn enable(&self) -> bool {
+        self.enable
+    }
+}
+
+impl ConfigLoader for GrpcServerConfig {
+    type Config = GrpcServerConfig;
+    type Error = Error;
+}
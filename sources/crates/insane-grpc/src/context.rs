@@ -0,0 +1,54 @@
+use std::sync::Arc;
+
+use insane_core::prelude::*;
+use tonic_health::server::HealthReporter;
+
+use crate::config::GrpcServerConfig;
+
+#[derive(Clone)]
+pub struct GrpcContext {
+    pub server_config: GrpcServerConfig,
+    pub config: InsaneConfig,
+    pub environment: Environment,
+
+    #[cfg(feature = "with-sql")]
+    pub sql: DatabaseConnection,
+
+    #[cfg(feature = "with-redis")]
+    pub redis: Option<RedisPool>,
+
+    /// The cache of the application, see [`insane_core::cache`].
+    pub cache: Arc<dyn Cache>,
+
+    /// The file storage of the application, see [`insane_core::storage`].
+    pub storage: Arc<dyn Storage>,
+
+    /// Services registered on the application context.
+    pub extensions: Extensions,
+
+    /// Reporter of the health service, to set the serving status of each
+    /// registered service.
+    pub health: HealthReporter,
+}
+
+impl GrpcContext {
+    pub fn new(
+        server_config: GrpcServerConfig,
+        context: Arc<Box<dyn Context>>,
+        health: HealthReporter,
+    ) -> Self {
+        Self {
+            server_config,
+            config: context.config().clone(),
+            environment: context.environment().clone(),
+            #[cfg(feature = "with-sql")]
+            sql: context.sql().clone(),
+            #[cfg(feature = "with-redis")]
+            redis: context.redis().cloned(),
+            cache: context.cache().clone(),
+            storage: context.storage().clone(),
+            extensions: context.extensions().clone(),
+            health,
+        }
+    }
+}
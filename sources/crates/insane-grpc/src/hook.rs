@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use insane_core::{context::Context, error::Result, hook::Initializer};
+use tonic::service::RoutesBuilder;
+
+use crate::context::GrpcContext;
+
+#[async_trait::async_trait]
+pub trait GrpcHooks: Sync + Send {
+    /// Register the tonic services of the application.
+    ///
+    /// # Errors
+    /// When a service can't be created.
+    async fn services(&self, routes: &mut RoutesBuilder, ctx: &GrpcContext) -> Result<()>;
+
+    /// Encoded file descriptor sets of the registered services, exposed by
+    /// the reflection service. Usually generated by `tonic-build` with
+    /// `file_descriptor_set_path` and included with `include_bytes!`.
+    fn file_descriptor_sets(&self) -> Vec<&'static [u8]> {
+        vec![]
+    }
+
+    /// Initializers of the gRPC server, run around it like the ones of the
+    /// application.
+    ///
+    /// # Errors
+    /// When an initializer can't be created.
+    async fn initializers(
+        &self,
+        _app_context: Arc<Box<dyn Context>>,
+        _context: Arc<Box<GrpcContext>>,
+    ) -> Result<Vec<Box<dyn Initializer<Context = Arc<Box<GrpcContext>>>>>> {
+        Ok(vec![])
+    }
+
+    /// Called before the initializers and the services of the server.
+    ///
+    /// # Errors
+    /// When the server must not start.
+    async fn before_run(
+        &self,
+        _app_context: Arc<Box<dyn Context>>,
+        _context: Arc<Box<GrpcContext>>,
+    ) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+//! # gRPC server
+//!
+//! Serves the tonic services registered by [`hook::GrpcHooks`], along with
+//! the standard health and reflection services.
+//!
+//! # Example:
+//!
+//! ```rust
+//! use insane_core::error::Result;
+//! use insane_grpc::{context::GrpcContext, hook::GrpcHooks, server::GrpcServer};
+//! use tonic::service::RoutesBuilder;
+//!
+//! struct GrpcApp;
+//!
+//! #[async_trait::async_trait]
+//! impl GrpcHooks for GrpcApp {
+//!     async fn services(&self, _routes: &mut RoutesBuilder, _ctx: &GrpcContext) -> Result<()> {
+//!         // routes.add_service(GreeterServer::new(MyGreeter::default()));
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let server = GrpcServer::new(GrpcApp);
+//! ```
+
+pub mod config;
+pub mod context;
+pub mod hook;
+pub mod middlewares;
+pub mod server;
+
+pub mod prelude {
+    pub use crate::context::GrpcContext;
+    pub use crate::hook::GrpcHooks;
+    pub use crate::server::GrpcServer;
+    pub use tonic::{service::RoutesBuilder, Request, Response, Status};
+}
@@ -0,0 +1,88 @@
+//! Tower layers applied to every gRPC call.
+
+use insane_core::environment::Environment;
+use nanoid::nanoid;
+use tonic::codegen::http::{HeaderValue, Request};
+use tower_http::request_id::{MakeRequestId, RequestId};
+
+/// Header carrying the request id of a call, set when the client did not send
+/// one and returned in the response metadata.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Generate the request id of the calls without one.
+#[derive(Clone, Copy, Default)]
+pub struct MakeNanoid;
+
+impl MakeRequestId for MakeNanoid {
+    fn make_request_id<B>(&mut self, _request: &Request<B>) -> Option<RequestId> {
+        HeaderValue::from_str(&nanoid!()).ok().map(RequestId::new)
+    }
+}
+
+/// Create the tracing span of a call, with the same fields as the HTTP logger
+/// middleware. With `with-otel`, the span continues the trace of the caller.
+pub fn make_span<B>(request: &Request<B>, environment: &Environment) -> tracing::Span {
+    let header = |name| {
+        request
+            .headers()
+            .get(name)
+            .map_or("", |value| value.to_str().unwrap_or(""))
+    };
+
+    let span = tracing::error_span!(
+        "grpc-request",
+        "grpc.method" = tracing::field::display(request.uri().path()),
+        "http.version" = tracing::field::debug(request.version()),
+        "http.user_agent" = tracing::field::display(header("user-agent")),
+        "environment" = tracing::field::display(environment),
+        request_id = tracing::field::display(header(REQUEST_ID_HEADER)),
+    );
+
+    // continue the trace of the caller, from its `traceparent` header
+    #[cfg(feature = "with-otel")]
+    {
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+        let parent = opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.extract(&opentelemetry_http::HeaderExtractor(request.headers()))
+        });
+        span.set_parent(parent);
+    }
+
+    span
+}
+
+#[cfg(all(test, feature = "with-otel"))]
+mod tests {
+    use opentelemetry::trace::{TraceContextExt, TracerProvider};
+    use opentelemetry_sdk::{
+        propagation::TraceContextPropagator, trace::TracerProvider as SdkProvider,
+    };
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    #[test]
+    fn continues_the_trace_of_the_caller() {
+        opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+        let tracer = SdkProvider::builder().build().tracer("test");
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(tracer));
+
+        let request = Request::builder()
+            .uri("/helloworld.Greeter/SayHello")
+            .header(
+                "traceparent",
+                "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            )
+            .body(())
+            .unwrap();
+        let trace_id = tracing::subscriber::with_default(subscriber, || {
+            let span = make_span(&request, &Environment::Test);
+            span.context().span().span_context().trace_id()
+        });
+
+        assert_eq!(trace_id.to_string(), "4bf92f3577b34da6a3ce929d0e0e4736");
+    }
+}
@@ -0,0 +1,348 @@
+use std::{sync::Arc, time::Duration};
+
+use insane_core::{
+    context::Context,
+    error::{Error, Result},
+    hook::Initializer,
+    initializers::InitializerChain,
+    server::{Readiness, Server, ServerLifeCycle},
+    shutdown::ShutdownToken,
+};
+use tokio::{net::TcpListener, sync::Mutex};
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{
+    codegen::http::Request,
+    service::{Routes, RoutesBuilder},
+    transport::Server as TonicServer,
+};
+use tonic_health::ServingStatus;
+use tower_http::{
+    request_id::{PropagateRequestIdLayer, SetRequestIdLayer},
+    trace::TraceLayer,
+};
+
+use crate::{
+    config::GrpcServerConfig,
+    context::GrpcContext,
+    hook::GrpcHooks,
+    middlewares::{self, MakeNanoid},
+};
+
+pub struct GrpcServer<H: GrpcHooks> {
+    pub hooks: H,
+    pub config: Arc<Mutex<Option<GrpcServerConfig>>>,
+}
+
+impl<H: GrpcHooks> GrpcServer<H> {
+    /// Create a new instance of the server
+    pub fn new(hooks: H) -> Self {
+        Self {
+            hooks,
+            config: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Load the `grpc` configuration once and cache it.
+    async fn config(&self, context: &Arc<Box<dyn Context>>) -> Result<GrpcServerConfig> {
+        let mut guard = self.config.lock().await;
+        if let Some(config) = &*guard {
+            return Ok(config.clone());
+        }
+
+        let config =
+            GrpcServerConfig::new(context.environment(), &context.config().application_name)?;
+        *guard = Some(config.clone());
+        Ok(config)
+    }
+
+    /// Collect the services of the application, plus the reflection service
+    /// when enabled.
+    async fn routes(&self, ctx: &GrpcContext) -> Result<RoutesBuilder> {
+        let mut routes = RoutesBuilder::default();
+        self.hooks.services(&mut routes, ctx).await?;
+
+        if ctx.server_config.reflection {
+            let sets = self.hooks.file_descriptor_sets();
+            routes.add_service(
+                Self::reflection(&sets)
+                    .build_v1()
+                    .map_err(|e| Error::msg(e).bt())?,
+            );
+            routes.add_service(
+                Self::reflection(&sets)
+                    .build_v1alpha()
+                    .map_err(|e| Error::msg(e).bt())?,
+            );
+            tracing::info!("[Service] Adding reflection");
+        }
+
+        Ok(routes)
+    }
+
+    fn reflection<'a>(sets: &[&'a [u8]]) -> tonic_reflection::server::Builder<'a> {
+        sets.iter().fold(
+            tonic_reflection::server::Builder::configure()
+                .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET),
+            |builder, set| builder.register_encoded_file_descriptor_set(set),
+        )
+    }
+
+    /// Bind the listener on the configured address and port.
+    async fn bind(config: &GrpcServerConfig) -> Result<TcpListener> {
+        Ok(TcpListener::bind(&format!("{}:{}", config.binding, config.port)).await?)
+    }
+
+    /// Serve the routes on the given listener until `shutdown` is triggered,
+    /// then drain the in-flight calls.
+    async fn start(
+        listener: TcpListener,
+        routes: Routes,
+        ctx: &GrpcContext,
+        shutdown: ShutdownToken,
+    ) -> Result<()> {
+        let incoming = TcpListenerStream::new(listener);
+        let signal = async move { shutdown.triggered().await };
+
+        let mut server = TonicServer::builder();
+        if let Some(timeout) = &ctx.server_config.middlewares.timeout_request {
+            if timeout.enable {
+                server = server.timeout(Duration::from_millis(timeout.timeout));
+                tracing::info!("[Middleware] Adding timeout");
+            }
+        }
+
+        let logger = ctx
+            .server_config
+            .middlewares
+            .logger
+            .as_ref()
+            .is_some_and(|logger| logger.enable);
+
+        let served =
+            if logger {
+                tracing::info!("[Middleware] Adding log trace id");
+                let environment = ctx.environment.clone();
+                server
+                    .layer(SetRequestIdLayer::x_request_id(MakeNanoid))
+                    .layer(PropagateRequestIdLayer::x_request_id())
+                    .layer(TraceLayer::new_for_grpc().make_span_with(
+                        move |request: &Request<_>| middlewares::make_span(request, &environment),
+                    ))
+                    .add_routes(routes)
+                    .serve_with_incoming_shutdown(incoming, signal)
+                    .await
+            } else {
+                server
+                    .add_routes(routes)
+                    .serve_with_incoming_shutdown(incoming, signal)
+                    .await
+            };
+
+        served.map_err(|e| Error::msg(e).bt())
+    }
+}
+
+#[async_trait::async_trait]
+impl<H: GrpcHooks> Server for GrpcServer<H> {
+    fn name(&self) -> String {
+        "grpc_server".to_string()
+    }
+
+    async fn enable(&self, context: Arc<Box<dyn Context>>) -> Result<bool> {
+        Ok(self.config(&context).await?.enable)
+    }
+
+    async fn serve(
+        &self,
+        context: Arc<Box<dyn Context>>,
+        shutdown: ShutdownToken,
+        ready: Readiness,
+    ) -> Result<()> {
+        let config = self.config(&context).await?;
+
+        let (mut health, health_service) = tonic_health::server::health_reporter();
+        let grpc_context = GrpcContext::new(config.clone(), context.clone(), health.clone());
+        let grpc_context_boxed = Arc::new(Box::new(grpc_context.clone()));
+
+        self.before_run(context.clone(), grpc_context_boxed.clone())
+            .await?;
+
+        let initializers = InitializerChain::new(
+            self.initializers(context.clone(), grpc_context_boxed.clone())
+                .await?,
+        )?;
+        tracing::info!(initializers = ?initializers.names().join(","), "server initializers loaded");
+        initializers
+            .before_run(&context, Some(grpc_context_boxed.clone()))
+            .await?;
+
+        // the initializers are shut down even when the server fails to start
+        let served = async {
+            let mut routes = self.routes(&grpc_context).await?;
+            if config.health {
+                routes.add_service(health_service);
+                tracing::info!("[Service] Adding health");
+            }
+
+            let listener = Self::bind(&config).await?;
+            tracing::info!(
+                "{} listening on {}:{}",
+                self.name(),
+                config.binding,
+                config.port
+            );
+
+            initializers
+                .after_start(&context, Some(grpc_context_boxed.clone()))
+                .await?;
+
+            health.set_service_status("", ServingStatus::Serving).await;
+            ready.ready();
+            let served = Self::start(listener, routes.routes(), &grpc_context, shutdown).await;
+            health
+                .set_service_status("", ServingStatus::NotServing)
+                .await;
+            served
+        }
+        .await;
+
+        initializers
+            .before_shutdown(&context, Some(grpc_context_boxed))
+            .await;
+
+        served
+    }
+}
+
+#[async_trait::async_trait]
+impl<H: GrpcHooks> ServerLifeCycle for GrpcServer<H> {
+    type ServerContext = Arc<Box<GrpcContext>>;
+
+    async fn initializers(
+        &self,
+        app_context: Arc<Box<dyn Context>>,
+        context: Self::ServerContext,
+    ) -> Result<Vec<Box<dyn Initializer<Context = Self::ServerContext>>>> {
+        self.hooks.initializers(app_context, context).await
+    }
+
+    async fn before_run(
+        &self,
+        app_context: Arc<Box<dyn Context>>,
+        context: Self::ServerContext,
+    ) -> Result<()> {
+        self.hooks.before_run(app_context, context).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex as StdMutex;
+
+    use insane_core::{config::InsaneConfig, context::test_context};
+    use tonic_health::pb::{health_client::HealthClient, HealthCheckRequest};
+
+    use super::*;
+
+    type Calls = Arc<StdMutex<Vec<&'static str>>>;
+
+    struct Recorder(Calls);
+
+    #[async_trait::async_trait]
+    impl Initializer for Recorder {
+        type Context = Arc<Box<GrpcContext>>;
+
+        fn name(&self) -> String {
+            "recorder".to_string()
+        }
+
+        async fn before_run(
+            &self,
+            _app_context: Arc<Box<dyn Context>>,
+            _context: Option<Self::Context>,
+        ) -> Result<()> {
+            self.0.lock().unwrap().push("before_run");
+            Ok(())
+        }
+
+        async fn before_shutdown(
+            &self,
+            _app_context: Arc<Box<dyn Context>>,
+            _context: Option<Self::Context>,
+        ) -> Result<()> {
+            self.0.lock().unwrap().push("before_shutdown");
+            Ok(())
+        }
+    }
+
+    struct App(Calls);
+
+    #[async_trait::async_trait]
+    impl GrpcHooks for App {
+        async fn services(&self, _routes: &mut RoutesBuilder, _ctx: &GrpcContext) -> Result<()> {
+            Ok(())
+        }
+
+        async fn initializers(
+            &self,
+            _app_context: Arc<Box<dyn Context>>,
+            _context: Arc<Box<GrpcContext>>,
+        ) -> Result<Vec<Box<dyn Initializer<Context = Arc<Box<GrpcContext>>>>>> {
+            Ok(vec![Box::new(Recorder(self.0.clone()))])
+        }
+    }
+
+    fn context() -> Arc<Box<dyn Context>> {
+        test_context(InsaneConfig::default())
+    }
+
+    #[tokio::test]
+    async fn serves_the_health_until_the_shutdown() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let calls = Calls::default();
+        let server = Arc::new(GrpcServer::new(App(calls.clone())));
+        *server.config.lock().await = Some(GrpcServerConfig {
+            binding: "127.0.0.1".to_string(),
+            port: i32::from(port),
+            enable: true,
+            ..GrpcServerConfig::default()
+        });
+
+        let shutdown = ShutdownToken::new();
+        let ready = Readiness::new();
+        let served = tokio::spawn({
+            let (server, shutdown, ready) = (server.clone(), shutdown.clone(), ready.clone());
+            async move { server.serve(context(), shutdown, ready).await }
+        });
+        ready.wait().await;
+        assert_eq!(*calls.lock().unwrap(), ["before_run"]);
+
+        let channel = tonic::transport::Channel::from_shared(format!("http://127.0.0.1:{port}"))
+            .unwrap()
+            .connect()
+            .await
+            .unwrap();
+        let mut health = HealthClient::new(channel);
+        let status = health
+            .check(HealthCheckRequest {
+                service: String::new(),
+            })
+            .await
+            .unwrap()
+            .into_inner()
+            .status();
+        assert_eq!(status, ServingStatus::Serving.into());
+
+        shutdown.trigger();
+        tokio::time::timeout(Duration::from_secs(5), served)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(*calls.lock().unwrap(), ["before_run", "before_shutdown"]);
+    }
+}
@@ -0,0 +1,195 @@
+//! This module contains the administration routes of the running
+//! application, under `/_admin`. They answer `404` unless `http.admin.enable`
+//! is set, and `401` without the `http.admin.token` bearer token.
+//!
+//! * `GET /_admin/log-level`: the filters in use.
+//! * `PUT /_admin/log-level`: add filter directives, reset after `ttl`
+//!   seconds when given, `{"directives": "insane_http=debug", "ttl": 600}`.
+//! * `DELETE /_admin/log-level`: go back to the configured filters.
+//!
+//! ```yaml
+//! http:
+//!   admin:
+//!     enable: true
+//!     token: file:///run/secrets/admin_token
+//! ```
+
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    extract::State,
+    http::{header::AUTHORIZATION, HeaderMap},
+    response::Response,
+    routing::get,
+};
+use insane_core::traces::LogFilter;
+use serde::Deserialize;
+
+use super::error::{Error, Result};
+use super::{format, routes::Routes, Json};
+use crate::context::HttpContext;
+
+/// Body of `PUT /_admin/log-level`.
+#[derive(Debug, Deserialize)]
+struct SetLogLevel {
+    /// Filter directives, like `insane_http=debug,sqlx=trace`.
+    directives: String,
+    /// Seconds before the directives are reset.
+    ttl: Option<u64>,
+}
+
+/// Check the request is allowed and get the log filter.
+fn authorize(ctx: &HttpContext, headers: &HeaderMap) -> Result<Arc<LogFilter>> {
+    let admin = &ctx.server_config.admin;
+    if !admin.enable {
+        return Err(Error::NotFound);
+    }
+
+    let token = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let expected = admin.token.expose();
+    if expected.is_empty() || !token.is_some_and(|token| same(token, expected)) {
+        return Err(Error::Unauthorized("invalid admin token".to_string()));
+    }
+
+    ctx.log_filter
+        .clone()
+        .ok_or_else(|| Error::BadRequest("the logs are not filtered by insane".to_string()))
+}
+
+/// Compare the tokens in a time independent of their content.
+fn same(token: &str, expected: &str) -> bool {
+    token.len() == expected.len()
+        && token
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+async fn log_level(State(ctx): State<HttpContext>, headers: HeaderMap) -> Result<Response> {
+    let log_filter = authorize(&ctx, &headers)?;
+    format::json(log_filter.status())
+}
+
+async fn set_log_level(
+    State(ctx): State<HttpContext>,
+    headers: HeaderMap,
+    Json(params): Json<SetLogLevel>,
+) -> Result<Response> {
+    let log_filter = authorize(&ctx, &headers)?;
+    let status = log_filter
+        .set(&params.directives, params.ttl.map(Duration::from_secs))
+        .map_err(|err| Error::BadRequest(err.to_string()))?;
+    format::json(status)
+}
+
+async fn reset_log_level(State(ctx): State<HttpContext>, headers: HeaderMap) -> Result<Response> {
+    let log_filter = authorize(&ctx, &headers)?;
+    format::json(log_filter.reset()?)
+}
+
+/// Defines and returns the administration routes.
+pub fn routes() -> Routes {
+    Routes::at("_admin").add(
+        "/log-level",
+        get(log_level).put(set_log_level).delete(reset_log_level),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+        Router,
+    };
+    use insane_core::{config::InsaneConfig, context::test_context};
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::config::{AdminConfig, HTTPServerConfig};
+
+    fn app(admin: AdminConfig) -> Router {
+        let server_config = HTTPServerConfig {
+            admin,
+            ..Default::default()
+        };
+        let ctx = HttpContext::new(server_config, test_context(InsaneConfig::default()));
+        Router::new()
+            .route(
+                "/_admin/log-level",
+                get(log_level).put(set_log_level).delete(reset_log_level),
+            )
+            .with_state(ctx)
+    }
+
+    async fn status(admin: AdminConfig, authorization: Option<&str>) -> StatusCode {
+        let mut request = Request::get("/_admin/log-level");
+        if let Some(authorization) = authorization {
+            request = request.header(AUTHORIZATION, authorization);
+        }
+        app(admin)
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    fn enabled(token: &str) -> AdminConfig {
+        AdminConfig {
+            enable: true,
+            token: token.into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn answers_not_found_when_disabled() {
+        let admin = AdminConfig {
+            enable: false,
+            token: "secret".into(),
+        };
+        assert_eq!(
+            status(admin, Some("Bearer secret")).await,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn requires_the_bearer_token() {
+        assert_eq!(
+            status(enabled("secret"), None).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(enabled("secret"), Some("Bearer wrong!")).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(enabled("secret"), Some("secret")).await,
+            StatusCode::UNAUTHORIZED
+        );
+        // the logs are not filtered by insane in the tests
+        assert_eq!(
+            status(enabled("secret"), Some("Bearer secret")).await,
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[tokio::test]
+    async fn refuses_every_token_without_a_configured_one() {
+        assert_eq!(
+            status(enabled(""), Some("Bearer ")).await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[test]
+    fn compares_the_tokens() {
+        assert!(same("secret", "secret"));
+        assert!(!same("secret", "secreT"));
+        assert!(!same("secret", "secrets"));
+    }
+}
@@ -1,6 +1,9 @@
 use crate::error::{Error, Result};
 use insane_core::{
-    config::loader::{Config, ConfigLoader},
+    config::{
+        loader::{Config, ConfigLoader},
+        Secret,
+    },
     environment::Environment,
 };
 use serde::{Deserialize, Serialize};
@@ -68,6 +71,15 @@ pub struct LimitPayloadMiddleware {
     pub body_limit: String,
 }
 
+/// Administration routes configuration, see [`crate::admin`].
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct AdminConfig {
+    pub enable: bool,
+    /// The bearer token of the requests, every request is refused when empty.
+    #[serde(default)]
+    pub token: Secret,
+}
+
 /// A generic middleware configuration that can be enabled or
 /// disabled.
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
@@ -86,6 +98,10 @@ pub struct HTTPServerConfig {
     /// logging, and error handling.
     pub middlewares: Middlewares,
 
+    /// Administration routes, under `/_admin`.
+    #[serde(default)]
+    pub admin: AdminConfig,
+
     /// Enable the server
     pub enable: bool,
 }
@@ -103,11 +119,9 @@ impl HTTPServerConfig {
 
 #[async_trait::async_trait]
 impl Config for HTTPServerConfig {
-
     fn enable(&self) -> bool {
         self.enable
     }
-
 }
 
 #[async_trait::async_trait]
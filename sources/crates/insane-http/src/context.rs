@@ -2,6 +2,7 @@ use insane_core::config::InsaneConfig;
 // use insane_core::context::ServerContext;
 use crate::config::HTTPServerConfig;
 use insane_core::prelude::*;
+use insane_core::traces::LogFilter;
 // use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
@@ -13,6 +14,22 @@ pub struct HttpContext {
 
     #[cfg(feature = "with-sql")]
     pub sql: DatabaseConnection,
+
+    #[cfg(feature = "with-redis")]
+    pub redis: Option<RedisPool>,
+
+    /// The cache of the application, see [`insane_core::cache`].
+    pub cache: Arc<dyn Cache>,
+
+    /// The file storage of the application, see [`insane_core::storage`].
+    pub storage: Arc<dyn Storage>,
+
+    /// Services registered on the application context, see
+    /// [`crate::extension::Ext`].
+    pub extensions: Extensions,
+
+    /// The filters of the logs, see [`insane_core::traces::LogFilter`].
+    pub log_filter: Option<Arc<LogFilter>>,
 }
 
 impl HttpContext {
@@ -21,7 +38,14 @@ impl HttpContext {
             server_config,
             config: context.config().clone(),
             environment: context.environment().clone(),
+            #[cfg(feature = "with-sql")]
             sql: context.sql().clone(),
+            #[cfg(feature = "with-redis")]
+            redis: context.redis().cloned(),
+            cache: context.cache().clone(),
+            storage: context.storage().clone(),
+            extensions: context.extensions().clone(),
+            log_filter: context.log_filter().cloned(),
         }
     }
 }
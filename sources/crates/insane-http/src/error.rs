@@ -1,202 +1,26 @@
 //! # Application Error Handling
-use axum::{
-    extract::rejection::JsonRejection,
-    http::{
-        header::{InvalidHeaderName, InvalidHeaderValue},
-        method::InvalidMethod,
-        StatusCode,
-    },
-    response::{IntoResponse, Response},
-};
-
-use super::Json;
-
-use colored::Colorize;
-use insane_core::error::Error as InsaneError;
-use serde::Serialize;
-
-/// Application results options list
-pub type Result<T, E = Error> = std::result::Result<T, E>;
-
-// TODO: Refactor this to avoid duplication in all other errors
-#[derive(thiserror::Error, Debug)]
-pub enum Error {
-    #[error("{inner}\n{backtrace}")]
-    WithBacktrace {
-        inner: Box<Self>,
-        backtrace: Box<std::backtrace::Backtrace>,
-    },
-
-    #[error(transparent)]
-    IO(#[from] std::io::Error),    
-
-    #[error(transparent)]
-    Axum(#[from] axum::http::Error),
-
-    #[error(transparent)]
-    JSON(serde_json::Error),
-
-    #[error(transparent)]
-    JsonRejection(#[from] JsonRejection),
-
-    #[error("{0}")]
-    Message(String),
-
-    // API
-    #[error("{0}")]
-    Unauthorized(String),
-
-    #[error("not found")]
-    NotFound,
-
-    #[error("{0}")]
-    BadRequest(String),
-
-    #[error("")]
-    CustomError(StatusCode, ErrorDetail),
-
-    #[error("internal server error")]
-    InternalServerError,
-
-    #[error(transparent)]
-    InvalidHeaderValue(#[from] InvalidHeaderValue),
-
-    #[error(transparent)]
-    InvalidHeaderName(#[from] InvalidHeaderName),
-
-    #[error(transparent)]
-    InvalidMethod(#[from] InvalidMethod),
-
-    #[error(transparent)]
-    InsaneError(#[from] InsaneError),
-
-    #[error(transparent)]
-    Any(#[from] Box<dyn std::error::Error + Send + Sync>),
-
-    #[error(transparent)]
-    Anyhow(#[from] eyre::Report),    
-}
-
-/*
-backtrace principles:
-- use a plan warapper variant with no 'from' conversion
-- hand-code "From" conversion and force capture there with 'bt', which
-  will wrap and create backtrace only if RUST_BACKTRACE=1.
-costs:
-- when RUST_BACKTRACE is not set, we don't pay for the capture and we dont pay for printing.
-
- */
-impl From<serde_json::Error> for Error {
-    fn from(val: serde_json::Error) -> Self {
-        Self::JSON(val).bt()
-    }
-  }
-  
-impl Error {
-    pub fn msg(err: impl std::error::Error + Send + Sync + 'static) -> Self {
-        Self::Message(err.to_string()) //.bt()
-    }
-
-    #[must_use]
-    pub fn bt(self) -> Self {
-        let backtrace = std::backtrace::Backtrace::capture();
-        match backtrace.status() {
-            std::backtrace::BacktraceStatus::Disabled
-            | std::backtrace::BacktraceStatus::Unsupported => self,
-            _ => Self::WithBacktrace {
-                inner: Box::new(self),
-                backtrace: Box::new(backtrace),
-            },
-        }
-    }
-}
-
-impl IntoResponse for Error {
-    /// Convert an `Error` into an HTTP response.
-    fn into_response(self) -> Response {
-        match &self {
-            Self::WithBacktrace {
-                inner,
-                backtrace: _,
-            } => {
-                tracing::error!(
-                error.msg = %inner,
-                error.details = ?inner,
-                "controller_error"
-                );
-            }
-            err => {
-                tracing::error!(
-                error.msg = %err,
-                error.details = ?err,
-                "controller_error"
-                );
-            }
-        }
-
-        let public_facing_error = match self {
-            Self::NotFound => (
-                StatusCode::NOT_FOUND,
-                ErrorDetail::new("not_found", "Resource was not found"),
-            ),
-            Self::InternalServerError => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                ErrorDetail::new("internal_server_error", "Internal Server Error"),
-            ),
-            Self::Unauthorized(err) => {
-                tracing::warn!(err);
-                (
-                    StatusCode::UNAUTHORIZED,
-                    ErrorDetail::new(
-                        "unauthorized",
-                        "You do not have permission to access this resource",
-                    ),
-                )
-            }
-            Self::CustomError(status_code, data) => (status_code, data),
-            Self::WithBacktrace { inner, backtrace } => {
-                println!("\n{}", inner.to_string().red().underline());
-                insane_core::backtrace::print_backtrace(&backtrace).unwrap();
-                (
-                    StatusCode::BAD_REQUEST,
-                    ErrorDetail::with_reason("Bad Request"),
-                )
-            }
-            _ => (
-                StatusCode::BAD_REQUEST,
-                ErrorDetail::with_reason("Bad Request"),
-            ),
-        };
-
-        (public_facing_error.0, Json(public_facing_error.1)).into_response()
-    }
-}
-
-#[derive(Debug, Serialize)]
-/// Structure representing details about an error.
-pub struct ErrorDetail {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub error: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub description: Option<String>,
-}
-
-impl ErrorDetail {
-    /// Create a new `ErrorDetail` with the specified error and description.
-    #[must_use]
-    pub fn new<T: Into<String>>(error: T, description: T) -> Self {
-        Self {
-            error: Some(error.into()),
-            description: Some(description.into()),
-        }
-    }
-
-    /// Create an `ErrorDetail` with only an error reason and no description.
-    #[must_use]
-    pub fn with_reason<T: Into<String>>(error: T) -> Self {
-        Self {
-            error: Some(error.into()),
-            description: None,
-        }
-    }
-}
+//!
+//! The handlers return the error of the framework,
+//! [`insane_core::error::Error`]. It is answered with the HTTP status of its
+//! [`ErrorCode`] and a JSON body telling the code:
+//!
+//! ```json
+//! {"error": "not_found", "description": "Resource was not found"}
+//! ```
+//!
+//! The client errors describe what went wrong with their message, the server
+//! errors only with their status, their details are logged.
+//!
+//! ```rust
+//! use insane_http::{error::{Error, ErrorCode, Result}, format, prelude::*};
+//!
+//! async fn transfer() -> Result<Response> {
+//!     let balance = 10;
+//!     if balance < 100 {
+//!         return Err(Error::new(ErrorCode::CONFLICT, "insufficient balance"));
+//!     }
+//!     format::empty()
+//! }
+//! ```
+
+pub use insane_core::error::{Error, ErrorCode, ErrorDetail, FieldError, FieldErrors, Result};
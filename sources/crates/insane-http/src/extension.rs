@@ -0,0 +1,104 @@
+//! Extractor giving handlers access to the services registered on the
+//! application context [`insane_core::extensions::Extensions`].
+//!
+//! # Example:
+//!
+//! ```rust
+//! use insane_http::{error::Result, format, prelude::*};
+//!
+//! struct Greeter {
+//!     greeting: String,
+//! }
+//!
+//! async fn hello(Ext(greeter): Ext<Greeter>) -> Result<Response> {
+//!     format::text(&greeter.greeting)
+//! }
+//! ```
+
+use std::{ops::Deref, sync::Arc};
+
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
+
+use crate::{context::HttpContext, error::Error};
+
+/// Extract the service of type `T` registered on the application context.
+///
+/// Rejects the request with an internal server error when no such service is
+/// registered.
+#[derive(Debug)]
+pub struct Ext<T>(pub Arc<T>);
+
+impl<T> Deref for Ext<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> Clone for Ext<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+#[async_trait]
+impl<T: Send + Sync + 'static> FromRequestParts<HttpContext> for Ext<T> {
+    type Rejection = Error;
+
+    async fn from_request_parts(_parts: &mut Parts, state: &HttpContext) -> Result<Self, Error> {
+        state.extensions.get::<T>().map(Self).ok_or_else(|| {
+            tracing::error!(
+                extension = std::any::type_name::<T>(),
+                "extension is not registered on the context"
+            );
+            Error::InternalServerError
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, http::StatusCode, routing::get, Router};
+    use insane_core::{config::InsaneConfig, context::test_context};
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::config::HTTPServerConfig;
+
+    struct Greeter(&'static str);
+
+    async fn status(greeter: Option<Greeter>) -> (StatusCode, String) {
+        let context = test_context(InsaneConfig::default());
+        if let Some(greeter) = greeter {
+            context.extensions().insert(greeter);
+        }
+        let router = Router::new()
+            .route(
+                "/",
+                get(|Ext(greeter): Ext<Greeter>| async move { greeter.0 }),
+            )
+            .with_state(HttpContext::new(HTTPServerConfig::default(), context));
+
+        let request = axum::http::Request::get("/").body(Body::empty()).unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn extracts_the_registered_service() {
+        let (status, body) = status(Some(Greeter("hello"))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "hello");
+    }
+
+    #[tokio::test]
+    async fn rejects_a_missing_service() {
+        let (status, _) = status(None).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
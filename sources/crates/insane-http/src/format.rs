@@ -10,7 +10,7 @@
 //! Rust struct.
 //!
 //! ```rust
-//! use loco_rs::prelude::*;
+//! use insane_http::{error::Result, format, prelude::*};
 //! use serde::Serialize;
 //!
 //! #[derive(Serialize)]
@@ -35,8 +35,8 @@ use serde::Serialize;
 use serde_json::json;
 
 // use super::views::ViewRenderer;
-use crate::error::Result;
 use super::Json;
+use crate::error::Result;
 
 /// Returns an empty response.
 ///
@@ -44,7 +44,7 @@ use super::Json;
 ///
 /// This example illustrates how to return an empty response.
 /// ```rust
-/// use loco_rs::prelude::*;
+/// use insane_http::{error::Result, format, prelude::*};
 ///
 /// async fn endpoint() -> Result<Response> {
 ///    format::empty()
@@ -65,7 +65,7 @@ pub fn empty() -> Result<Response> {
 ///
 /// This example illustrates how to return an text response.
 /// ```rust
-/// use loco_rs::prelude::*;
+/// use insane_http::{error::Result, format, prelude::*};
 ///
 /// async fn endpoint() -> Result<Response> {
 ///    format::text("MESSAGE-RESPONSE")
@@ -88,7 +88,7 @@ pub fn text(t: &str) -> Result<Response> {
 /// Rust struct.
 ///
 /// ```rust
-/// use loco_rs::prelude::*;
+/// use insane_http::{error::Result, format, prelude::*};
 /// use serde::Serialize;
 ///
 /// #[derive(Serialize)]
@@ -122,7 +122,7 @@ pub fn empty_json() -> Result<Response> {
 /// # Example:
 ///
 /// ```rust
-/// use loco_rs::prelude::*;
+/// use insane_http::{error::Result, format, prelude::*};
 ///
 /// async fn endpoint() -> Result<Response> {
 ///    format::html("hello, world")
@@ -7,9 +7,9 @@
 use axum::{extract::State, response::Response, routing::get};
 use serde::Serialize;
 
-use crate::context::HttpContext;
-use super::{format, routes::Routes};
 use super::error::Result;
+use super::{format, routes::Routes};
+use crate::context::HttpContext;
 
 /// Represents the health status of the application.
 #[derive(Serialize)]
@@ -20,22 +20,22 @@ struct Health {
 /// Check the healthiness of the application bt ping to the redis and the DB to
 /// insure that connection
 async fn health(State(ctx): State<HttpContext>) -> Result<Response> {
+    #[allow(unused_mut)]
+    let mut is_ok = true;
+
     #[cfg(feature = "with-sql")]
-    let is_ok = match ctx.sql.ping().await {
-        Ok(()) => true,
-        Err(error) => {
-            tracing::error!(err.msg = %error, err.detail = ?error, "health_db_ping_error");
-            false
-        }
-    };
+    if let Err(error) = ctx.sql.ping().await {
+        tracing::error!(err.msg = %error, err.detail = ?error, "health_db_ping_error");
+        is_ok = false;
+    }
 
-    // #[cfg(feature = "with-redis")]
-    // if let Some(pool) = ctx.redis {
-    //     if let Err(error) = redis::ping(&pool).await {
-    //         tracing::error!(err.msg = %error, err.detail = ?error, "health_redis_ping_error");
-    //         is_ok = false;
-    //     }
-    // }
+    #[cfg(feature = "with-redis")]
+    if let Some(pool) = &ctx.redis {
+        if let Err(error) = insane_core::redis::ping(pool).await {
+            tracing::error!(err.msg = %error, err.detail = ?error, "health_redis_ping_error");
+            is_ok = false;
+        }
+    }
 
     format::json(Health { ok: is_ok })
 }
@@ -44,3 +44,63 @@ async fn health(State(ctx): State<HttpContext>) -> Result<Response> {
 pub fn routes() -> Routes {
     Routes::new().add("/_health", get(health))
 }
+
+#[cfg(all(test, feature = "with-redis"))]
+mod tests {
+    use std::{net::TcpListener, time::Duration};
+
+    use axum::{body::Body, http::Request};
+    use insane_core::{
+        config::InsaneConfig,
+        context::test_context,
+        redis::{test_server, RedisConnectionManager},
+    };
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::{config::HTTPServerConfig, http_routes::HttpRoutes};
+
+    async fn health_of(addr: std::net::SocketAddr) -> serde_json::Value {
+        let context = test_context(InsaneConfig::default());
+        let mut ctx = HttpContext::new(HTTPServerConfig::default(), context.clone());
+        #[cfg(feature = "with-sql")]
+        {
+            let config = insane_core::config::SqlConfig {
+                uri: "sqlite::memory:".into(),
+                ..Default::default()
+            };
+            ctx.sql = insane_core::sql::connect(&config).await.unwrap();
+        }
+        let manager = RedisConnectionManager::new(format!("redis://{addr}")).unwrap();
+        ctx.redis = Some(
+            bb8::Pool::builder()
+                .connection_timeout(Duration::from_millis(200))
+                .build_unchecked(manager),
+        );
+        let router = HttpRoutes::empty()
+            .add_route(routes())
+            .to_router(ctx, context)
+            .unwrap();
+
+        let request = Request::get("/_health").body(Body::empty()).unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn pings_redis() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        test_server(listener);
+        assert_eq!(health_of(addr).await, serde_json::json!({ "ok": true }));
+
+        let closed = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        assert_eq!(health_of(closed).await, serde_json::json!({ "ok": false }));
+    }
+}
@@ -1,16 +1,18 @@
-
-use crate::error::Result;
 use crate::context::HttpContext;
+use crate::error::Result;
 use crate::http_routes::HttpRoutes;
+use crate::renderer::ErrorRenderer;
 // use crate::server::HttpServer;
 use axum::Router as AxumRouter;
 // use insane_core::{context::Context, error::Result as CoreResult, server::Server};
 
 use insane_core::prelude::*;
+use std::sync::Arc;
 
 #[async_trait::async_trait]
 pub trait HttpHooks: Sync + Send {
     /// Defines the application's routing configuration.
+    #[allow(clippy::borrowed_box)]
     fn routes(&self, _ctx: &HttpContext, _context: &Box<dyn Context>) -> HttpRoutes;
     // fn routes(&self, _context: Box<dyn Context>) -> HttpRoutes;
 
@@ -24,6 +26,12 @@ pub trait HttpHooks: Sync + Send {
         Ok(router)
     }
 
+    /// Renders the errors of the handlers, instead of the JSON body of
+    /// [`crate::error::ErrorDetail`]. See [`crate::renderer`].
+    fn error_renderer(&self) -> Option<Arc<dyn ErrorRenderer>> {
+        None
+    }
+
     // /// Create a new instance of the server
     // async fn new(hooks: Self) -> CoreResult<Box<dyn Server>> {
     //     Ok(Box::new(HttpServer{ hooks: Self }))
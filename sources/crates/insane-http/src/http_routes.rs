@@ -2,11 +2,12 @@
 //! configuring routes in an Axum application. It allows you to define route
 //! prefixes, add routes, and configure middlewares for the application.
 
-use crate::error::{Error, Result};
+use crate::error::{Error, ErrorCode, Result};
 use crate::{
     config::{CorsMiddleware, LimitPayloadMiddleware, TimeoutRequestMiddleware},
     context::HttpContext,
-    middlewares::etag::EtagLayer,
+    middlewares::{etag::EtagLayer, metrics},
+    renderer::{self, ErrorRenderer, RequestId},
 };
 use axum::{http, response::IntoResponse, Router as AXRouter};
 use insane_core::context::Context;
@@ -18,12 +19,8 @@ use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 use tower_http::{
-    add_extension::AddExtensionLayer,
-    catch_panic::CatchPanicLayer,
-    compression::CompressionLayer,
-    cors,
-    timeout::TimeoutLayer,
-    trace::TraceLayer,
+    add_extension::AddExtensionLayer, catch_panic::CatchPanicLayer, compression::CompressionLayer,
+    cors, timeout::TimeoutLayer, trace::TraceLayer,
 };
 
 use super::routes::Routes;
@@ -41,6 +38,7 @@ lazy_static! {
 pub struct HttpRoutes {
     prefix: Option<String>,
     routes: Vec<Routes>,
+    error_renderer: Option<Arc<dyn ErrorRenderer>>,
     // #[cfg(feature = "channels")]
     // channels: Option<AppChannels>,
 }
@@ -51,8 +49,8 @@ pub struct ListRoutes {
     pub method: axum::routing::MethodRouter<HttpContext>,
 }
 
-impl ToString for ListRoutes {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for ListRoutes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let actions_str = self
             .actions
             .iter()
@@ -60,7 +58,7 @@ impl ToString for ListRoutes {
             .collect::<Vec<_>>()
             .join(",");
         // Define your custom logic here to format the struct as a string
-        format!("[{}] {}", actions_str, self.uri)
+        write!(f, "[{}] {}", actions_str, self.uri)
     }
 }
 
@@ -68,7 +66,10 @@ impl HttpRoutes {
     /// Create a new instance with the default routes.
     #[must_use]
     pub fn with_default_routes() -> Self {
-        let routes = Self::empty().add_route(super::ping::routes());
+        let routes = Self::empty()
+            .add_route(super::ping::routes())
+            .add_route(super::admin::routes())
+            .add_route(super::metrics::routes());
         #[cfg(feature = "with-sql")]
         let routes = routes.add_route(super::health::routes());
 
@@ -81,6 +82,7 @@ impl HttpRoutes {
         Self {
             prefix: None,
             routes: vec![],
+            error_renderer: None,
             // #[cfg(feature = "channels")]
             // channels: None,
         }
@@ -140,7 +142,7 @@ impl HttpRoutes {
     /// In the following example you are adding api as a prefix for all routes
     ///
     /// ```rust
-    /// use loco_rs::controller::HttpRoutes;
+    /// use insane_http::http_routes::HttpRoutes;
     ///
     /// HttpRoutes::with_default_routes().prefix("api");
     /// ```
@@ -166,6 +168,14 @@ impl HttpRoutes {
         self
     }
 
+    /// Render the errors of the handlers with `renderer`, see
+    /// [`crate::renderer`].
+    #[must_use]
+    pub fn error_renderer(mut self, renderer: Arc<dyn ErrorRenderer>) -> Self {
+        self.error_renderer = Some(renderer);
+        self
+    }
+
     // #[cfg(feature = "channels")]
     // #[must_use]
     // pub fn add_app_channels(mut self, channels: AppChannels) -> Self {
@@ -188,7 +198,7 @@ impl HttpRoutes {
         let mut app = AXRouter::new();
 
         for router in self.collect() {
-            tracing::info!("{}", router.to_string());
+            tracing::info!("{}", router);
 
             app = app.route(&router.uri, router.method);
         }
@@ -201,6 +211,12 @@ impl HttpRoutes {
             }
         }
 
+        // inside the other middlewares, so that the rendered errors get the
+        // CORS headers, are compressed and so on
+        if let Some(error_renderer) = &self.error_renderer {
+            app = Self::add_error_renderer(app, error_renderer.clone());
+        }
+
         if let Some(compression) = &ctx.server_config.middlewares.compression {
             if compression.enable {
                 app = Self::add_compression_middleware(app);
@@ -251,6 +267,15 @@ impl HttpRoutes {
             }
         }
 
+        if self.error_renderer.is_some() {
+            app = Self::add_request_id_middleware(app);
+        }
+
+        // outermost, to time the requests through the other middlewares
+        if ctx.config.metrics.enable {
+            app = Self::add_metrics_middleware(app);
+        }
+
         // #[cfg(feature = "channels")]
         // if let Some(channels) = self.channels.as_ref() {
         //     tracing::info!("[Middleware] Adding channels");
@@ -314,6 +339,36 @@ impl HttpRoutes {
         app
     }
 
+    fn add_error_renderer(
+        app: AXRouter<HttpContext>,
+        error_renderer: Arc<dyn ErrorRenderer>,
+    ) -> AXRouter<HttpContext> {
+        // rendered instead of the empty responses of axum
+        let app = app
+            .fallback(|| async { Error::NotFound })
+            .method_not_allowed_fallback(|| async {
+                Error::new(ErrorCode::METHOD_NOT_ALLOWED, "Method not allowed")
+            })
+            .layer(axum::middleware::from_fn_with_state(
+                error_renderer,
+                renderer::render,
+            ));
+        tracing::info!("[Middleware] Adding error renderer");
+        app
+    }
+
+    fn add_request_id_middleware(app: AXRouter<HttpContext>) -> AXRouter<HttpContext> {
+        let app = app.layer(axum::middleware::from_fn(renderer::request_id));
+        tracing::info!("[Middleware] Adding request id");
+        app
+    }
+
+    fn add_metrics_middleware(app: AXRouter<HttpContext>) -> AXRouter<HttpContext> {
+        let app = app.layer(axum::middleware::from_fn(metrics::track));
+        tracing::info!("[Middleware] Adding metrics");
+        app
+    }
+
     fn get_cors_middleware(config: &CorsMiddleware) -> Result<cors::CorsLayer> {
         let mut cors: cors::CorsLayer = cors::CorsLayer::permissive();
 
@@ -379,7 +434,10 @@ impl HttpRoutes {
         let app = app
             .layer(
                 TraceLayer::new_for_http().make_span_with(|request: &http::Request<_>| {
-                    let request_id = nanoid!();
+                    let request_id = request
+                        .extensions()
+                        .get::<RequestId>()
+                        .map_or_else(|| nanoid!(), |id| id.0.clone());
                     let user_agent = request
                         .headers()
                         .get(axum::http::header::USER_AGENT)
@@ -391,7 +449,7 @@ impl HttpRoutes {
                         .map(std::string::ToString::to_string)
                         .unwrap_or_default();
 
-                    tracing::error_span!(
+                    let span = tracing::error_span!(
                         "http-request",
                         "http.method" = tracing::field::display(request.method()),
                         "http.uri" = tracing::field::display(request.uri()),
@@ -399,7 +457,21 @@ impl HttpRoutes {
                         "http.user_agent" = tracing::field::display(user_agent),
                         "environment" = tracing::field::display(env),
                         request_id = tracing::field::display(request_id),
-                    )
+                    );
+
+                    // continue the trace of the caller, from its `traceparent` header
+                    #[cfg(feature = "with-otel")]
+                    {
+                        use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+                        let parent = opentelemetry::global::get_text_map_propagator(|propagator| {
+                            propagator
+                                .extract(&opentelemetry_http::HeaderExtractor(request.headers()))
+                        });
+                        span.set_parent(parent);
+                    }
+
+                    span
                 }),
             )
             .layer(AddExtensionLayer::new(environment.clone()));
@@ -466,3 +538,162 @@ fn handle_panic(err: Box<dyn std::any::Any + Send + 'static>) -> axum::response:
 
     Error::InternalServerError.into_response()
 }
+
+#[cfg(all(test, feature = "with-otel"))]
+mod tests {
+    use std::sync::Mutex;
+
+    use axum::body::Body;
+    use insane_core::{
+        config::{InsaneConfig, OtelBatchConfig, OtelConfig, OtelProtocol, TraceConfig},
+        context::test_context,
+        hook::Hooks,
+        server::Server,
+        traces,
+    };
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::config::{EnableMiddleware, HTTPServerConfig, Middlewares};
+
+    const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+    const PARENT_ID: &str = "00f067aa0ba902b7";
+
+    struct App;
+
+    #[async_trait::async_trait]
+    impl Hooks for App {
+        fn app_name() -> &'static str {
+            "http_test"
+        }
+
+        async fn servers(_app_context: Arc<Box<dyn Context>>) -> Result<Vec<Box<dyn Server>>> {
+            Ok(vec![])
+        }
+
+        #[cfg(feature = "with-sql")]
+        async fn truncate(_db: &insane_core::prelude::DatabaseConnection) -> Result<()> {
+            Ok(())
+        }
+
+        #[cfg(feature = "with-sql")]
+        async fn seed(
+            _db: &insane_core::prelude::DatabaseConnection,
+            _path: &std::path::Path,
+        ) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    /// A collector stub, recording the bodies of the OTLP requests.
+    async fn collector() -> (String, Arc<Mutex<Vec<u8>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let bodies = Arc::new(Mutex::new(Vec::new()));
+        let recorded = bodies.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let bodies = recorded.clone();
+                tokio::spawn(async move {
+                    let mut stream = BufReader::new(stream);
+                    let mut line = String::new();
+                    while stream.read_line(&mut line).await.unwrap_or(0) > 0 {
+                        let mut length = 0;
+                        loop {
+                            let mut header = String::new();
+                            stream.read_line(&mut header).await.unwrap();
+                            match header.trim_end().split_once(':') {
+                                Some((name, value))
+                                    if name.eq_ignore_ascii_case("content-length") =>
+                                {
+                                    length = value.trim().parse().unwrap();
+                                }
+                                Some(_) => {}
+                                None => break,
+                            }
+                        }
+                        let mut body = vec![0; length];
+                        stream.read_exact(&mut body).await.unwrap();
+                        bodies.lock().unwrap().extend(body);
+
+                        let response =
+                            "HTTP/1.1 200 OK\r\ncontent-type: application/x-protobuf\r\n\
+                                        content-length: 0\r\n\r\n";
+                        stream.write_all(response.as_bytes()).await.unwrap();
+                        line.clear();
+                    }
+                });
+            }
+        });
+        (endpoint, bodies)
+    }
+
+    fn context() -> Arc<Box<dyn Context>> {
+        test_context(InsaneConfig::default())
+    }
+
+    /// The protobuf encoding of the `bytes` field `field` holding `hex`.
+    fn field(field: u8, hex: &str) -> Vec<u8> {
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect::<Vec<_>>();
+        let mut encoded = vec![field << 3 | 2, u8::try_from(bytes.len()).unwrap()];
+        encoded.extend(bytes);
+        encoded
+    }
+
+    fn contains(body: &[u8], part: &[u8]) -> bool {
+        body.windows(part.len()).any(|window| window == part)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn continues_the_trace_of_the_traceparent_header() {
+        // the only test of the crate installing the subscriber
+        let (endpoint, bodies) = collector().await;
+        let mut config = TraceConfig::default();
+        config.otel = Some(OtelConfig {
+            protocol: OtelProtocol::Http,
+            endpoint: Some(endpoint),
+            timeout: 5_000,
+            filter: None,
+            sampling_ratio: 1.0,
+            batch: OtelBatchConfig::default(),
+            metrics: false,
+            metrics_interval: 60_000,
+        });
+        traces::init::<App>(&config).unwrap();
+
+        let server_config = HTTPServerConfig {
+            middlewares: Middlewares {
+                logger: Some(EnableMiddleware { enable: true }),
+                ..Middlewares::default()
+            },
+            ..HTTPServerConfig::default()
+        };
+        let context = context();
+        let router = HttpRoutes::with_default_routes()
+            .to_router(HttpContext::new(server_config, context.clone()), context)
+            .unwrap();
+        let request = http::Request::get("/_ping")
+            .header("traceparent", format!("00-{TRACE_ID}-{PARENT_ID}-01"))
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), http::StatusCode::OK);
+        // the span ends with the body
+        drop(response);
+        // exported on shutdown, before the batch delay
+        tokio::task::spawn_blocking(traces::flush).await.unwrap();
+
+        let body = bodies.lock().unwrap().clone();
+        assert!(contains(&body, b"http-request"));
+        // `trace_id` is the field 1 of a span, `parent_span_id` the field 4
+        assert!(contains(&body, &field(1, TRACE_ID)));
+        assert!(contains(&body, &field(4, PARENT_ID)));
+    }
+}
@@ -1,15 +1,20 @@
-pub mod hook;
-pub mod error;
+pub mod admin;
+pub mod config;
 pub mod context;
-pub mod routes;
-pub mod http_routes;
 pub mod describe;
+pub mod error;
+pub mod extension;
+pub mod format;
+pub mod health;
+pub mod hook;
+pub mod http_routes;
+pub mod metrics;
 pub mod middlewares;
 pub mod ping;
-pub mod health;
-pub mod format;
-pub mod config;
+pub mod renderer;
+pub mod routes;
 pub mod server;
+pub mod validation;
 
 use axum::extract::FromRequest;
 use axum::response::IntoResponse;
@@ -18,16 +23,18 @@ use serde::Serialize;
 use error::{Error, Result};
 
 pub mod prelude {
-  pub use async_trait::async_trait;
-  pub use axum::{
-      extract::{Form, Path, State},
-      response::{IntoResponse, Response},
-      routing::{delete, get, post, put},
-  };
-  pub use axum_extra::extract::cookie;
+    pub use crate::extension::Ext;
+    pub use crate::validation::{ValidatedForm, ValidatedJson, ValidatedQuery};
+    pub use async_trait::async_trait;
+    pub use axum::{
+        extract::{Form, Path, State},
+        response::{IntoResponse, Response},
+        routing::{delete, get, post, put},
+    };
+    pub use axum_extra::extract::cookie;
+    pub use validator::Validate;
 }
 
-
 /// Create an unauthorized error with a specified message.
 ///
 /// This function is used to generate an `Error::Unauthorized` variant with a
@@ -40,7 +47,7 @@ pub mod prelude {
 /// # Example
 ///
 /// ```rust
-/// use loco_rs::prelude::*;
+/// use insane_http::{error::Result, format, prelude::*, unauthorized};
 ///
 /// async fn login() -> Result<Response> {
 ///     let valid = false;
@@ -81,4 +88,3 @@ impl<T: Serialize> IntoResponse for Json<T> {
         axum::Json(self.0).into_response()
     }
 }
-
@@ -0,0 +1,105 @@
+//! This module serves the metrics of [`insane_core::metrics`] to Prometheus.
+//! They answer `404` unless `metrics.enable` is set. With `metrics.port`, they
+//! are served on that port instead, see [`serve`], to keep them off the
+//! public port of the application.
+//!
+//! * `GET /_metrics`: the metrics in the Prometheus text format.
+//!
+//! ```yaml
+//! metrics:
+//!   enable: true
+//!   port: 9100
+//! ```
+
+use axum::{
+    extract::State,
+    http::header::CONTENT_TYPE,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router as AXRouter,
+};
+use insane_core::{metrics, shutdown::ShutdownToken};
+use tokio::net::TcpListener;
+
+use super::error::{Error, Result};
+use super::routes::Routes;
+use crate::context::HttpContext;
+
+fn render() -> Result<Response> {
+    Ok(([(CONTENT_TYPE, metrics::CONTENT_TYPE)], metrics::render()?).into_response())
+}
+
+async fn scrape(State(ctx): State<HttpContext>) -> Result<Response> {
+    let config = &ctx.config.metrics;
+    if !config.enable || config.port.is_some() {
+        return Err(Error::NotFound);
+    }
+    render()
+}
+
+/// Defines and returns the metrics routes.
+pub fn routes() -> Routes {
+    Routes::new().add("/_metrics", get(scrape))
+}
+
+/// Bind the listener of the metrics on `binding:port`.
+///
+/// # Errors
+/// When the port can't be bound.
+pub async fn bind(binding: &str, port: u16) -> Result<TcpListener> {
+    let listener = TcpListener::bind(&format!("{binding}:{port}")).await?;
+    tracing::info!("metrics listening on {binding}:{port}");
+    Ok(listener)
+}
+
+/// Serve `/_metrics` on `listener`, see [`bind`], until `shutdown` is
+/// triggered.
+///
+/// # Errors
+/// When the server fails.
+pub async fn serve(listener: TcpListener, shutdown: ShutdownToken) -> Result<()> {
+    let router = AXRouter::new().route("/_metrics", get(|| async { render() }));
+    axum::serve(listener, router)
+        .with_graceful_shutdown(async move { shutdown.triggered().await })
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn serves_the_metrics_until_shutdown() {
+        let listener = bind("127.0.0.1", 0).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let shutdown = ShutdownToken::new();
+        let served = tokio::spawn(serve(listener, shutdown.clone()));
+
+        let response = tokio::task::spawn_blocking(move || {
+            let mut stream = std::net::TcpStream::connect(addr).unwrap();
+            let request = "GET /_metrics HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n";
+            stream.write_all(request.as_bytes()).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        })
+        .await
+        .unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains(metrics::CONTENT_TYPE));
+
+        shutdown.trigger();
+        served.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn fails_to_bind_a_used_port() {
+        let used = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = used.local_addr().unwrap().port();
+
+        assert!(bind("127.0.0.1", port).await.is_err());
+    }
+}
@@ -0,0 +1,111 @@
+//! Records the requests in the `http_requests_total` counter and the
+//! `http_request_duration_seconds` histogram of [`insane_core::metrics`].
+//!
+//! The requests are labeled by the template of the matched route, like
+//! `/api/users/:id`, so that the identifiers in the paths don't create a
+//! series each. The requests matching no route are labeled `<unmatched>`.
+
+use std::time::Instant;
+
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use insane_core::metrics::{self, HistogramVec, IntCounterVec};
+use lazy_static::lazy_static;
+
+lazy_static! {
+    static ref REQUESTS: Option<IntCounterVec> = metrics::counter(
+        "http_requests_total",
+        "HTTP requests handled",
+        &["method", "route", "status"],
+    )
+    .map_err(|err| tracing::error!(err.msg = %err, "failed to register the http metrics"))
+    .ok();
+    static ref DURATION: Option<HistogramVec> = metrics::histogram(
+        "http_request_duration_seconds",
+        "Duration of the HTTP requests",
+        &["method", "route"],
+    )
+    .map_err(|err| tracing::error!(err.msg = %err, "failed to register the http metrics"))
+    .ok();
+}
+
+/// The middleware, added with [`axum::middleware::from_fn`].
+pub async fn track(request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = request.extensions().get::<MatchedPath>().map_or_else(
+        || "<unmatched>".to_string(),
+        |path| path.as_str().to_string(),
+    );
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+
+    if let Some(requests) = REQUESTS.as_ref() {
+        requests
+            .with_label_values(&[&method, &route, response.status().as_str()])
+            .inc();
+    }
+    if let Some(duration) = DURATION.as_ref() {
+        duration
+            .with_label_values(&[&method, &route])
+            .observe(started.elapsed().as_secs_f64());
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, http::StatusCode, routing::get};
+    use insane_core::{
+        config::{InsaneConfig, MetricsConfig},
+        context::test_context,
+    };
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::{
+        config::HTTPServerConfig, context::HttpContext, http_routes::HttpRoutes, routes::Routes,
+    };
+
+    #[tokio::test]
+    async fn labels_the_requests_by_route_template() {
+        let context = test_context(InsaneConfig {
+            metrics: MetricsConfig {
+                enable: true,
+                port: None,
+            },
+            ..InsaneConfig::default()
+        });
+        let http_context = HttpContext::new(HTTPServerConfig::default(), context.clone());
+        let router = HttpRoutes::empty()
+            .add_route(Routes::new().add("/users/:id", get(|| async { "user" })))
+            .to_router(http_context, context)
+            .unwrap();
+
+        for (uri, status) in [
+            ("/users/42", StatusCode::OK),
+            ("/users/43", StatusCode::OK),
+            ("/missing", StatusCode::NOT_FOUND),
+        ] {
+            let request = Request::get(uri).body(Body::empty()).unwrap();
+            assert_eq!(
+                router.clone().oneshot(request).await.unwrap().status(),
+                status
+            );
+        }
+
+        let rendered = metrics::render().unwrap();
+        assert!(rendered
+            .contains("http_requests_total{method=\"GET\",route=\"/users/:id\",status=\"200\"} 2"));
+        assert!(rendered.contains(
+            "http_requests_total{method=\"GET\",route=\"<unmatched>\",status=\"404\"} 1"
+        ));
+        assert!(rendered.contains(
+            "http_request_duration_seconds_count{method=\"GET\",route=\"/users/:id\"} 2"
+        ));
+        assert!(!rendered.contains("/users/42"));
+    }
+}
@@ -1,2 +1,3 @@
 pub mod etag;
-pub mod format;
\ No newline at end of file
+pub mod format;
+pub mod metrics;
@@ -0,0 +1,419 @@
+//! This module renders the errors of the handlers. By default they are
+//! answered with the JSON body of [`crate::error::ErrorDetail`], an
+//! [`ErrorRenderer`] returned by [`crate::hook::HttpHooks::error_renderer`]
+//! renders them instead.
+//!
+//! The requests matching no route, and the methods a route doesn't answer,
+//! are rendered as [`Error::NotFound`] and a `method_not_allowed` error. The
+//! rejections of the extractors of axum, like `Json` or `Path`, are answered
+//! by axum as plain text, the extractors of [`crate::validation`] fail with an
+//! [`Error`] rendered like the others.
+//!
+//! [`ProblemRenderer`] answers with an RFC 7807 `application/problem+json`
+//! document, and with an HTML page to the browsers:
+//!
+//! ```json
+//! {
+//!   "type": "https://example.com/errors/not_found",
+//!   "title": "Not Found",
+//!   "status": 404,
+//!   "detail": "Resource was not found",
+//!   "instance": "/api/users/42",
+//!   "code": "not_found",
+//!   "request_id": "V1StGXR8_Z5jdHi6B-myT"
+//! }
+//! ```
+//!
+//! ```rust
+//! use std::sync::Arc;
+//!
+//! use insane_core::context::Context;
+//! use insane_http::{
+//!     context::HttpContext,
+//!     hook::HttpHooks,
+//!     http_routes::HttpRoutes,
+//!     renderer::{ErrorRenderer, ProblemRenderer},
+//! };
+//!
+//! struct App;
+//!
+//! impl HttpHooks for App {
+//!     fn routes(&self, _ctx: &HttpContext, _context: &Box<dyn Context>) -> HttpRoutes {
+//!         HttpRoutes::with_default_routes()
+//!     }
+//!
+//!     fn error_renderer(&self) -> Option<Arc<dyn ErrorRenderer>> {
+//!         Some(Arc::new(ProblemRenderer::new().types("https://example.com/errors/")))
+//!     }
+//! }
+//! ```
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::{
+        header::{ACCEPT, CONTENT_LENGTH, CONTENT_TYPE},
+        HeaderValue, StatusCode,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use nanoid::nanoid;
+use serde::Serialize;
+
+use crate::error::{Error, FieldErrors};
+
+/// Header of the request id, taken from the request when given.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// The id of a request, in the extensions of the requests when an
+/// [`ErrorRenderer`] is used, and in the logs of the `logger` middleware.
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/// The request an error is rendered for.
+#[derive(Debug, Clone)]
+pub struct ErrorRequest {
+    /// The `Accept` header of the request.
+    pub accept: Option<String>,
+    /// The path of the request.
+    pub instance: String,
+    pub request_id: String,
+}
+
+impl ErrorRequest {
+    /// Whether the client prefers HTML to JSON, like the browsers: `text/html`
+    /// weighs more than the JSON media ranges in the `Accept` header, or as
+    /// much and comes first. A `q=0` range is refused.
+    #[must_use]
+    pub fn wants_html(&self) -> bool {
+        let Some(accept) = &self.accept else {
+            return false;
+        };
+        // the best weight of the HTML and JSON media ranges, with their position
+        let mut html: Option<(f32, usize)> = None;
+        let mut json: Option<(f32, usize)> = None;
+        for (position, range) in accept.split(',').enumerate() {
+            let mut params = range.split(';');
+            let media = params
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_ascii_lowercase();
+            let weight = params
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            let best = if media == "text/html" {
+                &mut html
+            } else if media.contains("json") {
+                &mut json
+            } else {
+                continue;
+            };
+            if best.is_none_or(|(q, _)| weight > q) {
+                *best = Some((weight, position));
+            }
+        }
+        match (html, json) {
+            (Some((html, _)), _) if html <= 0.0 => false,
+            (Some((html, html_at)), Some((json, json_at))) => {
+                html > json || (html >= json && html_at < json_at)
+            }
+            (html, _) => html.is_some(),
+        }
+    }
+}
+
+/// Renders the errors of the handlers, see [`crate::hook::HttpHooks`].
+pub trait ErrorRenderer: Send + Sync {
+    /// The response to `request` failing with `error`.
+    fn render(&self, error: &Error, request: &ErrorRequest) -> Response;
+}
+
+/// Renders the errors as RFC 7807 problem details, or as an HTML page when
+/// the client prefers HTML.
+#[derive(Debug, Clone, Default)]
+pub struct ProblemRenderer {
+    types: Option<String>,
+}
+
+impl ProblemRenderer {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The `type` of the problems is the code of the error appended to
+    /// `base`, `about:blank` without it.
+    #[must_use]
+    pub fn types(mut self, base: &str) -> Self {
+        self.types = Some(base.to_string());
+        self
+    }
+}
+
+/// An RFC 7807 problem details document.
+#[derive(Debug, Serialize)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    pub instance: String,
+    pub code: String,
+    pub request_id: String,
+    /// The errors of the fields of an [`Error::Validation`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<FieldErrors>,
+}
+
+impl ErrorRenderer for ProblemRenderer {
+    fn render(&self, error: &Error, request: &ErrorRequest) -> Response {
+        let status = error.status();
+        let code = error.code();
+        let problem = Problem {
+            kind: self
+                .types
+                .as_ref()
+                .map_or_else(|| "about:blank".to_string(), |base| format!("{base}{code}")),
+            title: status.canonical_reason().unwrap_or_default().to_string(),
+            status: status.as_u16(),
+            detail: error.detail(),
+            instance: request.instance.clone(),
+            code: code.to_string(),
+            request_id: request.request_id.clone(),
+            errors: error.field_errors().cloned(),
+        };
+
+        if request.wants_html() {
+            return (status, axum::response::Html(html(&problem))).into_response();
+        }
+
+        match serde_json::to_vec(&problem) {
+            Ok(body) => (
+                status,
+                [(
+                    CONTENT_TYPE,
+                    HeaderValue::from_static("application/problem+json"),
+                )],
+                body,
+            )
+                .into_response(),
+            Err(err) => {
+                tracing::error!(err.msg = %err, "failed to render the error");
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
+    }
+}
+
+fn html(problem: &Problem) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{status} {title}</title></head>\n<body>\n<h1>{status} {title}</h1>\n<p>{detail}</p>\n<p><small>Request {request_id}</small></p>\n</body>\n</html>\n",
+        status = problem.status,
+        title = escape(&problem.title),
+        detail = escape(&problem.detail),
+        request_id = escape(&problem.request_id),
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.chars()
+        .fold(String::with_capacity(text.len()), |mut escaped, c| {
+            match c {
+                '&' => escaped.push_str("&amp;"),
+                '<' => escaped.push_str("&lt;"),
+                '>' => escaped.push_str("&gt;"),
+                '"' => escaped.push_str("&quot;"),
+                '\'' => escaped.push_str("&#39;"),
+                c => escaped.push(c),
+            }
+            escaped
+        })
+}
+
+/// The middleware giving an id to the requests, from the `x-request-id`
+/// header when given, see [`RequestId`]. Added outside the `logger`
+/// middleware when an [`ErrorRenderer`] is used.
+pub async fn request_id(mut request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty())
+        .map_or_else(|| nanoid!(), ToString::to_string);
+    request.extensions_mut().insert(RequestId(request_id));
+    next.run(request).await
+}
+
+/// The middleware rendering the errors with `renderer`, added with
+/// [`axum::middleware::from_fn_with_state`] inside the other middlewares.
+pub async fn render(
+    State(renderer): State<Arc<dyn ErrorRenderer>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let error_request = ErrorRequest {
+        accept: request
+            .headers()
+            .get(ACCEPT)
+            .and_then(|value| value.to_str().ok())
+            .map(ToString::to_string),
+        instance: request.uri().path().to_string(),
+        request_id: request
+            .extensions()
+            .get::<RequestId>()
+            .map_or_else(|| nanoid!(), |id| id.0.clone()),
+    };
+
+    let response = next.run(request).await;
+    let Some(error) = response.extensions().get::<Arc<Error>>().cloned() else {
+        return response;
+    };
+
+    let mut rendered = renderer.render(&error, &error_request);
+    // the headers set by the handler, e.g. `WWW-Authenticate`
+    for name in response.headers().keys() {
+        if name == CONTENT_TYPE || name == CONTENT_LENGTH || rendered.headers().contains_key(name) {
+            continue;
+        }
+        for value in response.headers().get_all(name) {
+            rendered.headers_mut().append(name.clone(), value.clone());
+        }
+    }
+    rendered.extensions_mut().insert(error);
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::{to_bytes, Body},
+        http::header::{ACCESS_CONTROL_ALLOW_ORIGIN, ORIGIN, WWW_AUTHENTICATE},
+        routing::get,
+        Router,
+    };
+    use tower::ServiceExt;
+    use tower_http::cors::CorsLayer;
+
+    use super::*;
+
+    async fn unauthorized() -> Response {
+        let mut response = Error::Unauthorized("no token".to_string()).into_response();
+        response
+            .headers_mut()
+            .insert(WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        response
+    }
+
+    fn app() -> Router {
+        let renderer: Arc<dyn ErrorRenderer> = Arc::new(ProblemRenderer::new().types("/errors/"));
+        Router::new()
+            .route("/private", get(unauthorized))
+            .route("/public", get(|| async { "hello" }))
+            .layer(axum::middleware::from_fn_with_state(renderer, render))
+            .layer(CorsLayer::permissive())
+            .layer(axum::middleware::from_fn(request_id))
+    }
+
+    async fn call(request: Request) -> (Response, String) {
+        let response = app().oneshot(request).await.unwrap();
+        let (parts, body) = response.into_parts();
+        let body = to_bytes(body, usize::MAX).await.unwrap();
+        (
+            Response::from_parts(parts, Body::empty()),
+            String::from_utf8(body.to_vec()).unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn renders_problems_with_the_headers_of_the_response() {
+        let request = Request::get("/private")
+            .header(ORIGIN, "https://example.com")
+            .header(REQUEST_ID_HEADER, "req-1")
+            .body(Body::empty())
+            .unwrap();
+        let (response, body) = call(request).await;
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/problem+json");
+        assert_eq!(response.headers()[ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+        assert_eq!(response.headers()[WWW_AUTHENTICATE], "Bearer");
+        assert!(response.extensions().get::<Arc<Error>>().is_some());
+
+        let problem: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(
+            problem,
+            serde_json::json!({
+                "type": "/errors/unauthorized",
+                "title": "Unauthorized",
+                "status": 401,
+                "detail": "You do not have permission to access this resource",
+                "instance": "/private",
+                "code": "unauthorized",
+                "request_id": "req-1",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn renders_html_to_the_browsers() {
+        let request = Request::get("/private")
+            .header(ACCEPT, "text/html,application/xhtml+xml,*/*;q=0.8")
+            .body(Body::empty())
+            .unwrap();
+        let (response, body) = call(request).await;
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(response.headers()[CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("text/html"));
+        assert!(body.contains("<h1>401 Unauthorized</h1>"));
+    }
+
+    #[tokio::test]
+    async fn keeps_the_successful_responses() {
+        let request = Request::get("/public").body(Body::empty()).unwrap();
+        let (response, body) = call(request).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body, "hello");
+    }
+
+    #[test]
+    fn weighs_the_accepted_media_ranges() {
+        let wants_html = |accept: Option<&str>| {
+            ErrorRequest {
+                accept: accept.map(ToString::to_string),
+                instance: "/".to_string(),
+                request_id: "req-1".to_string(),
+            }
+            .wants_html()
+        };
+
+        assert!(!wants_html(None));
+        assert!(!wants_html(Some("*/*")));
+        assert!(wants_html(Some("text/html")));
+        assert!(wants_html(Some("text/html, application/json")));
+        assert!(!wants_html(Some("application/json, text/html")));
+        assert!(!wants_html(Some("text/html;q=0")));
+        assert!(!wants_html(Some("text/html;q=0, */*")));
+        assert!(!wants_html(Some("text/html;q=0.5, application/json")));
+        assert!(wants_html(Some("application/json;q=0.2, text/html;q=0.9")));
+        assert!(wants_html(Some(
+            "text/html; charset=utf-8; q=0.8, application/*+json;q=0.1"
+        )));
+    }
+
+    #[test]
+    fn escapes_the_html() {
+        assert_eq!(
+            escape("<a href=\"x\">'&'</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&#39;&amp;&#39;&lt;/a&gt;"
+        );
+    }
+}
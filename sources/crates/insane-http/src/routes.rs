@@ -33,8 +33,8 @@ impl Routes {
     /// _ping endpoint HOST/status/_ping.
     ///
     /// ```rust
-    /// use loco_rs::prelude::*;
-    /// use serde::Serialize;;
+    /// use insane_http::{error::Result, format, prelude::*, routes::Routes};
+    /// use serde::Serialize;
     ///
     /// #[derive(Serialize)]
     /// struct Health {
@@ -62,7 +62,7 @@ impl Routes {
     /// This example preset how to add a get endpoint int the Router.
     ///
     /// ```rust
-    /// use loco_rs::prelude::*;
+    /// use insane_http::{error::Result, format, prelude::*, routes::Routes};
     /// use serde::Serialize;
     ///
     /// #[derive(Serialize)]
@@ -95,7 +95,7 @@ impl Routes {
     /// _ping endpoint HOST/status/_ping.
     ///
     /// ```rust
-    /// use loco_rs::prelude::*;
+    /// use insane_http::{error::Result, format, prelude::*, routes::Routes};
     /// use serde::Serialize;
     ///
     /// #[derive(Serialize)]
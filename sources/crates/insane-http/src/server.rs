@@ -3,16 +3,23 @@ use crate::{
     context::HttpContext,
     error::{Error, Result},
     hook::HttpHooks,
+    metrics,
 };
-use axum::Router as AxumRouter;
+use axum::{extract::Request, Router as AxumRouter};
 use insane_core::{
     context::Context,
     error::{Error as CoreError, Result as CoreResult},
     hook::Initializer,
-    server::{Server, ServerLifeCycle},
+    initializers::InitializerChain,
+    server::{Readiness, Server, ServerLifeCycle},
+    shutdown::ShutdownToken,
 };
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::{
+    net::TcpListener,
+    sync::{watch, Mutex},
+};
+use tower::{service_fn, ServiceExt};
 
 // /// Configuration structure for serving an application.
 // pub struct ServerParams {
@@ -43,21 +50,106 @@ impl<H: HttpHooks> HttpServer<H> {
 }
 
 impl<H: HttpHooks> HttpServer<H> {
-    /// Start serving the Axum web application on the specified address and
-    /// port.
+    /// Bind the listener on the configured address and port.
+    async fn bind(http_config: &HTTPServerConfig) -> Result<TcpListener> {
+        Ok(TcpListener::bind(&format!("{}:{}", http_config.binding, http_config.port)).await?)
+    }
+
+    /// Start serving the Axum web application on the given listener. Once
+    /// `shutdown` is triggered the listener stops accepting new connections
+    /// and the in-flight requests are drained.
     ///
     /// # Returns
     /// A Result indicating success () or an error if the server fails to start.
-    async fn start(http: AxumRouter, http_config: HTTPServerConfig) -> Result<()> {
-        let listener =
-            tokio::net::TcpListener::bind(&format!("{}:{}", http_config.binding, http_config.port))
-                .await?;
-
-        axum::serve(listener, http).await?;
+    async fn start(listener: TcpListener, http: AxumRouter, shutdown: ShutdownToken) -> Result<()> {
+        axum::serve(listener, http)
+            .with_graceful_shutdown(async move { shutdown.triggered().await })
+            .await?;
 
         Ok(())
     }
 
+    /// Build the router of the application, with the middlewares of the
+    /// server configuration of `http_context`.
+    async fn router(
+        &self,
+        context: &Arc<Box<dyn Context>>,
+        http_context: &HttpContext,
+    ) -> CoreResult<AxumRouter> {
+        let mut routes = self.hooks.routes(http_context, context.as_ref());
+        if let Some(error_renderer) = self.hooks.error_renderer() {
+            routes = routes.error_renderer(error_renderer);
+        }
+        let app = routes
+            .to_router(http_context.clone(), context.clone())
+            .map_err(CoreError::bt)?;
+
+        self.hooks
+            .after_routes(app, http_context)
+            .await
+            .map_err(CoreError::bt)
+    }
+
+    /// A router handing every request to the last router sent on `routers`.
+    /// Requests in flight finish on the router they started with, and the
+    /// connections are kept.
+    fn swappable(routers: watch::Receiver<AxumRouter>) -> AxumRouter {
+        AxumRouter::new().fallback_service(service_fn(move |request: Request| {
+            let router = routers.borrow().clone();
+            router.oneshot(request)
+        }))
+    }
+
+    /// Build the router again each time the configuration is reloaded, so
+    /// that the middlewares (CORS, timeouts, payload limits...) follow the
+    /// `http` configuration. The binding and the port only change on restart.
+    ///
+    /// Never returns, the reloads stop with the server.
+    async fn reload_routes(
+        &self,
+        context: &Arc<Box<dyn Context>>,
+        routers: &watch::Sender<AxumRouter>,
+    ) {
+        let mut changes = context.reloader().subscribe();
+        while changes.changed().await.is_ok() {
+            let loaded = changes.borrow_and_update().clone();
+            let mut http_config = match loaded.section::<HTTPServerConfig>("http") {
+                Ok(http_config) => http_config,
+                Err(err) => {
+                    tracing::error!(err.msg = %err, "invalid http configuration, keeping the previous one");
+                    continue;
+                }
+            };
+
+            if let Some(previous) = &*self.config.lock().await {
+                if previous.binding != http_config.binding || previous.port != http_config.port {
+                    tracing::warn!(
+                        "{} keeps listening on {}:{} until restarted",
+                        self.name(),
+                        previous.binding,
+                        previous.port
+                    );
+                    http_config.binding.clone_from(&previous.binding);
+                    http_config.port = previous.port;
+                }
+            }
+
+            let mut http_context = HttpContext::new(http_config.clone(), context.clone());
+            http_context.config = loaded.config.clone();
+            match self.router(context, &http_context).await {
+                Ok(router) => {
+                    routers.send_modify(|current| *current = router);
+                    *self.config.lock().await = Some(http_config);
+                    tracing::info!("{} routes reloaded", self.name());
+                }
+                Err(err) => {
+                    tracing::error!(err.msg = %err, "failed to reload the routes, keeping the previous ones");
+                }
+            }
+        }
+        std::future::pending().await
+    }
+
     /// Asynchronously loads and provides access to the HTTP server configuration.
     ///
     /// This function checks if the HTTP server configuration has already been loaded. If it has,
@@ -73,10 +165,11 @@ impl<H: HttpHooks> HttpServer<H> {
     ///
     /// A Result containing the loaded HTTP server configuration on success, or an Error if loading fails.
     /// If the configuration has already been loaded, the cached configuration is returned without reloading.
+    /// The cache is replaced when the configuration is reloaded, see `reload_routes`.
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```ignore
     /// # use std::sync::{Arc, Mutex};
     /// # use your_module::{Context, HTTPServerConfig};
     /// # async fn example_function(server: &Mutex<YourServer>, context: Arc<Box<dyn Context>>) {
@@ -98,10 +191,10 @@ impl<H: HttpHooks> HttpServer<H> {
 
         // Load config if not already loaded
         let config = HTTPServerConfig::load_from_env(
-            &context.environment(),
+            context.environment(),
             &context.config().application_name,
         )
-        .map_err(|e| Error::msg(e).bt())?;
+        .map_err(Error::bt)?;
 
         // Update config and return
         *guard = Some(config.clone());
@@ -116,19 +209,22 @@ impl<H: HttpHooks> Server for HttpServer<H> {
     }
 
     async fn enable(&self, context: Arc<Box<dyn Context>>) -> CoreResult<bool> {
-        let http_config = self
-            .config(context.clone())
-            .await
-            .map_err(|e| CoreError::msg(e).bt())?;
+        let http_config = self.config(context.clone()).await.map_err(CoreError::bt)?;
 
         Ok(http_config.enable)
     }
 
-    async fn serve(&self, context: Arc<Box<dyn Context>>) -> CoreResult<()> {
-        let http_config = self
-            .config(context.clone())
-            .await
-            .map_err(|e| CoreError::msg(e).bt())?;
+    async fn serve(
+        &self,
+        context: Arc<Box<dyn Context>>,
+        shutdown: ShutdownToken,
+        ready: Readiness,
+    ) -> CoreResult<()> {
+        let http_config = self.config(context.clone()).await.map_err(CoreError::bt)?;
+
+        // an invalid `http` section is refused with the rest of the reloaded
+        // configuration, see `reload_routes`
+        context.reloader().check_section::<HTTPServerConfig>("http");
 
         let http_context = HttpContext::new(http_config.clone(), context.clone());
         let htt_context_boxed = Arc::new(Box::new(http_context.clone()));
@@ -136,40 +232,73 @@ impl<H: HttpHooks> Server for HttpServer<H> {
         self.before_run(context.clone(), htt_context_boxed.clone())
             .await?;
 
-        let initializers = self
-            .initializers(context.clone(), htt_context_boxed.clone())
+        let initializers = InitializerChain::new(
+            self.initializers(context.clone(), htt_context_boxed.clone())
+                .await?,
+        )?;
+        tracing::info!(initializers = ?initializers.names().join(","), "server initializers loaded");
+        initializers
+            .before_run(&context, Some(htt_context_boxed.clone()))
             .await?;
-        tracing::info!(initializers = ?initializers.iter().map(|init| init.name()).collect::<Vec<_>>().join(","), "server initializers loaded");
-        for initializer in &initializers {
-            initializer
-                .before_run(context.clone(), Some(htt_context_boxed.clone()))
-                .await?;
-        }
 
-        let app = self
-            .hooks
-            .routes(&http_context, &context)
-            .to_router(http_context.clone(), context.clone()) // Unwrap the Arc<Box<HttpContext>> to get the inner HttpContext object
-            .map_err(|e| CoreError::msg(e).bt())?;
+        // the initializers are shut down even when the server fails to start
+        let served = async {
+            let router = self.router(&context, &http_context).await?;
 
-        let mut router = self
-            .hooks
-            .after_routes(app, &http_context)
-            .await
-            .map_err(|e| CoreError::msg(e).bt())?;
+            let listener = HttpServer::<H>::bind(&http_config)
+                .await
+                .map_err(CoreError::bt)?;
+            tracing::info!(
+                "{} listening on {}:{}",
+                self.name(),
+                http_config.binding,
+                http_config.port
+            );
 
-        router = self
-            .hooks
-            .after_routes(router, &http_context)
-            .await
-            .map_err(|e| CoreError::msg(e).bt())?;
+            // the metrics on their own port, away from the public one, served
+            // as long as the server
+            let metrics_config = &context.config().metrics;
+            let metrics_listener = match metrics_config.port.filter(|_| metrics_config.enable) {
+                Some(port) => Some(
+                    metrics::bind(&http_config.binding, port)
+                        .await
+                        .map_err(CoreError::bt)?,
+                ),
+                None => None,
+            };
 
-        tracing::info!("{} listening on {}:{}", self.name(), http_config.binding, http_config.port);
-        HttpServer::<H>::start(router, http_config)
-            .await
-            .map_err(|e| CoreError::msg(e).bt())?;
+            initializers
+                .after_start(&context, Some(htt_context_boxed.clone()))
+                .await?;
 
-        Ok(())
+            ready.ready();
+            let (routers, router) = watch::channel(router);
+            let http = Self::swappable(router);
+            let metrics = async {
+                match metrics_listener {
+                    Some(listener) => metrics::serve(listener, shutdown.clone()).await,
+                    None => Ok(()),
+                }
+            };
+            tokio::select! {
+                served = async {
+                    tokio::try_join!(
+                        HttpServer::<H>::start(listener, http, shutdown.clone()),
+                        metrics,
+                    )
+                } => {
+                    served.map(|_| ()).map_err(CoreError::bt)
+                }
+                () = self.reload_routes(&context, &routers) => unreachable!(),
+            }
+        }
+        .await;
+
+        initializers
+            .before_shutdown(&context, Some(htt_context_boxed))
+            .await;
+
+        served
     }
 }
 
@@ -193,3 +322,211 @@ impl<H: HttpHooks> ServerLifeCycle for HttpServer<H> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        sync::Mutex as StdMutex,
+        time::Duration,
+    };
+
+    use axum::{body::Body, http::StatusCode, routing::get};
+    use insane_core::{
+        config::{loader, InsaneConfig},
+        context::test_context,
+    };
+    use tokio::sync::Notify;
+
+    use super::*;
+    use crate::{http_routes::HttpRoutes, renderer::ProblemRenderer, routes::Routes};
+
+    /// Records whether the admin routes were enabled in each router built.
+    struct App(Arc<StdMutex<Vec<bool>>>);
+
+    impl HttpHooks for App {
+        fn routes(&self, ctx: &HttpContext, _context: &Box<dyn Context>) -> HttpRoutes {
+            self.0.lock().unwrap().push(ctx.server_config.admin.enable);
+            HttpRoutes::with_default_routes()
+        }
+    }
+
+    /// Serves `/slow`, answering once released.
+    struct Slow {
+        started: Arc<Notify>,
+        release: Arc<Notify>,
+    }
+
+    impl HttpHooks for Slow {
+        fn routes(&self, _ctx: &HttpContext, _context: &Box<dyn Context>) -> HttpRoutes {
+            let (started, release) = (self.started.clone(), self.release.clone());
+            let slow = move || async move {
+                started.notify_one();
+                release.notified().await;
+                "done"
+            };
+            HttpRoutes::empty().add_route(Routes::new().add("/slow", get(slow)))
+        }
+    }
+
+    /// A folder of configuration files, removed when dropped.
+    struct Folder(std::path::PathBuf);
+
+    impl Folder {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("insane-http-{name}-{}", std::process::id()));
+            std::fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for Folder {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    async fn status(router: AxumRouter, uri: &str) -> StatusCode {
+        let request = Request::get(uri).body(Body::empty()).unwrap();
+        router.oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn swaps_the_router_when_the_configuration_is_reloaded() {
+        let folder = Folder::new("reload");
+        let file = folder.0.join("test-reload_http.yaml");
+        let section = |extra: &str| format!("http:\n  enable: true\n  middlewares: {{}}\n{extra}");
+        std::fs::write(&file, section("  port: 9999\n")).unwrap();
+        let _config_dir = loader::TestConfigDir::new(&folder.0);
+
+        let context = test_context(InsaneConfig {
+            application_name: "reload_http".to_string(),
+            ..InsaneConfig::default()
+        });
+        context.reloader().check_section::<HTTPServerConfig>("http");
+        let built = Arc::new(StdMutex::new(Vec::new()));
+        let server = HttpServer::new(App(built.clone()));
+        *server.config.lock().await = Some(HTTPServerConfig {
+            binding: "127.0.0.1".to_string(),
+            port: 1234,
+            ..HTTPServerConfig::default()
+        });
+
+        let (routers, router) = watch::channel(AxumRouter::new());
+        let http = HttpServer::<App>::swappable(router.clone());
+        assert_eq!(status(http.clone(), "/_ping").await, StatusCode::NOT_FOUND);
+
+        let reload = async {
+            // refused as a whole, the routes are kept
+            std::fs::write(&file, section("  port: nine\n")).unwrap();
+            assert!(context.reloader().reload().is_err());
+
+            std::fs::write(&file, section("  port: 9999\n  admin:\n    enable: true\n")).unwrap();
+            assert!(context.reloader().reload().unwrap());
+            router.clone().changed().await.unwrap();
+        };
+        tokio::select! {
+            biased;
+            () = server.reload_routes(&context, &routers) => unreachable!(),
+            () = reload => {}
+        }
+
+        assert_eq!(*built.lock().unwrap(), [true]);
+        assert_eq!(status(http, "/_ping").await, StatusCode::OK);
+        // the listener only changes on restart
+        let config = server.config.lock().await.clone().unwrap();
+        assert_eq!((config.binding.as_str(), config.port), ("127.0.0.1", 1234));
+        assert!(config.admin.enable);
+    }
+
+    #[tokio::test]
+    async fn renders_the_requests_matching_no_route() {
+        let context = test_context(InsaneConfig::default());
+        let http_context = HttpContext::new(HTTPServerConfig::default(), context.clone());
+        let router = HttpRoutes::with_default_routes()
+            .error_renderer(Arc::new(ProblemRenderer::new()))
+            .to_router(http_context, context)
+            .unwrap();
+
+        let problem = |response: axum::response::Response| async move {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()["code"].clone()
+        };
+
+        let request = Request::get("/missing").body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(problem(response).await, "not_found");
+
+        let request = Request::post("/_ping").body(Body::empty()).unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(response.headers()[axum::http::header::ALLOW], "GET,HEAD");
+        assert_eq!(problem(response).await, "method_not_allowed");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn drains_the_requests_in_flight_on_shutdown() {
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let (started, release) = (Arc::new(Notify::new()), Arc::new(Notify::new()));
+        let server = Arc::new(HttpServer::new(Slow {
+            started: started.clone(),
+            release: release.clone(),
+        }));
+        *server.config.lock().await = Some(HTTPServerConfig {
+            binding: "127.0.0.1".to_string(),
+            port: addr.port().into(),
+            ..HTTPServerConfig::default()
+        });
+
+        let shutdown = ShutdownToken::new();
+        let ready = Readiness::new();
+        let served = tokio::spawn({
+            let (server, shutdown, ready) = (server.clone(), shutdown.clone(), ready.clone());
+            async move {
+                server
+                    .serve(test_context(InsaneConfig::default()), shutdown, ready)
+                    .await
+            }
+        });
+        ready.wait().await;
+
+        let response = tokio::task::spawn_blocking(move || {
+            let mut stream = std::net::TcpStream::connect(addr).unwrap();
+            let request = "GET /slow HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n";
+            stream.write_all(request.as_bytes()).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        });
+        started.notified().await;
+
+        shutdown.trigger();
+        let mut refused = false;
+        for _ in 0..100 {
+            if std::net::TcpStream::connect(addr).is_err() {
+                refused = true;
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(refused);
+        assert!(!served.is_finished());
+
+        release.notify_one();
+        let response = response.await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.ends_with("done"));
+        tokio::time::timeout(Duration::from_secs(5), served)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+    }
+}
@@ -0,0 +1,305 @@
+//! This module provides extractors validating the requests after
+//! deserializing them, with the rules of the [`validator`] crate: `length`,
+//! `range`, `email`, `regex`, `custom` functions and so on.
+//!
+//! [`ValidatedJson`], [`ValidatedQuery`] and [`ValidatedForm`] answer the
+//! invalid requests with `422` and the errors of every invalid field, see
+//! [`insane_core::error::Error::Validation`]:
+//!
+//! ```json
+//! {
+//!   "error": "validation",
+//!   "description": "invalid fields: email",
+//!   "errors": {"email": [{"code": "email", "message": "not an email"}]}
+//! }
+//! ```
+//!
+//! The `Validate` derive macro expands to paths of the `validator` crate, the
+//! application depends on it as well.
+//!
+//! ```rust
+//! use insane_http::{error::Result, format, prelude::*};
+//! use serde::Deserialize;
+//! use validator::Validate;
+//!
+//! #[derive(Deserialize, Validate)]
+//! struct SignUp {
+//!     #[validate(email(message = "not an email"))]
+//!     email: String,
+//!     #[validate(length(min = 8))]
+//!     password: String,
+//! }
+//!
+//! async fn sign_up(ValidatedJson(params): ValidatedJson<SignUp>) -> Result<Response> {
+//!     format::json(params.email)
+//! }
+//! ```
+
+use axum::{
+    async_trait,
+    extract::{FromRequest, FromRequestParts, Query, Request},
+    http::request::Parts,
+    Form,
+};
+use insane_core::error::{FieldError, FieldErrors};
+use serde::de::DeserializeOwned;
+use validator::{Validate, ValidationErrors, ValidationErrorsKind};
+
+use super::error::{Error, Result};
+use super::Json;
+
+/// A JSON body, deserialized then validated.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidatedJson<T>(pub T);
+
+/// A query string, deserialized then validated.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidatedQuery<T>(pub T);
+
+/// A form body, deserialized then validated.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidatedForm<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self> {
+        let Json(value) = Json::<T>::from_request(request, state).await?;
+        validate(&value)?;
+        Ok(Self(value))
+    }
+}
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for ValidatedQuery<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self> {
+        let Query(value) = Query::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|rejection| Error::BadRequest(rejection.body_text()))?;
+        validate(&value)?;
+        Ok(Self(value))
+    }
+}
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidatedForm<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self> {
+        let Form(value) = Form::<T>::from_request(request, state)
+            .await
+            .map_err(|rejection| Error::BadRequest(rejection.body_text()))?;
+        validate(&value)?;
+        Ok(Self(value))
+    }
+}
+
+/// Validate `value`, failing with the errors of its fields.
+///
+/// # Errors
+/// An [`Error::Validation`] when a field is invalid.
+pub fn validate(value: &impl Validate) -> Result<()> {
+    value.validate().map_err(|errors| {
+        let mut fields = FieldErrors::new();
+        collect(&mut fields, None, &errors);
+        Error::Validation(fields)
+    })
+}
+
+/// Flatten the nested `errors` in `fields`, by path of the field.
+fn collect(fields: &mut FieldErrors, parent: Option<&str>, errors: &ValidationErrors) {
+    for (field, kind) in errors.errors() {
+        let path = parent.map_or_else(|| field.to_string(), |parent| format!("{parent}.{field}"));
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                fields
+                    .entry(path)
+                    .or_default()
+                    .extend(errors.iter().map(|error| {
+                        let mut field_error = FieldError::new(&error.code);
+                        field_error.message = error.message.as_ref().map(ToString::to_string);
+                        // the submitted value could be a password
+                        field_error.params = error
+                            .params
+                            .iter()
+                            .filter(|(name, _)| *name != "value")
+                            .map(|(name, value)| (name.to_string(), value.clone()))
+                            .collect();
+                        field_error
+                    }));
+            }
+            ValidationErrorsKind::Struct(errors) => collect(fields, Some(&path), errors),
+            ValidationErrorsKind::List(items) => {
+                for (index, errors) in items {
+                    collect(fields, Some(&format!("{path}[{index}]")), errors);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::{to_bytes, Body},
+        http::{header::CONTENT_TYPE, Request, StatusCode},
+        response::{IntoResponse, Response},
+        routing::{get, post},
+        Router,
+    };
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    #[derive(Deserialize, Validate)]
+    struct Address {
+        #[validate(length(min = 2, message = "too short"))]
+        city: String,
+    }
+
+    #[derive(Deserialize, Validate)]
+    struct SignUp {
+        #[validate(email(message = "not an email"))]
+        email: String,
+        #[validate(length(min = 8))]
+        password: String,
+        #[validate(nested)]
+        address: Address,
+        #[validate(nested)]
+        #[serde(default)]
+        previous: Vec<Address>,
+    }
+
+    #[derive(Deserialize, Validate)]
+    struct Search {
+        #[validate(range(min = 1, max = 100))]
+        limit: u32,
+    }
+
+    async fn sign_up(ValidatedJson(params): ValidatedJson<SignUp>) -> Response {
+        params.email.into_response()
+    }
+
+    async fn search(ValidatedQuery(params): ValidatedQuery<Search>) -> Response {
+        params.limit.to_string().into_response()
+    }
+
+    async fn search_form(ValidatedForm(params): ValidatedForm<Search>) -> Response {
+        params.limit.to_string().into_response()
+    }
+
+    async fn call(request: Request<Body>) -> (StatusCode, String) {
+        let router = Router::new()
+            .route("/sign_up", post(sign_up))
+            .route("/search", get(search))
+            .route("/form", post(search_form));
+        let response = router.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    fn json_request(body: &Value) -> Request<Body> {
+        Request::post("/sign_up")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn answers_the_invalid_fields_with_422() {
+        let request = json_request(&json!({
+            "email": "user",
+            "password": "hunter2",
+            "address": {"city": "P"},
+            "previous": [{"city": "Lyon"}, {"city": "N"}],
+        }));
+        let (status, body) = call(request).await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["error"], "validation");
+        assert_eq!(
+            body["description"],
+            "invalid fields: address.city, email, password, previous[1].city"
+        );
+        assert_eq!(
+            body["errors"],
+            json!({
+                "address.city": [{"code": "length", "message": "too short", "params": {"min": 2}}],
+                "email": [{"code": "email", "message": "not an email"}],
+                "password": [{"code": "length", "params": {"min": 8}}],
+                "previous[1].city": [
+                    {"code": "length", "message": "too short", "params": {"min": 2}}
+                ],
+            })
+        );
+        // the submitted values, like the password, are not echoed
+        assert!(!body.to_string().contains("hunter2"));
+    }
+
+    #[tokio::test]
+    async fn passes_the_valid_requests() {
+        let request = json_request(&json!({
+            "email": "user@example.com",
+            "password": "correct horse",
+            "address": {"city": "Paris"},
+        }));
+
+        assert_eq!(
+            call(request).await,
+            (StatusCode::OK, "user@example.com".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn validates_the_query_and_the_form() {
+        let request = Request::get("/search?limit=500")
+            .body(Body::empty())
+            .unwrap();
+        let (status, body) = call(request).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(
+            body["errors"],
+            json!({"limit": [{"code": "range", "params": {"min": 1, "max": 100}}]})
+        );
+
+        let request = Request::post("/form")
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Body::from("limit=0"))
+            .unwrap();
+        assert_eq!(call(request).await.0, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let request = Request::get("/search?limit=10")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(call(request).await, (StatusCode::OK, "10".to_string()));
+    }
+
+    #[tokio::test]
+    async fn answers_the_malformed_requests_with_400() {
+        let request = Request::get("/search?limit=ten")
+            .body(Body::empty())
+            .unwrap();
+
+        assert_eq!(call(request).await.0, StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,144 @@
+use std::{collections::BTreeMap, sync::Mutex};
+
+use chrono::{DateTime, Utc};
+use insane_core::error::Result;
+
+use super::{Backend, JobLock, JobRecord, JobStatus, NewJob};
+
+#[derive(Default)]
+struct State {
+    next_id: i64,
+    jobs: BTreeMap<i64, JobRecord>,
+}
+
+/// Keeps the jobs in memory. Jobs are lost when the process stops, so this
+/// backend is meant for tests and local development.
+#[derive(Default)]
+pub struct MemoryBackend {
+    state: Mutex<State>,
+}
+
+impl MemoryBackend {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every stored job, ordered by id.
+    #[must_use]
+    pub fn jobs(&self) -> Vec<JobRecord> {
+        self.lock().jobs.values().cloned().collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().expect("memory jobs lock poisoned")
+    }
+
+    /// The job running under `lock`.
+    fn locked(state: &mut State, lock: JobLock) -> Result<&mut JobRecord> {
+        state
+            .jobs
+            .get_mut(&lock.id)
+            .filter(|job| job.status == JobStatus::Running && job.attempts == lock.attempt)
+            .ok_or_else(|| lock.lost())
+    }
+
+    fn update(&self, lock: JobLock, f: impl FnOnce(&mut JobRecord)) -> Result<()> {
+        f(Self::locked(&mut self.lock(), lock)?);
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Backend for MemoryBackend {
+    async fn enqueue(&self, job: NewJob) -> Result<i64> {
+        let mut state = self.lock();
+        state.next_id += 1;
+        let id = state.next_id;
+        state.jobs.insert(
+            id,
+            JobRecord {
+                id,
+                name: job.name,
+                args: job.args,
+                status: JobStatus::Queued,
+                attempts: 0,
+                run_at: job.run_at,
+                locked_at: None,
+                last_error: None,
+                created_at: Utc::now(),
+            },
+        );
+        Ok(id)
+    }
+
+    async fn claim(&self, now: DateTime<Utc>) -> Result<Option<JobRecord>> {
+        let mut state = self.lock();
+        let next = state
+            .jobs
+            .values_mut()
+            .filter(|job| job.status == JobStatus::Queued && job.run_at <= now)
+            .min_by_key(|job| (job.run_at, job.id));
+
+        Ok(next.map(|job| {
+            job.status = JobStatus::Running;
+            job.locked_at = Some(now);
+            job.attempts += 1;
+            job.clone()
+        }))
+    }
+
+    async fn heartbeat(&self, lock: JobLock, now: DateTime<Utc>) -> Result<()> {
+        self.update(lock, |job| job.locked_at = Some(now))
+    }
+
+    async fn complete(&self, lock: JobLock) -> Result<()> {
+        let mut state = self.lock();
+        Self::locked(&mut state, lock)?;
+        state.jobs.remove(&lock.id);
+        Ok(())
+    }
+
+    async fn retry(&self, lock: JobLock, run_at: DateTime<Utc>, error: &str) -> Result<()> {
+        self.update(lock, |job| {
+            job.status = JobStatus::Queued;
+            job.run_at = run_at;
+            job.locked_at = None;
+            job.last_error = Some(error.to_string());
+        })
+    }
+
+    async fn kill(&self, lock: JobLock, error: &str) -> Result<()> {
+        self.update(lock, |job| {
+            job.status = JobStatus::Dead;
+            job.locked_at = None;
+            job.last_error = Some(error.to_string());
+        })
+    }
+
+    async fn requeue_stale(&self, locked_before: DateTime<Utc>) -> Result<u64> {
+        let mut requeued = 0;
+        for job in self.lock().jobs.values_mut() {
+            if job.status == JobStatus::Running
+                && job
+                    .locked_at
+                    .is_some_and(|locked_at| locked_at < locked_before)
+            {
+                job.status = JobStatus::Queued;
+                job.locked_at = None;
+                requeued += 1;
+            }
+        }
+        Ok(requeued)
+    }
+
+    async fn dead(&self) -> Result<Vec<JobRecord>> {
+        Ok(self
+            .lock()
+            .jobs
+            .values()
+            .filter(|job| job.status == JobStatus::Dead)
+            .cloned()
+            .collect())
+    }
+}
@@ -0,0 +1,333 @@
+//! Storage of the enqueued jobs.
+
+mod memory;
+#[cfg(feature = "with-sql")]
+mod sql;
+
+use chrono::{DateTime, Utc};
+use insane_core::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+
+pub use memory::MemoryBackend;
+#[cfg(feature = "with-sql")]
+pub use sql::SqlBackend;
+
+/// The state of a stored job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    /// Waiting for `run_at` and a free worker.
+    Queued,
+    /// Claimed by a worker.
+    Running,
+    /// Ran out of attempts, or has no registered handler. Kept for
+    /// inspection and never picked again.
+    Dead,
+}
+
+impl JobStatus {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Running => "running",
+            Self::Dead => "dead",
+        }
+    }
+}
+
+impl std::fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+impl std::str::FromStr for JobStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "queued" => Ok(Self::Queued),
+            "running" => Ok(Self::Running),
+            "dead" => Ok(Self::Dead),
+            _ => Err(format!("unknown job status `{s}`")),
+        }
+    }
+}
+
+/// A stored job.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobRecord {
+    pub id: i64,
+    pub name: String,
+    pub args: serde_json::Value,
+    pub status: JobStatus,
+    /// Number of times the job was claimed, including the current run.
+    pub attempts: u32,
+    pub run_at: DateTime<Utc>,
+    pub locked_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl JobRecord {
+    /// The lock of the job, as claimed by [`Backend::claim`].
+    #[must_use]
+    pub fn lock(&self) -> JobLock {
+        JobLock {
+            id: self.id,
+            attempt: self.attempts,
+        }
+    }
+}
+
+/// The claim of a running job by a worker, identified by the attempt it
+/// counted. Once the job is queued again by [`Backend::requeue_stale`] and
+/// claimed by another worker, the updates of the previous claim are rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JobLock {
+    pub id: i64,
+    pub attempt: u32,
+}
+
+impl JobLock {
+    /// The error of an update whose job isn't running under this lock
+    /// anymore.
+    #[must_use]
+    pub fn lost(&self) -> Error {
+        Error::Message(format!(
+            "job {} is not locked by attempt {} anymore",
+            self.id, self.attempt
+        ))
+    }
+}
+
+/// A job to store.
+#[derive(Debug, Clone)]
+pub struct NewJob {
+    pub name: String,
+    pub args: serde_json::Value,
+    pub run_at: DateTime<Utc>,
+}
+
+#[async_trait::async_trait]
+pub trait Backend: Send + Sync {
+    /// Prepare the storage, once when the [`crate::queue::Queue`] of the
+    /// context is created.
+    async fn setup(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Store a job and return its id.
+    async fn enqueue(&self, job: NewJob) -> Result<i64>;
+
+    /// Claim the next queued job due at `now`, marking it as running and
+    /// counting the attempt. A job is claimed by one worker only, even when
+    /// several processes poll the same storage.
+    async fn claim(&self, now: DateTime<Utc>) -> Result<Option<JobRecord>>;
+
+    /// Refresh the lock of a running job, so it isn't queued again by
+    /// [`Backend::requeue_stale`] while its worker is still performing it.
+    ///
+    /// The updates of a claimed job fail with [`JobLock::lost`] when the job
+    /// isn't running under `lock` anymore.
+    async fn heartbeat(&self, lock: JobLock, now: DateTime<Utc>) -> Result<()>;
+
+    /// Remove a job that was performed successfully.
+    async fn complete(&self, lock: JobLock) -> Result<()>;
+
+    /// Queue a failed job again, to run at `run_at`.
+    async fn retry(&self, lock: JobLock, run_at: DateTime<Utc>, error: &str) -> Result<()>;
+
+    /// Dead-letter a job.
+    async fn kill(&self, lock: JobLock, error: &str) -> Result<()>;
+
+    /// Queue again the jobs locked before `locked_before`, whose worker is
+    /// presumed lost. Returns the number of jobs queued again.
+    async fn requeue_stale(&self, locked_before: DateTime<Utc>) -> Result<u64>;
+
+    /// List the dead-lettered jobs.
+    async fn dead(&self) -> Result<Vec<JobRecord>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    fn job(name: &str, run_at: DateTime<Utc>) -> NewJob {
+        NewJob {
+            name: name.to_string(),
+            args: serde_json::json!({ "name": name }),
+            run_at,
+        }
+    }
+
+    async fn backends() -> Vec<Box<dyn Backend>> {
+        #[cfg_attr(not(feature = "with-sql"), allow(unused_mut))]
+        let mut backends: Vec<Box<dyn Backend>> = vec![Box::new(MemoryBackend::new())];
+
+        #[cfg(feature = "with-sql")]
+        {
+            // every connection to `sqlite::memory:` opens its own database
+            let mut options = sea_orm::ConnectOptions::new("sqlite::memory:");
+            options.max_connections(1).sqlx_logging(false);
+            let db = sea_orm::Database::connect(options).await.unwrap();
+            use sea_orm_migration::{MigrationTrait, SchemaManager};
+            crate::migration::Migration
+                .up(&SchemaManager::new(&db))
+                .await
+                .unwrap();
+            backends.push(Box::new(SqlBackend::new(db)));
+        }
+
+        backends
+    }
+
+    #[tokio::test]
+    async fn claims_the_due_jobs_once_in_order() {
+        let now = Utc::now();
+        for backend in backends().await {
+            let later = backend
+                .enqueue(job("later", now + Duration::hours(1)))
+                .await
+                .unwrap();
+            let second = backend
+                .enqueue(job("second", now - Duration::seconds(1)))
+                .await
+                .unwrap();
+            let first = backend
+                .enqueue(job("first", now - Duration::seconds(2)))
+                .await
+                .unwrap();
+
+            let claimed = backend.claim(now).await.unwrap().unwrap();
+            assert_eq!(claimed.id, first);
+            assert_eq!(claimed.name, "first");
+            assert_eq!(claimed.args, serde_json::json!({ "name": "first" }));
+            assert_eq!(claimed.status, JobStatus::Running);
+            assert_eq!(claimed.attempts, 1);
+            assert!(claimed.locked_at.is_some());
+
+            let claimed_second = backend.claim(now).await.unwrap().unwrap();
+            assert_eq!(claimed_second.id, second);
+            assert!(backend.claim(now).await.unwrap().is_none());
+
+            backend.complete(claimed.lock()).await.unwrap();
+            backend.complete(claimed_second.lock()).await.unwrap();
+            assert_eq!(
+                backend
+                    .claim(now + Duration::hours(2))
+                    .await
+                    .unwrap()
+                    .unwrap()
+                    .id,
+                later
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_then_dead_letters() {
+        let now = Utc::now();
+        for backend in backends().await {
+            let id = backend.enqueue(job("flaky", now)).await.unwrap();
+
+            let claimed = backend.claim(now).await.unwrap().unwrap();
+            let run_at = now + Duration::seconds(10);
+            backend.retry(claimed.lock(), run_at, "boom").await.unwrap();
+            assert!(backend.claim(now).await.unwrap().is_none());
+
+            let retried = backend.claim(run_at).await.unwrap().unwrap();
+            assert_eq!(retried.id, id);
+            assert_eq!(retried.attempts, 2);
+            assert_eq!(retried.last_error.as_deref(), Some("boom"));
+
+            backend.kill(retried.lock(), "boom again").await.unwrap();
+            assert!(backend
+                .claim(run_at + Duration::days(1))
+                .await
+                .unwrap()
+                .is_none());
+
+            let dead = backend.dead().await.unwrap();
+            assert_eq!(dead.len(), 1);
+            assert_eq!(dead[0].id, id);
+            assert_eq!(dead[0].status, JobStatus::Dead);
+            assert_eq!(dead[0].attempts, 2);
+            assert_eq!(dead[0].last_error.as_deref(), Some("boom again"));
+            assert_eq!(dead[0].locked_at, None);
+        }
+    }
+
+    #[tokio::test]
+    async fn requeues_the_jobs_without_heartbeat() {
+        let now = Utc::now();
+        for backend in backends().await {
+            let lost = backend.enqueue(job("lost", now)).await.unwrap();
+            let alive = backend.enqueue(job("alive", now)).await.unwrap();
+            backend.claim(now).await.unwrap().unwrap();
+            let claimed = backend.claim(now).await.unwrap().unwrap();
+            assert_eq!(claimed.id, alive);
+
+            let later = now + Duration::minutes(5);
+            backend.heartbeat(claimed.lock(), later).await.unwrap();
+            assert_eq!(
+                backend
+                    .requeue_stale(later - Duration::minutes(1))
+                    .await
+                    .unwrap(),
+                1
+            );
+
+            let requeued = backend.claim(later).await.unwrap().unwrap();
+            assert_eq!(requeued.id, lost);
+            assert_eq!(requeued.attempts, 2);
+            assert!(backend.claim(later).await.unwrap().is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_the_updates_of_a_lost_lock() {
+        let now = Utc::now();
+        for backend in backends().await {
+            let id = backend.enqueue(job("slow", now)).await.unwrap();
+            let lost = backend.claim(now).await.unwrap().unwrap().lock();
+            backend
+                .requeue_stale(now + Duration::seconds(1))
+                .await
+                .unwrap();
+
+            // the worker of a job queued again doesn't lock it back
+            let err = backend.heartbeat(lost, now).await.unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("job {id} is not locked by attempt 1 anymore")
+            );
+            assert_eq!(
+                backend
+                    .requeue_stale(now + Duration::seconds(1))
+                    .await
+                    .unwrap(),
+                0
+            );
+
+            // nor updates it once another worker claimed it
+            let claimed = backend.claim(now).await.unwrap().unwrap();
+            assert_eq!(claimed.attempts, 2);
+            assert!(backend.complete(lost).await.is_err());
+            assert!(backend.retry(lost, now, "boom").await.is_err());
+            assert!(backend.kill(lost, "boom").await.is_err());
+            backend.heartbeat(claimed.lock(), now).await.unwrap();
+
+            backend.kill(claimed.lock(), "boom").await.unwrap();
+            assert!(backend.heartbeat(claimed.lock(), now).await.is_err());
+            assert!(backend.complete(claimed.lock()).await.is_err());
+            let dead = backend.dead().await.unwrap();
+            assert_eq!(dead.len(), 1);
+            assert_eq!(dead[0].locked_at, None);
+            assert_eq!(dead[0].last_error.as_deref(), Some("boom"));
+        }
+    }
+}
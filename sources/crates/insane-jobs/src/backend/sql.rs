@@ -0,0 +1,239 @@
+use chrono::{DateTime, Utc};
+use insane_core::error::{Error, Result};
+use sea_orm::{
+    sea_query::{Expr, SimpleExpr},
+    ActiveValue, ColumnTrait, ConnectionTrait, DatabaseBackend, DatabaseConnection, EntityTrait,
+    QueryFilter, Statement,
+};
+
+use super::{Backend, JobLock, JobRecord, JobStatus, NewJob};
+
+mod jobs {
+    use sea_orm::entity::prelude::*;
+
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+    #[sea_orm(table_name = "insane_jobs")]
+    pub struct Model {
+        #[sea_orm(primary_key)]
+        pub id: i64,
+        pub name: String,
+        pub args: Json,
+        pub status: String,
+        pub attempts: i32,
+        pub run_at: DateTimeUtc,
+        pub locked_at: Option<DateTimeUtc>,
+        #[sea_orm(column_type = "Text", nullable)]
+        pub last_error: Option<String>,
+        pub created_at: DateTimeUtc,
+        pub updated_at: DateTimeUtc,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+/// Claims the next due job. Postgres skips the rows locked by the other
+/// workers, SQLite relies on its single writer lock to make the update
+/// atomic.
+const CLAIM_POSTGRES: &str = r"
+UPDATE insane_jobs
+SET status = 'running', locked_at = $1, updated_at = $1, attempts = attempts + 1
+WHERE id = (
+    SELECT id FROM insane_jobs
+    WHERE status = 'queued' AND run_at <= $1
+    ORDER BY run_at, id
+    LIMIT 1
+    FOR UPDATE SKIP LOCKED
+)
+RETURNING *";
+
+const CLAIM_SQLITE: &str = r"
+UPDATE insane_jobs
+SET status = 'running', locked_at = ?, updated_at = ?, attempts = attempts + 1
+WHERE id = (
+    SELECT id FROM insane_jobs
+    WHERE status = 'queued' AND run_at <= ?
+    ORDER BY run_at, id
+    LIMIT 1
+)
+RETURNING *";
+
+/// Stores the jobs in the `insane_jobs` table of the application database.
+///
+/// Supports Postgres and SQLite. The table is created by the
+/// [`crate::migration::Migration`] of the application migrator.
+pub struct SqlBackend {
+    db: DatabaseConnection,
+}
+
+impl SqlBackend {
+    #[must_use]
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Update the job running under `lock`.
+    async fn update(&self, lock: JobLock, values: Vec<(jobs::Column, SimpleExpr)>) -> Result<()> {
+        let mut update = jobs::Entity::update_many()
+            .col_expr(jobs::Column::UpdatedAt, Expr::value(Utc::now()))
+            .filter(locked(lock));
+        for (column, value) in values {
+            update = update.col_expr(column, value);
+        }
+        match update.exec(&self.db).await?.rows_affected {
+            0 => Err(lock.lost()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The condition of the job running under `lock`.
+fn locked(lock: JobLock) -> SimpleExpr {
+    jobs::Column::Id
+        .eq(lock.id)
+        .and(jobs::Column::Attempts.eq(i64::from(lock.attempt)))
+        .and(jobs::Column::Status.eq(JobStatus::Running.as_str()))
+}
+
+impl TryFrom<jobs::Model> for JobRecord {
+    type Error = Error;
+
+    fn try_from(model: jobs::Model) -> Result<Self> {
+        Ok(Self {
+            id: model.id,
+            name: model.name,
+            args: model.args,
+            status: model.status.parse().map_err(Error::Message)?,
+            attempts: u32::try_from(model.attempts).unwrap_or_default(),
+            run_at: model.run_at,
+            locked_at: model.locked_at,
+            last_error: model.last_error,
+            created_at: model.created_at,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Backend for SqlBackend {
+    async fn enqueue(&self, job: NewJob) -> Result<i64> {
+        let now = Utc::now();
+        let model = jobs::ActiveModel {
+            id: ActiveValue::NotSet,
+            name: ActiveValue::Set(job.name),
+            args: ActiveValue::Set(job.args),
+            status: ActiveValue::Set(JobStatus::Queued.to_string()),
+            attempts: ActiveValue::Set(0),
+            run_at: ActiveValue::Set(job.run_at),
+            locked_at: ActiveValue::Set(None),
+            last_error: ActiveValue::Set(None),
+            created_at: ActiveValue::Set(now),
+            updated_at: ActiveValue::Set(now),
+        };
+        let inserted = jobs::Entity::insert(model).exec(&self.db).await?;
+        Ok(inserted.last_insert_id)
+    }
+
+    async fn claim(&self, now: DateTime<Utc>) -> Result<Option<JobRecord>> {
+        let backend = self.db.get_database_backend();
+        let statement = match backend {
+            DatabaseBackend::Postgres => {
+                Statement::from_sql_and_values(backend, CLAIM_POSTGRES, [now.into()])
+            }
+            DatabaseBackend::Sqlite => Statement::from_sql_and_values(
+                backend,
+                CLAIM_SQLITE,
+                [now.into(), now.into(), now.into()],
+            ),
+            DatabaseBackend::MySql => {
+                return Err(Error::string("jobs are not supported on MySQL databases"))
+            }
+        };
+
+        jobs::Entity::find()
+            .from_raw_sql(statement)
+            .one(&self.db)
+            .await?
+            .map(JobRecord::try_from)
+            .transpose()
+    }
+
+    async fn heartbeat(&self, lock: JobLock, now: DateTime<Utc>) -> Result<()> {
+        self.update(lock, vec![(jobs::Column::LockedAt, Expr::value(now))])
+            .await
+    }
+
+    async fn complete(&self, lock: JobLock) -> Result<()> {
+        let deleted = jobs::Entity::delete_many()
+            .filter(locked(lock))
+            .exec(&self.db)
+            .await?;
+        match deleted.rows_affected {
+            0 => Err(lock.lost()),
+            _ => Ok(()),
+        }
+    }
+
+    async fn retry(&self, lock: JobLock, run_at: DateTime<Utc>, error: &str) -> Result<()> {
+        self.update(
+            lock,
+            vec![
+                (
+                    jobs::Column::Status,
+                    Expr::value(JobStatus::Queued.as_str()),
+                ),
+                (jobs::Column::RunAt, Expr::value(run_at)),
+                (
+                    jobs::Column::LockedAt,
+                    Expr::value(Option::<DateTime<Utc>>::None),
+                ),
+                (jobs::Column::LastError, Expr::value(error)),
+            ],
+        )
+        .await
+    }
+
+    async fn kill(&self, lock: JobLock, error: &str) -> Result<()> {
+        self.update(
+            lock,
+            vec![
+                (jobs::Column::Status, Expr::value(JobStatus::Dead.as_str())),
+                (
+                    jobs::Column::LockedAt,
+                    Expr::value(Option::<DateTime<Utc>>::None),
+                ),
+                (jobs::Column::LastError, Expr::value(error)),
+            ],
+        )
+        .await
+    }
+
+    async fn requeue_stale(&self, locked_before: DateTime<Utc>) -> Result<u64> {
+        let result = jobs::Entity::update_many()
+            .col_expr(
+                jobs::Column::Status,
+                Expr::value(JobStatus::Queued.as_str()),
+            )
+            .col_expr(
+                jobs::Column::LockedAt,
+                Expr::value(Option::<DateTime<Utc>>::None),
+            )
+            .col_expr(jobs::Column::UpdatedAt, Expr::value(Utc::now()))
+            .filter(jobs::Column::Status.eq(JobStatus::Running.as_str()))
+            .filter(jobs::Column::LockedAt.lt(locked_before))
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    async fn dead(&self) -> Result<Vec<JobRecord>> {
+        jobs::Entity::find()
+            .filter(jobs::Column::Status.eq(JobStatus::Dead.as_str()))
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(JobRecord::try_from)
+            .collect()
+    }
+}
@@ -0,0 +1,186 @@
+use std::time::Duration;
+
+use insane_core::{
+    config::loader::{Config, ConfigLoader},
+    environment::Environment,
+    error::{Error, ErrorCode, Result},
+};
+use serde::{Deserialize, Serialize};
+
+fn default_concurrency() -> usize {
+    4
+}
+
+fn default_poll_interval() -> u64 {
+    1_000
+}
+
+fn default_max_attempts() -> u32 {
+    5
+}
+
+fn default_initial_backoff() -> u64 {
+    1_000
+}
+
+fn default_max_backoff() -> u64 {
+    3_600_000
+}
+
+fn default_lock_timeout() -> u64 {
+    300_000
+}
+
+/// The lowest `lock_timeout`, in milliseconds. The running jobs refresh
+/// their lock a few times per `lock_timeout`.
+const MIN_LOCK_TIMEOUT: u64 = 1_000;
+
+/// Where the jobs are stored.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobsBackend {
+    /// The `insane_jobs` table of the application database.
+    #[cfg_attr(feature = "with-sql", default)]
+    #[cfg(feature = "with-sql")]
+    Sql,
+    /// An in-process queue, lost on restart. Meant for tests.
+    #[cfg_attr(not(feature = "with-sql"), default)]
+    Memory,
+}
+
+/// Jobs configuration
+///
+/// Example:
+/// ```yaml
+/// jobs:
+///   enable: true
+///   backend: sql
+///   concurrency: 4
+///   poll_interval: 1000
+///   max_attempts: 5
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JobsConfig {
+    /// Enable the job server
+    #[serde(default)]
+    pub enable: bool,
+
+    #[serde(default)]
+    pub backend: JobsBackend,
+
+    /// Number of jobs performed at the same time.
+    #[serde(default = "default_concurrency")]
+    pub concurrency: usize,
+
+    /// Time in milliseconds to wait before polling again an empty queue.
+    #[serde(default = "default_poll_interval")]
+    pub poll_interval: u64,
+
+    /// Number of attempts before a job is dead-lettered, unless the job
+    /// overrides it with [`crate::job::Job::max_attempts`].
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+
+    /// Delay in milliseconds before the first retry. Doubled after each
+    /// failed attempt.
+    #[serde(default = "default_initial_backoff")]
+    pub initial_backoff: u64,
+
+    /// Upper bound in milliseconds of the retry delay.
+    #[serde(default = "default_max_backoff")]
+    pub max_backoff: u64,
+
+    /// Time in milliseconds after which a running job is considered lost
+    /// (e.g. the worker crashed) and queued again. The workers refresh the
+    /// lock of their running jobs, so it can be shorter than the jobs. At
+    /// least `1000`.
+    #[serde(default = "default_lock_timeout")]
+    pub lock_timeout: u64,
+}
+
+impl JobsConfig {
+    /// Load the `jobs` configuration.
+    ///
+    /// # Errors
+    /// When the configuration can't be loaded, or `lock_timeout` is lower
+    /// than `1000`.
+    pub fn new(env: &Environment, app_name: &str) -> Result<Self> {
+        let config = Self::from_key("jobs", env, app_name)?;
+        if config.lock_timeout < MIN_LOCK_TIMEOUT {
+            return Err(Error::new(
+                ErrorCode::CONFIG,
+                format!(
+                    "jobs.lock_timeout is {}ms, it must be at least {MIN_LOCK_TIMEOUT}ms",
+                    config.lock_timeout
+                ),
+            ));
+        }
+        Ok(config)
+    }
+
+    #[must_use]
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_millis(self.poll_interval)
+    }
+
+    #[must_use]
+    pub fn lock_timeout(&self) -> Duration {
+        Duration::from_millis(self.lock_timeout)
+    }
+
+    /// Delay before the next attempt of a job that failed `attempts` times.
+    #[must_use]
+    pub fn backoff(&self, attempts: u32) -> Duration {
+        let factor = 2u64.saturating_pow(attempts.saturating_sub(1));
+        Duration::from_millis(
+            self.initial_backoff
+                .saturating_mul(factor)
+                .min(self.max_backoff),
+        )
+    }
+}
+
+impl Default for JobsConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            backend: JobsBackend::default(),
+            concurrency: default_concurrency(),
+            poll_interval: default_poll_interval(),
+            max_attempts: default_max_attempts(),
+            initial_backoff: default_initial_backoff(),
+            max_backoff: default_max_backoff(),
+            lock_timeout: default_lock_timeout(),
+        }
+    }
+}
+
+impl Config for JobsConfig {
+    fn enable(&self) -> bool {
+        self.enable
+    }
+}
+
+impl ConfigLoader for JobsConfig {
+    type Config = JobsConfig;
+    type Error = Error;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_max() {
+        let config = JobsConfig {
+            initial_backoff: 1_000,
+            max_backoff: 5_000,
+            ..JobsConfig::default()
+        };
+        assert_eq!(config.backoff(1), Duration::from_secs(1));
+        assert_eq!(config.backoff(2), Duration::from_secs(2));
+        assert_eq!(config.backoff(3), Duration::from_secs(4));
+        assert_eq!(config.backoff(4), Duration::from_secs(5));
+        assert_eq!(config.backoff(u32::MAX), Duration::from_secs(5));
+    }
+}
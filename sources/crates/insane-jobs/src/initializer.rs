@@ -0,0 +1,28 @@
+use std::sync::Arc;
+
+use insane_core::{context::Context, error::Result, hook::Initializer};
+
+use crate::queue::Queue;
+
+/// Registers the [`Queue`] on the context before the servers start, so every
+/// server can enqueue jobs, including in processes not running the
+/// [`crate::server::JobServer`].
+pub struct JobsInitializer;
+
+#[async_trait::async_trait]
+impl Initializer for JobsInitializer {
+    type Context = Arc<Box<dyn Context>>;
+
+    fn name(&self) -> String {
+        "jobs".to_string()
+    }
+
+    async fn before_run(
+        &self,
+        app_context: Arc<Box<dyn Context>>,
+        _context: Option<Self::Context>,
+    ) -> Result<()> {
+        Queue::from_context(&app_context).await?;
+        Ok(())
+    }
+}
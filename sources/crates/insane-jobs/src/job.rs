@@ -0,0 +1,86 @@
+use std::{collections::HashMap, sync::Arc};
+
+use insane_core::{
+    context::Context,
+    error::{Error, Result},
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// A unit of background work.
+///
+/// The arguments are stored as JSON, so the job can be performed by another
+/// process than the one that enqueued it.
+#[async_trait::async_trait]
+pub trait Job: Send + Sync + 'static {
+    /// Unique name used to route the stored jobs to this handler.
+    const NAME: &'static str;
+
+    /// The arguments the job is enqueued with.
+    type Args: Serialize + DeserializeOwned + Send;
+
+    /// Perform the job. Returning an error schedules a retry, or dead-letters
+    /// the job once it ran out of attempts.
+    async fn perform(&self, ctx: Arc<Box<dyn Context>>, args: Self::Args) -> Result<()>;
+
+    /// Number of attempts before the job is dead-lettered. Defaults to
+    /// `jobs.max_attempts`.
+    fn max_attempts(&self) -> Option<u32> {
+        None
+    }
+}
+
+/// Object-safe view of a [`Job`], taking its arguments as JSON.
+#[async_trait::async_trait]
+pub(crate) trait Handler: Send + Sync {
+    async fn perform(&self, ctx: Arc<Box<dyn Context>>, args: serde_json::Value) -> Result<()>;
+
+    fn max_attempts(&self) -> Option<u32>;
+}
+
+struct JobHandler<J: Job>(J);
+
+#[async_trait::async_trait]
+impl<J: Job> Handler for JobHandler<J> {
+    async fn perform(&self, ctx: Arc<Box<dyn Context>>, args: serde_json::Value) -> Result<()> {
+        let args = serde_json::from_value(args).map_err(|err| {
+            Error::Message(format!("invalid arguments for job `{}`: {err}", J::NAME))
+        })?;
+        self.0.perform(ctx, args).await
+    }
+
+    fn max_attempts(&self) -> Option<u32> {
+        self.0.max_attempts()
+    }
+}
+
+/// The jobs a [`crate::server::JobServer`] knows how to perform.
+#[derive(Default, Clone)]
+pub struct JobRegistry {
+    handlers: HashMap<&'static str, Arc<dyn Handler>>,
+}
+
+impl JobRegistry {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a job, replacing any job registered with the same name.
+    #[must_use]
+    pub fn register<J: Job>(mut self, job: J) -> Self {
+        self.handlers.insert(J::NAME, Arc::new(JobHandler(job)));
+        self
+    }
+
+    /// Names of the registered jobs.
+    #[must_use]
+    pub fn names(&self) -> Vec<&'static str> {
+        let mut names: Vec<_> = self.handlers.keys().copied().collect();
+        names.sort_unstable();
+        names
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<Arc<dyn Handler>> {
+        self.handlers.get(name).cloned()
+    }
+}
@@ -0,0 +1,57 @@
+//! # Background jobs
+//!
+//! Jobs are enqueued through a [`queue::Queue`] registered on the application
+//! context extensions, stored by a [`backend::Backend`] and performed by the
+//! [`server::JobServer`].
+//!
+//! # Example:
+//!
+//! ```rust
+//! use insane_core::{context::Context, error::Result};
+//! use insane_jobs::prelude::*;
+//! use serde::{Deserialize, Serialize};
+//! use std::sync::Arc;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct WelcomeArgs {
+//!     user_id: i32,
+//! }
+//!
+//! struct WelcomeEmail;
+//!
+//! #[async_trait::async_trait]
+//! impl Job for WelcomeEmail {
+//!     const NAME: &'static str = "welcome_email";
+//!     type Args = WelcomeArgs;
+//!
+//!     async fn perform(&self, _ctx: Arc<Box<dyn Context>>, args: WelcomeArgs) -> Result<()> {
+//!         tracing::info!(user_id = args.user_id, "sending welcome email");
+//!         Ok(())
+//!     }
+//! }
+//!
+//! async fn register(ctx: Arc<Box<dyn Context>>) -> Result<()> {
+//!     let queue = Queue::from_context(&ctx).await?;
+//!     queue.enqueue::<WelcomeEmail>(&WelcomeArgs { user_id: 1 }).await?;
+//!     Ok(())
+//! }
+//!
+//! let server = JobServer::new(JobRegistry::new().register(WelcomeEmail));
+//! ```
+
+pub mod backend;
+pub mod config;
+pub mod initializer;
+pub mod job;
+#[cfg(feature = "with-sql")]
+pub mod migration;
+pub mod queue;
+pub mod server;
+
+pub mod prelude {
+    pub use crate::config::JobsConfig;
+    pub use crate::initializer::JobsInitializer;
+    pub use crate::job::{Job, JobRegistry};
+    pub use crate::queue::Queue;
+    pub use crate::server::JobServer;
+}
@@ -0,0 +1,131 @@
+//! The migration creating the `insane_jobs` table of the
+//! [`crate::backend::SqlBackend`].
+//!
+//! Add it to the migrator of the application, before the migrations of the
+//! application:
+//!
+//! ```rust
+//! use sea_orm_migration::prelude::*;
+//!
+//! pub struct Migrator;
+//!
+//! #[async_trait::async_trait]
+//! impl MigratorTrait for Migrator {
+//!     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+//!         vec![Box::new(insane_jobs::migration::Migration)]
+//!     }
+//! }
+//! ```
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveIden)]
+enum InsaneJobs {
+    Table,
+    Id,
+    Name,
+    Args,
+    Status,
+    Attempts,
+    RunAt,
+    LockedAt,
+    LastError,
+    CreatedAt,
+    UpdatedAt,
+}
+
+const STATUS_RUN_AT_INDEX: &str = "idx_insane_jobs_status_run_at";
+
+/// Creates the `insane_jobs` table.
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &'static str {
+        "m20240101_000001_insane_jobs"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(InsaneJobs::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(InsaneJobs::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(InsaneJobs::Name).string().not_null())
+                    .col(ColumnDef::new(InsaneJobs::Args).json().not_null())
+                    .col(ColumnDef::new(InsaneJobs::Status).string().not_null())
+                    .col(ColumnDef::new(InsaneJobs::Attempts).integer().not_null())
+                    .col(
+                        ColumnDef::new(InsaneJobs::RunAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(InsaneJobs::LockedAt).timestamp_with_time_zone())
+                    .col(ColumnDef::new(InsaneJobs::LastError).text())
+                    .col(
+                        ColumnDef::new(InsaneJobs::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(InsaneJobs::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name(STATUS_RUN_AT_INDEX)
+                    .table(InsaneJobs::Table)
+                    .col(InsaneJobs::Status)
+                    .col(InsaneJobs::RunAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(InsaneJobs::Table).to_owned())
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::ConnectOptions;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn creates_and_drops_the_table() {
+        let mut options = ConnectOptions::new("sqlite::memory:");
+        options.max_connections(1).sqlx_logging(false);
+        let db = sea_orm::Database::connect(options).await.unwrap();
+        let manager = SchemaManager::new(&db);
+
+        Migration.up(&manager).await.unwrap();
+        assert!(manager.has_table("insane_jobs").await.unwrap());
+        assert!(manager
+            .has_index("insane_jobs", STATUS_RUN_AT_INDEX)
+            .await
+            .unwrap());
+
+        Migration.down(&manager).await.unwrap();
+        assert!(!manager.has_table("insane_jobs").await.unwrap());
+    }
+}
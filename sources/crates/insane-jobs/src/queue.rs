@@ -0,0 +1,101 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::{DateTime, Utc};
+use insane_core::{
+    context::Context,
+    error::{Error, Result},
+};
+
+use crate::{
+    backend::{Backend, MemoryBackend, NewJob},
+    config::{JobsBackend, JobsConfig},
+    job::Job,
+};
+
+/// Handle used to enqueue jobs.
+///
+/// The queue of the application is registered on the context extensions by
+/// [`Queue::from_context`], so HTTP handlers can get it with
+/// `Ext<Queue>`.
+#[derive(Clone)]
+pub struct Queue {
+    backend: Arc<dyn Backend>,
+}
+
+impl Queue {
+    #[must_use]
+    pub fn new(backend: Arc<dyn Backend>) -> Self {
+        Self { backend }
+    }
+
+    /// Get the queue registered on the context, creating it from the `jobs`
+    /// configuration when there is none yet.
+    ///
+    /// The `sql` backend stores the jobs in the table created by
+    /// [`crate::migration::Migration`].
+    ///
+    /// # Errors
+    /// When the configuration can't be loaded or the backend storage can't be
+    /// prepared.
+    pub async fn from_context(ctx: &Arc<Box<dyn Context>>) -> Result<Arc<Self>> {
+        if let Some(queue) = ctx.extensions().get::<Self>() {
+            return Ok(queue);
+        }
+
+        let config = JobsConfig::new(ctx.environment(), &ctx.config().application_name)?;
+        let mut created = false;
+        let queue = ctx.extensions().get_or_insert_with(|| {
+            created = true;
+            let backend: Arc<dyn Backend> = match config.backend {
+                #[cfg(feature = "with-sql")]
+                JobsBackend::Sql => Arc::new(crate::backend::SqlBackend::new(ctx.sql().clone())),
+                JobsBackend::Memory => Arc::new(MemoryBackend::new()),
+            };
+            Self::new(backend)
+        });
+        if created {
+            queue.backend.setup().await?;
+        }
+        Ok(queue)
+    }
+
+    #[must_use]
+    pub fn backend(&self) -> &Arc<dyn Backend> {
+        &self.backend
+    }
+
+    /// Enqueue a job to be performed as soon as possible.
+    ///
+    /// # Errors
+    /// When the arguments can't be serialized or the job can't be stored.
+    pub async fn enqueue<J: Job>(&self, args: &J::Args) -> Result<i64> {
+        self.enqueue_at::<J>(args, Utc::now()).await
+    }
+
+    /// Enqueue a job to be performed after `delay`.
+    ///
+    /// # Errors
+    /// When the arguments can't be serialized or the job can't be stored.
+    pub async fn enqueue_in<J: Job>(&self, args: &J::Args, delay: Duration) -> Result<i64> {
+        let delay = chrono::Duration::from_std(delay).map_err(|e| Error::msg(e).bt())?;
+        self.enqueue_at::<J>(args, Utc::now() + delay).await
+    }
+
+    /// Enqueue a job to be performed at `run_at`.
+    ///
+    /// # Errors
+    /// When the arguments can't be serialized or the job can't be stored.
+    pub async fn enqueue_at<J: Job>(&self, args: &J::Args, run_at: DateTime<Utc>) -> Result<i64> {
+        let args = serde_json::to_value(args).map_err(Error::JSON)?;
+        let id = self
+            .backend
+            .enqueue(NewJob {
+                name: J::NAME.to_string(),
+                args,
+                run_at,
+            })
+            .await?;
+        tracing::debug!(job.id = id, job.name = J::NAME, %run_at, "job enqueued");
+        Ok(id)
+    }
+}
@@ -0,0 +1,466 @@
+use std::{
+    panic::AssertUnwindSafe,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use futures_util::FutureExt;
+use insane_core::{
+    context::Context,
+    error::{Error, Result},
+    metrics,
+    server::{Readiness, Server},
+    shutdown::ShutdownToken,
+};
+use tokio::{
+    sync::{Mutex, OwnedSemaphorePermit, Semaphore},
+    task::JoinSet,
+};
+use tracing::Instrument;
+
+use crate::{
+    backend::{Backend, JobLock, JobRecord},
+    config::JobsConfig,
+    job::JobRegistry,
+    queue::Queue,
+};
+
+/// Server performing the jobs of the [`Queue`] registered on the context.
+///
+/// Several job servers, in the same or different processes, can share the
+/// same storage: a job is claimed by one worker only.
+pub struct JobServer {
+    pub registry: JobRegistry,
+    pub config: Arc<Mutex<Option<JobsConfig>>>,
+}
+
+impl JobServer {
+    /// Create a new instance of the server
+    #[must_use]
+    pub fn new(registry: JobRegistry) -> Self {
+        Self {
+            registry,
+            config: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Load the `jobs` configuration once and cache it.
+    async fn config(&self, context: &Arc<Box<dyn Context>>) -> Result<JobsConfig> {
+        let mut guard = self.config.lock().await;
+        if let Some(config) = &*guard {
+            return Ok(config.clone());
+        }
+
+        let config = JobsConfig::new(context.environment(), &context.config().application_name)?;
+        *guard = Some(config.clone());
+        Ok(config)
+    }
+}
+
+#[async_trait::async_trait]
+impl Server for JobServer {
+    fn name(&self) -> String {
+        "jobs_server".to_string()
+    }
+
+    async fn enable(&self, context: Arc<Box<dyn Context>>) -> Result<bool> {
+        Ok(self.config(&context).await?.enable)
+    }
+
+    /// Poll the queue and perform the due jobs, at most `jobs.concurrency` at
+    /// the same time. Once `shutdown` is triggered no new job is claimed and
+    /// the running ones are awaited.
+    async fn serve(
+        &self,
+        context: Arc<Box<dyn Context>>,
+        shutdown: ShutdownToken,
+        ready: Readiness,
+    ) -> Result<()> {
+        let config = self.config(&context).await?;
+        let queue = Queue::from_context(&context).await?;
+        let worker = Arc::new(Worker {
+            context,
+            registry: self.registry.clone(),
+            backend: queue.backend().clone(),
+            config: config.clone(),
+        });
+
+        tracing::info!(
+            jobs = ?self.registry.names().join(","),
+            concurrency = config.concurrency,
+            "job server started"
+        );
+        ready.ready();
+
+        let permits = Arc::new(Semaphore::new(config.concurrency.max(1)));
+        let mut running = JoinSet::new();
+        let mut stale_check = tokio::time::interval(config.lock_timeout() / 2);
+
+        loop {
+            tokio::select! {
+                () = shutdown.triggered() => break,
+                _ = stale_check.tick() => worker.requeue_stale().await,
+                permit = permits.clone().acquire_owned() => {
+                    let permit = permit.map_err(|e| Error::msg(e).bt())?;
+                    match worker.backend.claim(Utc::now()).await {
+                        Ok(Some(job)) => {
+                            running.spawn(worker.clone().perform(job, permit));
+                            continue;
+                        }
+                        Ok(None) => {}
+                        Err(err) => tracing::error!(err.msg = %err, "failed to claim a job"),
+                    }
+                    drop(permit);
+                    tokio::select! {
+                        () = tokio::time::sleep(config.poll_interval()) => {},
+                        () = shutdown.triggered() => break,
+                    }
+                }
+            }
+            while running.try_join_next().is_some() {}
+        }
+
+        tracing::info!(jobs = running.len(), "waiting for the running jobs");
+        while running.join_next().await.is_some() {}
+        Ok(())
+    }
+}
+
+struct Worker {
+    context: Arc<Box<dyn Context>>,
+    registry: JobRegistry,
+    backend: Arc<dyn Backend>,
+    config: JobsConfig,
+}
+
+impl Worker {
+    async fn requeue_stale(&self) {
+        let Ok(lock_timeout) = chrono::Duration::from_std(self.config.lock_timeout()) else {
+            return;
+        };
+        match self.backend.requeue_stale(Utc::now() - lock_timeout).await {
+            Ok(0) => {}
+            Ok(count) => tracing::warn!(count, "queued again jobs whose worker was lost"),
+            Err(err) => tracing::error!(err.msg = %err, "failed to queue again the stale jobs"),
+        }
+    }
+
+    /// Perform a claimed job and store the outcome: the job is removed on
+    /// success, queued again with a backoff on failure, and dead-lettered
+    /// once it ran out of attempts.
+    async fn perform(self: Arc<Self>, job: JobRecord, _permit: OwnedSemaphorePermit) {
+        let span = tracing::info_span!(
+            "job",
+            job.id = job.id,
+            job.name = job.name,
+            job.attempt = job.attempts
+        );
+
+        async move {
+            let Some(handler) = self.registry.get(&job.name) else {
+                let error = format!("no job registered with the name `{}`", job.name);
+                tracing::error!(error, "job dead-lettered");
+                record(&job.name, "dead", None);
+                self.store(self.backend.kill(job.lock(), &error).await);
+                return;
+            };
+
+            let started = Instant::now();
+            let performed =
+                AssertUnwindSafe(handler.perform(self.context.clone(), job.args.clone()))
+                    .catch_unwind();
+            let result = tokio::select! {
+                result = performed => result,
+                () = self.keep_locked(job.lock()) => unreachable!("the lock is kept forever"),
+            }
+            .unwrap_or_else(|panic| {
+                let msg = panic.downcast_ref::<String>().map_or_else(
+                    || {
+                        panic
+                            .downcast_ref::<&str>()
+                            .map_or("no error details", |s| s)
+                    },
+                    |s| s.as_str(),
+                );
+                Err(Error::Message(format!("job panicked: {msg}")))
+            });
+            let elapsed = started.elapsed();
+
+            let err = match result {
+                Ok(()) => {
+                    tracing::info!(?elapsed, "job completed");
+                    record(&job.name, "completed", Some(elapsed));
+                    self.store(self.backend.complete(job.lock()).await);
+                    return;
+                }
+                Err(err) => err.to_string(),
+            };
+
+            let max_attempts = handler.max_attempts().unwrap_or(self.config.max_attempts);
+            if job.attempts >= max_attempts {
+                tracing::error!(?elapsed, err.msg = err, "job dead-lettered");
+                record(&job.name, "dead", Some(elapsed));
+                self.store(self.backend.kill(job.lock(), &err).await);
+                return;
+            }
+
+            let backoff = self.config.backoff(job.attempts);
+            tracing::warn!(?elapsed, ?backoff, err.msg = err, "job failed, retrying");
+            record(&job.name, "retried", Some(elapsed));
+            let run_at = Utc::now() + chrono::Duration::from_std(backoff).unwrap_or_default();
+            self.store(self.backend.retry(job.lock(), run_at, &err).await);
+        }
+        .instrument(span)
+        .await;
+    }
+
+    /// Refresh `lock` a few times per `lock_timeout`, until the future is
+    /// dropped.
+    async fn keep_locked(&self, lock: JobLock) {
+        let mut heartbeat = tokio::time::interval(self.config.lock_timeout() / 3);
+        heartbeat.tick().await;
+        loop {
+            heartbeat.tick().await;
+            if let Err(err) = self.backend.heartbeat(lock, Utc::now()).await {
+                tracing::error!(err.msg = %err, "failed to refresh the job lock");
+            }
+        }
+    }
+
+    fn store(&self, result: Result<()>) {
+        if let Err(err) = result {
+            tracing::error!(err.msg = %err, "failed to store the job outcome");
+        }
+    }
+}
+
+/// Count the job in `jobs_total` by `outcome`, and time its run in
+/// `job_duration_seconds`, see [`insane_core::metrics`].
+fn record(job: &str, outcome: &str, elapsed: Option<Duration>) {
+    let recorded =
+        metrics::counter("jobs_total", "Jobs performed", &["job", "outcome"]).and_then(|jobs| {
+            jobs.with_label_values(&[job, outcome]).inc();
+            if let Some(elapsed) = elapsed {
+                metrics::histogram("job_duration_seconds", "Duration of the jobs", &["job"])?
+                    .with_label_values(&[job])
+                    .observe(elapsed.as_secs_f64());
+            }
+            Ok(())
+        });
+    if let Err(err) = recorded {
+        tracing::error!(err.msg = %err, "failed to record the job metrics");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex as StdMutex;
+
+    use insane_core::{config::InsaneConfig, context::test_context};
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::{
+        backend::{JobStatus, MemoryBackend},
+        config::JobsBackend,
+        job::Job,
+    };
+
+    #[derive(Serialize, Deserialize)]
+    struct Args {
+        fail: bool,
+        panic: bool,
+    }
+
+    /// Records the arguments of its runs and when they started.
+    #[derive(Default, Clone)]
+    struct Recorded {
+        runs: Arc<StdMutex<Vec<(Instant, bool)>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Job for Recorded {
+        const NAME: &'static str = "recorded";
+        type Args = Args;
+
+        async fn perform(&self, _ctx: Arc<Box<dyn Context>>, args: Args) -> Result<()> {
+            self.runs.lock().unwrap().push((Instant::now(), args.fail));
+            assert!(!args.panic, "boom");
+            if args.fail {
+                return Err(Error::string("boom"));
+            }
+            Ok(())
+        }
+
+        fn max_attempts(&self) -> Option<u32> {
+            Some(3)
+        }
+    }
+
+    fn config() -> JobsConfig {
+        JobsConfig {
+            enable: true,
+            backend: JobsBackend::Memory,
+            concurrency: 2,
+            poll_interval: 10,
+            max_attempts: 5,
+            initial_backoff: 20,
+            max_backoff: 1_000,
+            lock_timeout: 1_000,
+        }
+    }
+
+    /// A context holding a queue on `backend`.
+    fn context(backend: Arc<MemoryBackend>) -> Arc<Box<dyn Context>> {
+        let context = test_context(InsaneConfig::default());
+        context.extensions().insert(Queue::new(backend));
+        context
+    }
+
+    /// Serve the jobs of `backend` until `done` holds, then shut down.
+    async fn serve_until(
+        registry: JobRegistry,
+        backend: Arc<MemoryBackend>,
+        done: impl Fn(&MemoryBackend) -> bool,
+    ) {
+        let server = JobServer {
+            registry,
+            config: Arc::new(Mutex::new(Some(config()))),
+        };
+        let shutdown = ShutdownToken::new();
+        let served = server.serve(context(backend.clone()), shutdown.clone(), Readiness::new());
+        let waited = async {
+            tokio::time::timeout(Duration::from_secs(5), async {
+                while !done(&backend) {
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                }
+            })
+            .await
+            .expect("the jobs were not performed in time");
+            shutdown.trigger();
+        };
+
+        let (served, ()) = tokio::join!(served, waited);
+        served.unwrap();
+    }
+
+    async fn enqueue(backend: &Arc<MemoryBackend>, args: &Args) {
+        Queue::new(backend.clone())
+            .enqueue::<Recorded>(args)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn performs_and_removes_the_jobs() {
+        let backend = Arc::new(MemoryBackend::new());
+        let job = Recorded::default();
+        enqueue(
+            &backend,
+            &Args {
+                fail: false,
+                panic: false,
+            },
+        )
+        .await;
+        enqueue(
+            &backend,
+            &Args {
+                fail: false,
+                panic: false,
+            },
+        )
+        .await;
+
+        let registry = JobRegistry::new().register(job.clone());
+        serve_until(registry, backend.clone(), |backend| {
+            backend.jobs().is_empty()
+        })
+        .await;
+
+        assert_eq!(job.runs.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn retries_with_a_backoff_then_dead_letters() {
+        let backend = Arc::new(MemoryBackend::new());
+        let job = Recorded::default();
+        enqueue(
+            &backend,
+            &Args {
+                fail: true,
+                panic: false,
+            },
+        )
+        .await;
+
+        let registry = JobRegistry::new().register(job.clone());
+        let dead = |backend: &MemoryBackend| {
+            backend
+                .jobs()
+                .iter()
+                .all(|job| job.status == JobStatus::Dead)
+        };
+        serve_until(registry, backend.clone(), dead).await;
+
+        // the job overrides `max_attempts`
+        let runs = job.runs.lock().unwrap().clone();
+        assert_eq!(runs.len(), 3);
+        assert!(runs[1].0 - runs[0].0 >= Duration::from_millis(20));
+        assert!(runs[2].0 - runs[1].0 >= Duration::from_millis(40));
+
+        let jobs = backend.jobs();
+        assert_eq!(jobs[0].attempts, 3);
+        assert_eq!(jobs[0].last_error.as_deref(), Some("boom"));
+        assert_eq!(jobs[0].locked_at, None);
+    }
+
+    #[tokio::test]
+    async fn turns_the_panics_into_failures() {
+        let backend = Arc::new(MemoryBackend::new());
+        enqueue(
+            &backend,
+            &Args {
+                fail: false,
+                panic: true,
+            },
+        )
+        .await;
+
+        let registry = JobRegistry::new().register(Recorded::default());
+        let retried =
+            |backend: &MemoryBackend| backend.jobs().iter().all(|job| job.last_error.is_some());
+        serve_until(registry, backend.clone(), retried).await;
+
+        let jobs = backend.jobs();
+        assert_eq!(jobs[0].last_error.as_deref(), Some("job panicked: boom"));
+        assert_ne!(jobs[0].status, JobStatus::Running);
+    }
+
+    #[tokio::test]
+    async fn dead_letters_the_unknown_jobs() {
+        let backend = Arc::new(MemoryBackend::new());
+        enqueue(
+            &backend,
+            &Args {
+                fail: false,
+                panic: false,
+            },
+        )
+        .await;
+
+        let dead = |backend: &MemoryBackend| {
+            backend
+                .jobs()
+                .iter()
+                .all(|job| job.status == JobStatus::Dead)
+        };
+        serve_until(JobRegistry::new(), backend.clone(), dead).await;
+
+        assert_eq!(
+            backend.jobs()[0].last_error.as_deref(),
+            Some("no job registered with the name `recorded`")
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use std::{path::PathBuf, time::Duration};
+
+use insane_core::{
+    config::{
+        loader::{Config, ConfigLoader},
+        Secret,
+    },
+    environment::Environment,
+    error::{Error, Result},
+};
+use serde::{Deserialize, Serialize};
+
+fn default_templates() -> PathBuf {
+    PathBuf::from("mailers")
+}
+
+fn default_outbox() -> PathBuf {
+    PathBuf::from("tmp/mailer")
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+fn default_smtp_timeout() -> u64 {
+    10_000
+}
+
+/// Mailer configuration
+///
+/// Example:
+/// ```yaml
+/// mailer:
+///   from: "My App <noreply@myapp.com>"
+///   templates: mailers
+///   queue: true
+///   transport:
+///     driver: smtp
+///     host: smtp.myapp.com
+///     port: 587
+///     security: starttls
+///     username: myapp
+///     password: secret
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MailerConfig {
+    /// Sender of the emails not setting their own.
+    pub from: String,
+
+    /// Folder of the templates, see [`crate::mailer::Mailer::render`].
+    #[serde(default = "default_templates")]
+    pub templates: PathBuf,
+
+    /// Send the emails with a [`crate::job::SendEmail`] background job, so a
+    /// slow transport doesn't hold the requests. When not set, the emails are
+    /// queued when a job queue is registered and the job server is enabled,
+    /// `jobs.enable`, and delivered right away otherwise. `false` always
+    /// delivers right away.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queue: Option<bool>,
+
+    #[serde(default)]
+    pub transport: TransportConfig,
+}
+
+impl MailerConfig {
+    pub fn new(env: &Environment, app_name: &str) -> Result<Self> {
+        Self::from_key("mailer", env, app_name)
+    }
+}
+
+impl Default for MailerConfig {
+    fn default() -> Self {
+        Self {
+            from: "insane <noreply@localhost>".to_string(),
+            templates: default_templates(),
+            queue: None,
+            transport: TransportConfig::default(),
+        }
+    }
+}
+
+/// How the emails are delivered.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "driver", rename_all = "snake_case")]
+pub enum TransportConfig {
+    /// Send through an SMTP server.
+    Smtp(SmtpConfig),
+
+    /// Write each email to a `.eml` file of `path`, for development.
+    File {
+        #[serde(default = "default_outbox")]
+        path: PathBuf,
+    },
+
+    /// Keep the emails in memory, for tests to assert on, see
+    /// [`crate::transport::MemoryTransport`].
+    Memory,
+}
+
+impl Default for TransportConfig {
+    fn default() -> Self {
+        Self::File {
+            path: default_outbox(),
+        }
+    }
+}
+
+/// Encryption of the SMTP connection.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpSecurity {
+    /// Plain text connection, for local SMTP sinks only.
+    None,
+    /// Upgrade a plain text connection with `STARTTLS`.
+    #[default]
+    Starttls,
+    /// TLS from the start of the connection, usually on port 465.
+    Tls,
+}
+
+/// SMTP transport configuration.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SmtpConfig {
+    pub host: String,
+
+    #[serde(default = "default_smtp_port")]
+    pub port: u16,
+
+    #[serde(default)]
+    pub security: SmtpSecurity,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<Secret>,
+
+    /// Timeout in milliseconds of the SMTP commands.
+    #[serde(default = "default_smtp_timeout")]
+    pub timeout: u64,
+}
+
+impl SmtpConfig {
+    #[must_use]
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout)
+    }
+}
+
+impl Config for MailerConfig {}
+
+impl ConfigLoader for MailerConfig {
+    type Config = MailerConfig;
+    type Error = Error;
+}
@@ -0,0 +1,137 @@
+use insane_core::error::{Error, Result};
+use lettre::message::{header::ContentType, Mailbox, Message, MultiPart, SinglePart};
+use serde::{Deserialize, Serialize};
+
+/// An email to send.
+///
+/// Addresses are either bare (`user@example.com`) or with a display name
+/// (`User <user@example.com>`). The email is serializable so it can be sent
+/// from a background job.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Email {
+    /// Sender, `mailer.from` when missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    pub to: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cc: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bcc: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<String>,
+    pub subject: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub html: Option<String>,
+}
+
+impl Email {
+    #[must_use]
+    pub fn new(to: &str, subject: &str) -> Self {
+        Self {
+            to: vec![to.to_string()],
+            subject: subject.to_string(),
+            ..Self::default()
+        }
+    }
+
+    #[must_use]
+    pub fn from(mut self, from: &str) -> Self {
+        self.from = Some(from.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn to(mut self, to: &str) -> Self {
+        self.to.push(to.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn cc(mut self, cc: &str) -> Self {
+        self.cc.push(cc.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn bcc(mut self, bcc: &str) -> Self {
+        self.bcc.push(bcc.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn reply_to(mut self, reply_to: &str) -> Self {
+        self.reply_to = Some(reply_to.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn text(mut self, text: &str) -> Self {
+        self.text = Some(text.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn html(mut self, html: &str) -> Self {
+        self.html = Some(html.to_string());
+        self
+    }
+
+    /// Build the MIME message, `multipart/alternative` when the email has
+    /// both a text and an HTML body.
+    ///
+    /// # Errors
+    /// When an address is invalid, the sender or recipients are missing, or
+    /// the email has no body.
+    pub fn to_message(&self) -> Result<Message> {
+        let from = self
+            .from
+            .as_deref()
+            .ok_or_else(|| Error::string("email has no sender"))?;
+        if self.to.is_empty() && self.cc.is_empty() && self.bcc.is_empty() {
+            return Err(Error::string("email has no recipient"));
+        }
+
+        let mut builder = Message::builder()
+            .from(mailbox(from)?)
+            .subject(&self.subject);
+        for to in &self.to {
+            builder = builder.to(mailbox(to)?);
+        }
+        for cc in &self.cc {
+            builder = builder.cc(mailbox(cc)?);
+        }
+        for bcc in &self.bcc {
+            builder = builder.bcc(mailbox(bcc)?);
+        }
+        if let Some(reply_to) = &self.reply_to {
+            builder = builder.reply_to(mailbox(reply_to)?);
+        }
+
+        let message = match (&self.text, &self.html) {
+            (Some(text), Some(html)) => builder.multipart(MultiPart::alternative_plain_html(
+                text.clone(),
+                html.clone(),
+            )),
+            (Some(text), None) => builder.singlepart(
+                SinglePart::builder()
+                    .header(ContentType::TEXT_PLAIN)
+                    .body(text.clone()),
+            ),
+            (None, Some(html)) => builder.singlepart(
+                SinglePart::builder()
+                    .header(ContentType::TEXT_HTML)
+                    .body(html.clone()),
+            ),
+            (None, None) => return Err(Error::string("email has no body")),
+        };
+        message.map_err(|err| Error::msg(err).bt())
+    }
+}
+
+fn mailbox(address: &str) -> Result<Mailbox> {
+    address
+        .parse()
+        .map_err(|err| Error::Message(format!("invalid email address `{address}`: {err}")))
+}
@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use insane_core::{context::Context, error::Result, hook::Initializer};
+
+use crate::mailer::Mailer;
+
+/// Registers the [`Mailer`] on the context before the servers start, so a
+/// missing or invalid `mailer` configuration fails the boot.
+///
+/// The mailer looks up the job queue, registered by
+/// [`insane_jobs::initializer::JobsInitializer`], when sending, so it works
+/// with or without it, in any order.
+pub struct MailerInitializer;
+
+#[async_trait::async_trait]
+impl Initializer for MailerInitializer {
+    type Context = Arc<Box<dyn Context>>;
+
+    fn name(&self) -> String {
+        "mailer".to_string()
+    }
+
+    async fn before_run(
+        &self,
+        app_context: Arc<Box<dyn Context>>,
+        _context: Option<Self::Context>,
+    ) -> Result<()> {
+        Mailer::from_context(&app_context)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use insane_core::initializers::InitializerChain;
+
+    use super::*;
+
+    #[test]
+    fn boots_without_the_jobs() {
+        let chain = InitializerChain::new(vec![Box::new(MailerInitializer)]).unwrap();
+
+        assert_eq!(chain.names(), ["mailer"]);
+    }
+}
@@ -0,0 +1,22 @@
+use std::sync::Arc;
+
+use insane_core::{context::Context, error::Result};
+use insane_jobs::job::Job;
+
+use crate::{email::Email, mailer::Mailer};
+
+/// Background job delivering the emails sent by [`Mailer::send`].
+///
+/// It must be registered on the [`insane_jobs::server::JobServer`] of the
+/// application when it uses a job queue.
+pub struct SendEmail;
+
+#[async_trait::async_trait]
+impl Job for SendEmail {
+    const NAME: &'static str = "send_email";
+    type Args = Email;
+
+    async fn perform(&self, ctx: Arc<Box<dyn Context>>, email: Email) -> Result<()> {
+        Mailer::from_context(&ctx)?.deliver(email).await
+    }
+}
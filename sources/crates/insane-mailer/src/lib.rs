@@ -0,0 +1,39 @@
+//! # Mailer
+//!
+//! Renders emails from templates and delivers them with the transport of the
+//! `mailer` configuration: SMTP, `.eml` files or memory.
+//!
+//! # Example:
+//!
+//! ```rust
+//! use insane_core::{context::Context, error::Result};
+//! use insane_mailer::prelude::*;
+//! use serde_json::json;
+//! use std::sync::Arc;
+//!
+//! async fn welcome(ctx: Arc<Box<dyn Context>>, to: &str) -> Result<()> {
+//!     let mailer = Mailer::from_context(&ctx)?;
+//!     mailer
+//!         .send_template("welcome", to, json!({ "name": "Ada" }))
+//!         .await?;
+//!     mailer
+//!         .send(Email::new(to, "Your report").text("Everything is fine."))
+//!         .await
+//! }
+//! ```
+
+pub mod config;
+pub mod email;
+pub mod initializer;
+pub mod job;
+pub mod mailer;
+pub mod transport;
+
+pub mod prelude {
+    pub use crate::config::MailerConfig;
+    pub use crate::email::Email;
+    pub use crate::initializer::MailerInitializer;
+    pub use crate::job::SendEmail;
+    pub use crate::mailer::Mailer;
+    pub use crate::transport::{MemoryTransport, Transport};
+}
@@ -0,0 +1,394 @@
+use std::sync::Arc;
+
+use insane_core::{
+    context::Context,
+    error::{Error, Result},
+    extensions::Extensions,
+};
+use insane_jobs::{config::JobsConfig, queue::Queue};
+use minijinja::{path_loader, AutoEscape, ErrorKind};
+use serde::Serialize;
+
+use crate::{
+    config::{MailerConfig, TransportConfig},
+    email::Email,
+    job::SendEmail,
+    transport::{self, MemoryTransport, Transport},
+};
+
+/// Renders and sends the emails of the application.
+///
+/// Emails are sent by a [`SendEmail`] background job when a job [`Queue`] is
+/// registered on the context and the job server is enabled, or delivered
+/// right away, see `mailer.queue`. The `memory` transport always delivers
+/// right away, so that the tests see the emails in [`Mailer::memory`] without
+/// running the jobs.
+pub struct Mailer {
+    config: MailerConfig,
+    transport: Arc<dyn Transport>,
+    memory: Option<MemoryTransport>,
+    templates: minijinja::Environment<'static>,
+    extensions: Extensions,
+    jobs_enabled: bool,
+}
+
+impl Mailer {
+    /// Create a mailer looking for the job queue in `extensions` when sending.
+    /// The job server is considered disabled, see [`Mailer::with_jobs_enabled`].
+    ///
+    /// # Errors
+    /// When the transport can't be configured.
+    pub fn new(config: MailerConfig, extensions: Extensions) -> Result<Self> {
+        let memory = matches!(config.transport, TransportConfig::Memory).then(MemoryTransport::new);
+        let transport: Arc<dyn Transport> = match &memory {
+            Some(memory) => Arc::new(memory.clone()),
+            None => transport::create(&config.transport)?,
+        };
+
+        let mut templates = minijinja::Environment::new();
+        templates.set_loader(path_loader(&config.templates));
+        templates.set_auto_escape_callback(|name| {
+            if name.ends_with("/html.j2") {
+                AutoEscape::Html
+            } else {
+                AutoEscape::None
+            }
+        });
+
+        Ok(Self {
+            config,
+            transport,
+            memory,
+            templates,
+            extensions,
+            jobs_enabled: false,
+        })
+    }
+
+    /// Get the mailer registered on the context, creating it from the
+    /// `mailer` and `jobs` configurations when there is none yet.
+    ///
+    /// # Errors
+    /// When the configuration can't be loaded or the transport can't be
+    /// configured.
+    pub fn from_context(ctx: &Arc<Box<dyn Context>>) -> Result<Arc<Self>> {
+        if let Some(mailer) = ctx.extensions().get::<Self>() {
+            return Ok(mailer);
+        }
+
+        let app_name = &ctx.config().application_name;
+        let config = MailerConfig::new(ctx.environment(), app_name)?;
+        let jobs = JobsConfig::new(ctx.environment(), app_name)?;
+        let mailer = Self::new(config, ctx.extensions().clone())?.with_jobs_enabled(jobs.enable);
+        Ok(ctx.extensions().get_or_insert_with(|| mailer))
+    }
+
+    /// Replace the transport, e.g. with a custom API-based one.
+    #[must_use]
+    pub fn with_transport(mut self, transport: Arc<dyn Transport>) -> Self {
+        self.transport = transport;
+        self.memory = None;
+        self
+    }
+
+    /// Whether the job server runs, so the emails are queued by default.
+    #[must_use]
+    pub fn with_jobs_enabled(mut self, enabled: bool) -> Self {
+        self.jobs_enabled = enabled;
+        self
+    }
+
+    #[must_use]
+    pub fn config(&self) -> &MailerConfig {
+        &self.config
+    }
+
+    /// The in-memory transport when `mailer.transport.driver` is `memory`, to
+    /// assert on the sent emails in tests.
+    #[must_use]
+    pub fn memory(&self) -> Option<&MemoryTransport> {
+        self.memory.as_ref()
+    }
+
+    /// Render the `template` email to `to`.
+    ///
+    /// A template is a folder of the `mailer.templates` folder holding a
+    /// `subject.j2` and at least one of `text.j2` and `html.j2`, rendered
+    /// with `locals`. Values are escaped in `html.j2` only.
+    ///
+    /// ```text
+    /// mailers/
+    ///   welcome/
+    ///     subject.j2    Welcome {{ name }}
+    ///     text.j2
+    ///     html.j2
+    /// ```
+    ///
+    /// # Errors
+    /// When the subject or both bodies are missing, or a template fails to
+    /// render.
+    pub fn render(&self, template: &str, to: &str, locals: impl Serialize) -> Result<Email> {
+        let subject = self
+            .render_file(template, "subject.j2", &locals)?
+            .ok_or_else(|| {
+                Error::Message(format!("mail template `{template}` has no subject.j2"))
+            })?;
+        let text = self.render_file(template, "text.j2", &locals)?;
+        let html = self.render_file(template, "html.j2", &locals)?;
+        if text.is_none() && html.is_none() {
+            return Err(Error::Message(format!(
+                "mail template `{template}` has neither text.j2 nor html.j2"
+            )));
+        }
+
+        let mut email = Email::new(to, subject.trim());
+        email.text = text;
+        email.html = html;
+        Ok(email)
+    }
+
+    fn render_file(
+        &self,
+        template: &str,
+        file: &str,
+        locals: &impl Serialize,
+    ) -> Result<Option<String>> {
+        let name = format!("{template}/{file}");
+        match self.templates.get_template(&name) {
+            Ok(template) => Ok(Some(
+                template
+                    .render(locals)
+                    .map_err(|err| Error::msg(err).bt())?,
+            )),
+            Err(err) if err.kind() == ErrorKind::TemplateNotFound => Ok(None),
+            Err(err) => Err(Error::msg(err).bt()),
+        }
+    }
+
+    /// Send `email`, through the job queue when there is one and
+    /// `mailer.queue` or, when not set, `jobs.enable` is true, unless the
+    /// transport is `memory`. Use [`Mailer::deliver`] to skip the queue.
+    ///
+    /// # Errors
+    /// When the email can't be enqueued or delivered.
+    pub async fn send(&self, email: Email) -> Result<()> {
+        let email = self.with_sender(email);
+        match self.extensions.get::<Queue>() {
+            Some(queue) if self.queued() => {
+                queue.enqueue::<SendEmail>(&email).await?;
+                Ok(())
+            }
+            _ => self.transport.send(&email).await,
+        }
+    }
+
+    /// Render and send the `template` email, see [`Mailer::render`] and
+    /// [`Mailer::send`].
+    ///
+    /// # Errors
+    /// When the email can't be rendered, enqueued or delivered.
+    pub async fn send_template(
+        &self,
+        template: &str,
+        to: &str,
+        locals: impl Serialize,
+    ) -> Result<()> {
+        let email = self.render(template, to, locals)?;
+        self.send(email).await
+    }
+
+    /// Deliver `email` right away, bypassing the job queue.
+    ///
+    /// # Errors
+    /// When the transport fails.
+    pub async fn deliver(&self, email: Email) -> Result<()> {
+        self.transport.send(&self.with_sender(email)).await
+    }
+
+    fn queued(&self) -> bool {
+        self.memory.is_none() && self.config.queue.unwrap_or(self.jobs_enabled)
+    }
+
+    fn with_sender(&self, mut email: Email) -> Email {
+        if email.from.is_none() {
+            email.from = Some(self.config.from.clone());
+        }
+        email
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use insane_jobs::backend::MemoryBackend;
+
+    use super::*;
+
+    fn templates(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("insane-mailer-{name}-{}", std::process::id()));
+        let welcome = path.join("welcome");
+        std::fs::create_dir_all(&welcome).unwrap();
+        std::fs::write(welcome.join("subject.j2"), "Welcome {{ name }}\n").unwrap();
+        std::fs::write(welcome.join("text.j2"), "Hello {{ name }}").unwrap();
+        std::fs::write(welcome.join("html.j2"), "<p>Hello {{ name }}</p>").unwrap();
+        let broken = path.join("broken");
+        std::fs::create_dir_all(&broken).unwrap();
+        std::fs::write(broken.join("subject.j2"), "No body").unwrap();
+        path
+    }
+
+    fn mailer(
+        name: &str,
+        transport: TransportConfig,
+        queue: Option<bool>,
+        extensions: Extensions,
+    ) -> Mailer {
+        let config = MailerConfig {
+            templates: templates(name),
+            queue,
+            transport,
+            ..MailerConfig::default()
+        };
+        Mailer::new(config, extensions).unwrap()
+    }
+
+    #[test]
+    fn renders_the_templates() {
+        let mailer = mailer(
+            "render",
+            TransportConfig::Memory,
+            None,
+            Extensions::default(),
+        );
+
+        let email = mailer
+            .render(
+                "welcome",
+                "user@example.com",
+                serde_json::json!({ "name": "<Ann>" }),
+            )
+            .unwrap();
+        assert_eq!(email.to, ["user@example.com"]);
+        assert_eq!(email.subject, "Welcome <Ann>");
+        assert_eq!(email.text.as_deref(), Some("Hello <Ann>"));
+        assert_eq!(email.html.as_deref(), Some("<p>Hello &lt;Ann&gt;</p>"));
+
+        let err = mailer
+            .render("broken", "user@example.com", serde_json::json!({}))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "mail template `broken` has neither text.j2 nor html.j2"
+        );
+        assert!(mailer.render("missing", "user@example.com", ()).is_err());
+    }
+
+    #[tokio::test]
+    async fn memory_transport_delivers_despite_the_queue() {
+        let extensions = Extensions::default();
+        let backend = Arc::new(MemoryBackend::new());
+        extensions.insert(Queue::new(backend.clone()));
+        let mailer = mailer("memory", TransportConfig::Memory, Some(true), extensions);
+
+        mailer
+            .send_template(
+                "welcome",
+                "user@example.com",
+                serde_json::json!({ "name": "Ann" }),
+            )
+            .await
+            .unwrap();
+
+        let sent = mailer.memory().unwrap().sent();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].from.as_deref(), Some("insane <noreply@localhost>"));
+        assert_eq!(sent[0].subject, "Welcome Ann");
+        assert!(backend.jobs().is_empty());
+
+        mailer.memory().unwrap().clear();
+        assert!(mailer.memory().unwrap().sent().is_empty());
+    }
+
+    #[tokio::test]
+    async fn delivers_right_away_by_default() {
+        // the jobs initializer registers the queue even when the jobs are
+        // disabled, nothing would run the job
+        let extensions = Extensions::default();
+        let backend = Arc::new(MemoryBackend::new());
+        extensions.insert(Queue::new(backend.clone()));
+        let outbox =
+            std::env::temp_dir().join(format!("insane-outbox-direct-{}", std::process::id()));
+        let transport = TransportConfig::File {
+            path: outbox.clone(),
+        };
+        let mailer = mailer("direct", transport, None, extensions);
+
+        mailer
+            .send(Email::new("user@example.com", "Direct").text("body"))
+            .await
+            .unwrap();
+
+        assert!(backend.jobs().is_empty());
+        let sent = std::fs::read_dir(&outbox).unwrap().count();
+        std::fs::remove_dir_all(&outbox).unwrap();
+        assert_eq!(sent, 1);
+    }
+
+    #[tokio::test]
+    async fn other_transports_send_through_the_queue() {
+        let extensions = Extensions::default();
+        let backend = Arc::new(MemoryBackend::new());
+        extensions.insert(Queue::new(backend.clone()));
+        let outbox =
+            std::env::temp_dir().join(format!("insane-outbox-queued-{}", std::process::id()));
+        let transport = TransportConfig::File {
+            path: outbox.clone(),
+        };
+        let mailer = mailer("queue", transport, Some(true), extensions);
+
+        mailer
+            .send(Email::new("user@example.com", "Queued").text("body"))
+            .await
+            .unwrap();
+
+        let jobs = backend.jobs();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].name, "send_email");
+        assert_eq!(jobs[0].args["subject"], "Queued");
+        assert_eq!(jobs[0].args["from"], "insane <noreply@localhost>");
+        assert!(!outbox.exists());
+    }
+
+    #[tokio::test]
+    async fn queues_by_default_when_the_jobs_are_enabled() {
+        let extensions = Extensions::default();
+        let backend = Arc::new(MemoryBackend::new());
+        extensions.insert(Queue::new(backend.clone()));
+        let outbox =
+            std::env::temp_dir().join(format!("insane-outbox-jobs-{}", std::process::id()));
+        let transport = TransportConfig::File {
+            path: outbox.clone(),
+        };
+        let queued =
+            mailer("jobs", transport.clone(), None, extensions.clone()).with_jobs_enabled(true);
+        let direct = mailer("jobs", transport, Some(false), extensions).with_jobs_enabled(true);
+
+        queued
+            .send(Email::new("user@example.com", "Queued").text("body"))
+            .await
+            .unwrap();
+        assert_eq!(backend.jobs().len(), 1);
+        assert!(!outbox.exists());
+
+        direct
+            .send(Email::new("user@example.com", "Direct").text("body"))
+            .await
+            .unwrap();
+        assert_eq!(backend.jobs().len(), 1);
+        let sent = std::fs::read_dir(&outbox).unwrap().count();
+        std::fs::remove_dir_all(&outbox).unwrap();
+        assert_eq!(sent, 1);
+    }
+}
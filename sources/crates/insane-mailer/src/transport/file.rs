@@ -0,0 +1,66 @@
+use std::path::PathBuf;
+
+use insane_core::error::{Error, Result};
+use lettre::{AsyncFileTransport, AsyncTransport, Tokio1Executor};
+
+use super::Transport;
+use crate::email::Email;
+
+/// Writes each email to a `<id>.eml` file, to be opened with a mail client
+/// during development.
+#[derive(Debug, Clone)]
+pub struct FileTransport {
+    path: PathBuf,
+}
+
+impl FileTransport {
+    /// Create the transport. The folder is created with the first email.
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[async_trait::async_trait]
+impl Transport for FileTransport {
+    async fn send(&self, email: &Email) -> Result<()> {
+        tokio::fs::create_dir_all(&self.path).await?;
+        let id = AsyncFileTransport::<Tokio1Executor>::new(&self.path)
+            .send(email.to_message()?)
+            .await
+            .map_err(|err| Error::msg(err).bt())?;
+        tracing::info!(
+            path = %self.path.join(format!("{id}.eml")).display(),
+            "email written"
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn writes_an_eml_file_per_email() {
+        let path = std::env::temp_dir().join(format!("insane-outbox-{}", std::process::id()));
+        let transport = FileTransport::new(&path);
+
+        let email = Email::new("user@example.com", "Hello")
+            .from("app@example.com")
+            .html("<p>Hello</p>");
+        transport.send(&email).await.unwrap();
+        transport.send(&email).await.unwrap();
+
+        let files = std::fs::read_dir(&path)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect::<Vec<_>>();
+        assert_eq!(files.len(), 2);
+        let eml = std::fs::read_to_string(&files[0]).unwrap();
+        assert!(eml.contains("To: user@example.com"));
+        assert!(eml.contains("Subject: Hello"));
+        assert!(eml.contains("<p>Hello</p>"));
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+}
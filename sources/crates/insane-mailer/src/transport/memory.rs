@@ -0,0 +1,77 @@
+use std::sync::{Arc, Mutex, PoisonError};
+
+use insane_core::error::Result;
+
+use super::Transport;
+use crate::email::Email;
+
+/// Keeps the sent emails in memory, for tests.
+///
+/// Clones share the same emails, see [`crate::mailer::Mailer::memory`].
+#[derive(Debug, Clone, Default)]
+pub struct MemoryTransport {
+    sent: Arc<Mutex<Vec<Email>>>,
+}
+
+impl MemoryTransport {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The emails sent so far, oldest first.
+    #[must_use]
+    pub fn sent(&self) -> Vec<Email> {
+        self.sent
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Forget the emails sent so far.
+    pub fn clear(&self) {
+        self.sent
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+}
+
+#[async_trait::async_trait]
+impl Transport for MemoryTransport {
+    /// Store `email`, after checking it would make a valid message.
+    async fn send(&self, email: &Email) -> Result<()> {
+        email.to_message()?;
+        self.sent
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(email.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn keeps_the_valid_emails() {
+        let transport = MemoryTransport::new();
+        let shared = transport.clone();
+
+        let email = Email::new("user@example.com", "Hello")
+            .from("app@example.com")
+            .text("body");
+        transport.send(&email).await.unwrap();
+        assert_eq!(shared.sent(), [email]);
+
+        let invalid = Email::new("not an address", "Hello")
+            .from("app@example.com")
+            .text("body");
+        assert!(transport.send(&invalid).await.is_err());
+        assert_eq!(shared.sent().len(), 1);
+
+        shared.clear();
+        assert!(transport.sent().is_empty());
+    }
+}
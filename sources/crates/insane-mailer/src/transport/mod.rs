@@ -0,0 +1,34 @@
+//! Delivery of the emails, selected by the `mailer.transport`
+//! configuration.
+
+mod file;
+mod memory;
+mod smtp;
+
+use std::sync::Arc;
+
+use insane_core::error::Result;
+
+pub use self::file::FileTransport;
+pub use self::memory::MemoryTransport;
+pub use self::smtp::SmtpTransport;
+use crate::{config::TransportConfig, email::Email};
+
+/// Delivers emails.
+#[async_trait::async_trait]
+pub trait Transport: Send + Sync {
+    /// Deliver `email`. The sender is already resolved.
+    async fn send(&self, email: &Email) -> Result<()>;
+}
+
+/// Create the transport selected by the configuration.
+///
+/// # Errors
+/// When the SMTP relay can't be configured.
+pub fn create(config: &TransportConfig) -> Result<Arc<dyn Transport>> {
+    Ok(match config {
+        TransportConfig::Smtp(smtp) => Arc::new(SmtpTransport::new(smtp)?),
+        TransportConfig::File { path } => Arc::new(FileTransport::new(path)),
+        TransportConfig::Memory => Arc::new(MemoryTransport::new()),
+    })
+}
@@ -0,0 +1,159 @@
+use insane_core::error::{Error, Result};
+use lettre::{
+    transport::smtp::authentication::Credentials, AsyncSmtpTransport, AsyncTransport,
+    Tokio1Executor,
+};
+
+use super::Transport;
+use crate::{
+    config::{SmtpConfig, SmtpSecurity},
+    email::Email,
+};
+
+/// Sends the emails through an SMTP server, reusing pooled connections.
+#[derive(Clone)]
+pub struct SmtpTransport {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpTransport {
+    /// Create the transport. No connection is opened until the first email
+    /// is sent.
+    ///
+    /// # Errors
+    /// When the TLS parameters of the host can't be built.
+    pub fn new(config: &SmtpConfig) -> Result<Self> {
+        let builder = match config.security {
+            SmtpSecurity::None => {
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host)
+            }
+            SmtpSecurity::Starttls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)
+                    .map_err(|err| Error::msg(err).bt())?
+            }
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)
+                .map_err(|err| Error::msg(err).bt())?,
+        };
+        let mut builder = builder.port(config.port).timeout(Some(config.timeout()));
+        if let (Some(username), Some(password)) = (&config.username, &config.password) {
+            builder = builder.credentials(Credentials::new(
+                username.clone(),
+                password.expose().to_string(),
+            ));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Transport for SmtpTransport {
+    async fn send(&self, email: &Email) -> Result<()> {
+        let response = self
+            .transport
+            .send(email.to_message()?)
+            .await
+            .map_err(|err| Error::msg(err).bt())?;
+        tracing::debug!(
+            code = %response.code(),
+            response = response.message().collect::<Vec<_>>().join(" "),
+            "email sent"
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    /// A local SMTP sink accepting one email, returning the commands and the
+    /// message it received.
+    async fn sink(listener: TcpListener) -> Vec<String> {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (read, mut write) = stream.into_split();
+        let mut lines = BufReader::new(read).lines();
+        write.write_all(b"220 sink ESMTP\r\n").await.unwrap();
+
+        let mut received = Vec::new();
+        let mut data = false;
+        while let Some(line) = lines.next_line().await.unwrap() {
+            received.push(line.clone());
+            if data {
+                if line == "." {
+                    write.write_all(b"250 2.0.0 queued\r\n").await.unwrap();
+                    break;
+                }
+                continue;
+            }
+            let command = line.split(' ').next().unwrap_or_default().to_uppercase();
+            let reply: &[u8] = match command.as_str() {
+                "EHLO" => b"250-sink\r\n250 8BITMIME\r\n",
+                "DATA" => {
+                    data = true;
+                    b"354 end with <CRLF>.<CRLF>\r\n"
+                }
+                "QUIT" => b"221 bye\r\n",
+                _ => b"250 OK\r\n",
+            };
+            write.write_all(reply).await.unwrap();
+        }
+        received
+    }
+
+    #[tokio::test]
+    async fn sends_to_the_smtp_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let sink = tokio::spawn(sink(listener));
+
+        let transport = SmtpTransport::new(&SmtpConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            security: SmtpSecurity::None,
+            username: None,
+            password: None,
+            timeout: 5_000,
+        })
+        .unwrap();
+        let email = Email::new("User <user@example.com>", "Hello")
+            .from("App <app@example.com>")
+            .cc("cc@example.com")
+            .text("Hello from the tests");
+        transport.send(&email).await.unwrap();
+
+        let received = sink.await.unwrap();
+        assert!(received[0].starts_with("EHLO "));
+        assert!(received.contains(&"MAIL FROM:<app@example.com>".to_string()));
+        assert!(received.contains(&"RCPT TO:<user@example.com>".to_string()));
+        assert!(received.contains(&"RCPT TO:<cc@example.com>".to_string()));
+        assert!(received.contains(&"Subject: Hello".to_string()));
+        assert!(received.contains(&"Hello from the tests".to_string()));
+    }
+
+    #[tokio::test]
+    async fn fails_when_the_server_is_down() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+
+        let transport = SmtpTransport::new(&SmtpConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            security: SmtpSecurity::None,
+            username: None,
+            password: None,
+            timeout: 1_000,
+        })
+        .unwrap();
+        let email = Email::new("user@example.com", "Hello").text("body");
+        assert!(transport.send(&email).await.is_err());
+    }
+}
@@ -0,0 +1,238 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use chrono::Utc;
+use insane_cli::{
+    commands::{Arg, ArgMatches, Command, CommandCustom},
+    error::{Error, ErrorCode, Result},
+};
+use insane_core::{
+    boot_loader::create_context, config::InsaneConfig, context::Context, environment::Environment,
+    hook::Hooks, initializers::InitializerChain,
+};
+
+use crate::{config::SchedulerConfig, server, task::Tasks};
+
+/// `scheduler` CLI command, to inspect the schedules and run a task once for
+/// debugging.
+///
+/// ```text
+/// myapp scheduler list
+/// myapp scheduler run cleanup
+/// ```
+pub struct SchedulerCommand<H: Hooks> {
+    tasks: Tasks,
+    hooks: PhantomData<H>,
+}
+
+impl<H: Hooks> SchedulerCommand<H> {
+    #[must_use]
+    pub fn new(tasks: Tasks) -> Self {
+        Self {
+            tasks,
+            hooks: PhantomData,
+        }
+    }
+
+    fn list(&self, config: &SchedulerConfig) -> Result<()> {
+        for name in self.tasks.names() {
+            match config.tasks.get(name) {
+                Some(task) => {
+                    let next = task
+                        .schedule()?
+                        .upcoming(Utc)
+                        .next()
+                        .map_or_else(|| "never".to_string(), |next| next.to_rfc3339());
+                    let state = if task.enable { "enabled" } else { "disabled" };
+                    println!("{name}\t{}\t{state}\tnext run: {next}", task.cron);
+                }
+                None => println!("{name}\t-\tnot scheduled"),
+            }
+        }
+        Ok(())
+    }
+
+    async fn run_once(
+        &self,
+        name: &str,
+        config: &SchedulerConfig,
+        app_config: &InsaneConfig,
+        env: &Environment,
+    ) -> Result<()> {
+        let task = self.tasks.get(name).ok_or_else(|| {
+            Error::new(
+                ErrorCode::NOT_FOUND,
+                format!("task `{name}` is not registered"),
+            )
+        })?;
+        let timeout = config.tasks.get(name).and_then(|task| task.timeout());
+
+        let context = create_context::<H>(env, app_config).await?;
+        let result = async {
+            H::before_run(context.clone()).await?;
+            let initializers = InitializerChain::new(H::initializers(context.clone()).await?)?;
+            // the initializers started are shut down when one fails
+            initializers.before_run(&context, None).await?;
+
+            let result = server::run(name, task, context.clone(), timeout, "manual").await;
+            initializers.before_shutdown(&context, None).await;
+            result
+        }
+        .await;
+
+        close(&context).await;
+        result
+    }
+}
+
+#[cfg(feature = "with-sql")]
+async fn close(context: &Arc<Box<dyn Context>>) {
+    if let Err(err) = context.sql().close_by_ref().await {
+        tracing::error!(err.msg = %err, "failed to close the database connection");
+    }
+}
+
+#[cfg(not(feature = "with-sql"))]
+async fn close(_context: &Arc<Box<dyn Context>>) {}
+
+#[async_trait::async_trait]
+impl<H: Hooks> CommandCustom for SchedulerCommand<H> {
+    fn name(&self) -> &str {
+        "scheduler"
+    }
+
+    fn make_subcommand(&self) -> Command {
+        Command::new("scheduler")
+            .about("Inspect and run the scheduled tasks")
+            .subcommand_required(true)
+            .subcommand(Command::new("list").about("List the tasks and their next run"))
+            .subcommand(
+                Command::new("run")
+                    .about("Run a task once, now")
+                    .arg(Arg::new("task").required(true).help("Name of the task")),
+            )
+    }
+
+    async fn execute(
+        &self,
+        args: &ArgMatches,
+        config: &InsaneConfig,
+        env: &Environment,
+    ) -> Result<()> {
+        let scheduler = SchedulerConfig::new(env, &config.application_name)?;
+        match args.subcommand() {
+            Some(("list", _)) => self.list(&scheduler),
+            Some(("run", args)) => {
+                let name = args
+                    .get_one::<String>("task")
+                    .ok_or_else(|| Error::Message("task name missing".to_string()))?;
+                self.run_once(name, &scheduler, config, env).await
+            }
+            _ => Err(Error::Message("unknown scheduler command".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use insane_core::server::Server;
+
+    use super::*;
+    use crate::config::TaskConfig;
+
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+
+    struct App;
+
+    #[async_trait::async_trait]
+    impl Hooks for App {
+        fn app_name() -> &'static str {
+            "scheduler_test"
+        }
+
+        async fn servers(_app_context: Arc<Box<dyn Context>>) -> Result<Vec<Box<dyn Server>>> {
+            Ok(vec![])
+        }
+
+        #[cfg(feature = "with-sql")]
+        async fn truncate(_db: &insane_core::prelude::DatabaseConnection) -> Result<()> {
+            Ok(())
+        }
+
+        #[cfg(feature = "with-sql")]
+        async fn seed(
+            _db: &insane_core::prelude::DatabaseConnection,
+            _path: &std::path::Path,
+        ) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn command() -> SchedulerCommand<App> {
+        SchedulerCommand::new(
+            Tasks::new()
+                .add("count", |_context| async {
+                    RUNS.fetch_add(1, Ordering::SeqCst);
+                    Ok(())
+                })
+                .add("slow", |_context| async {
+                    tokio::time::sleep(Duration::from_secs(10)).await;
+                    Ok(())
+                }),
+        )
+    }
+
+    fn config() -> (SchedulerConfig, InsaneConfig) {
+        let slow = TaskConfig {
+            cron: "0 0 * * *".to_string(),
+            timeout: Some(50),
+            allow_overlap: false,
+            enable: true,
+        };
+        let app_config = InsaneConfig {
+            #[cfg(feature = "with-sql")]
+            sql: insane_core::config::SqlConfig {
+                uri: "sqlite::memory:".into(),
+                ..Default::default()
+            },
+            ..InsaneConfig::default()
+        };
+        let scheduler = SchedulerConfig {
+            enable: true,
+            tasks: [("slow".to_string(), slow)].into(),
+        };
+        (scheduler, app_config)
+    }
+
+    #[tokio::test]
+    async fn runs_a_task_once() {
+        let (scheduler, app_config) = config();
+        command()
+            .run_once("count", &scheduler, &app_config, &Environment::Test)
+            .await
+            .unwrap();
+        assert_eq!(RUNS.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn fails_the_unknown_and_timed_out_tasks() {
+        let (scheduler, app_config) = config();
+
+        let err = command()
+            .run_once("missing", &scheduler, &app_config, &Environment::Test)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), ErrorCode::NOT_FOUND);
+
+        // with the timeout of its schedule
+        let err = command()
+            .run_once("slow", &scheduler, &app_config, &Environment::Test)
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "task timed out after 50ms");
+    }
+}
@@ -0,0 +1,250 @@
+use std::{collections::BTreeMap, str::FromStr, time::Duration};
+
+use cron::Schedule;
+use insane_core::{
+    config::loader::{Config, ConfigLoader},
+    environment::Environment,
+    error::{Error, Result},
+};
+use serde::{Deserialize, Serialize};
+
+fn default_enable() -> bool {
+    true
+}
+
+/// Scheduler configuration
+///
+/// Tasks are keyed by the name they are registered with in
+/// [`crate::task::Tasks`].
+///
+/// Example:
+/// ```yaml
+/// scheduler:
+///   enable: true
+///   tasks:
+///     cleanup:
+///       cron: "0 */5 * * * *"
+///       timeout: 60000
+///     report:
+///       cron: "0 0 8 * * Mon-Fri"
+///       allow_overlap: true
+/// ```
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct SchedulerConfig {
+    /// Enable the scheduler server
+    #[serde(default)]
+    pub enable: bool,
+
+    #[serde(default)]
+    pub tasks: BTreeMap<String, TaskConfig>,
+}
+
+impl SchedulerConfig {
+    pub fn new(env: &Environment, app_name: &str) -> Result<Self> {
+        Self::from_key("scheduler", env, app_name)
+    }
+}
+
+/// Schedule of a task.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TaskConfig {
+    /// Cron expression, in UTC. Accepts the 5 fields form of crontab
+    /// (`min hour day month weekday`, with the weekdays from `0` or `7` for
+    /// Sunday to `6` for Saturday), and the forms with seconds, and
+    /// optionally years, of the `cron` crate, numbering the weekdays from
+    /// `1` for Sunday to `7` for Saturday. The names, like `Mon-Fri`, are
+    /// the same in both.
+    ///
+    /// Unlike crontab, a day of the month and a day of the week given
+    /// together must both match: `0 8 1 * Mon` runs on the Mondays that are
+    /// the 1st of the month, not on the 1st and on every Monday.
+    pub cron: String,
+
+    /// Maximum time in milliseconds a run may take before being cancelled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+
+    /// Start a run even when the previous one is still running. Such ticks
+    /// are skipped by default.
+    #[serde(default)]
+    pub allow_overlap: bool,
+
+    #[serde(default = "default_enable")]
+    pub enable: bool,
+}
+
+impl TaskConfig {
+    /// Parse the cron expression.
+    ///
+    /// # Errors
+    /// When the expression is invalid.
+    pub fn schedule(&self) -> Result<Schedule> {
+        let invalid = |err: &dyn std::fmt::Display| {
+            Error::Message(format!("invalid cron expression `{}`: {err}", self.cron))
+        };
+
+        let fields = self.cron.split_whitespace().collect::<Vec<_>>();
+        let expression = match fields.as_slice() {
+            [minute, hour, day, month, weekday] => format!(
+                "0 {minute} {hour} {day} {month} {}",
+                crontab_weekdays(weekday).map_err(|err| invalid(&err))?
+            ),
+            _ => fields.join(" "),
+        };
+        Schedule::from_str(&expression).map_err(|err| invalid(&err))
+    }
+
+    #[must_use]
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout.map(Duration::from_millis)
+    }
+}
+
+/// The names of the weekdays, by crontab number.
+const WEEKDAYS: [&str; 8] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Translate the crontab weekdays, from `0` or `7` for Sunday, to names: the
+/// `cron` crate numbers them from `1` for Sunday.
+fn crontab_weekdays(field: &str) -> std::result::Result<String, String> {
+    let weekday = |token: &str| match token.parse::<usize>() {
+        Ok(day) => WEEKDAYS
+            .get(day)
+            .map(ToString::to_string)
+            .ok_or_else(|| format!("invalid weekday `{day}`, expected 0 to 7")),
+        // a name
+        Err(_) => Ok(token.to_string()),
+    };
+
+    let items = field.split(',').map(|item| {
+        let (days, step) = match item.split_once('/') {
+            Some((days, step)) => (days, Some(step)),
+            None => (item, None),
+        };
+        let days = match days.split_once('-') {
+            None if days == "*" => days.to_string(),
+            None => weekday(days)?,
+            Some((start, end)) => match (start.parse::<usize>(), end.parse::<usize>()) {
+                (Ok(start), Ok(end)) if start > end => {
+                    return Err(format!("invalid weekdays `{days}`"));
+                }
+                // Sunday is first for the `cron` crate, split the ranges to it
+                (Ok(start), Ok(7)) if start > 0 => {
+                    if step.is_some() {
+                        return Err(format!("invalid weekdays `{item}`, use 0 for Sunday"));
+                    }
+                    match start {
+                        7 => "Sun".to_string(),
+                        _ => format!("{}-Sat,Sun", WEEKDAYS[start]),
+                    }
+                }
+                (Ok(0), Ok(7)) => "Sun-Sat".to_string(),
+                _ => format!("{}-{}", weekday(start)?, weekday(end)?),
+            },
+        };
+        Ok(step.map_or_else(|| days.clone(), |step| format!("{days}/{step}")))
+    });
+    Ok(items
+        .collect::<std::result::Result<Vec<_>, String>>()?
+        .join(","))
+}
+
+impl Config for SchedulerConfig {
+    fn enable(&self) -> bool {
+        self.enable
+    }
+}
+
+impl ConfigLoader for SchedulerConfig {
+    type Config = SchedulerConfig;
+    type Error = Error;
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeZone, Utc};
+
+    use super::*;
+
+    fn task(cron: &str) -> TaskConfig {
+        TaskConfig {
+            cron: cron.to_string(),
+            timeout: None,
+            allow_overlap: false,
+            enable: true,
+        }
+    }
+
+    /// The next fire times of `cron` after Saturday 2024-01-06 12:00.
+    fn next(cron: &str, count: usize) -> Vec<DateTime<Utc>> {
+        let after = Utc.with_ymd_and_hms(2024, 1, 6, 12, 0, 0).unwrap();
+        task(cron)
+            .schedule()
+            .unwrap()
+            .after(&after)
+            .take(count)
+            .collect()
+    }
+
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, day, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn crontab_monday() {
+        assert_eq!(next("0 8 * * 1", 2), vec![at(8, 8, 0), at(15, 8, 0)]);
+    }
+
+    #[test]
+    fn crontab_sunday() {
+        assert_eq!(next("0 8 * * 0", 1), vec![at(7, 8, 0)]);
+        assert_eq!(next("0 8 * * 7", 1), vec![at(7, 8, 0)]);
+        assert_eq!(next("* * * * 0", 1), vec![at(7, 0, 0)]);
+    }
+
+    #[test]
+    fn crontab_weekday_ranges() {
+        assert_eq!(
+            next("30 9 * * 1-5", 5),
+            vec![
+                at(8, 9, 30),
+                at(9, 9, 30),
+                at(10, 9, 30),
+                at(11, 9, 30),
+                at(12, 9, 30)
+            ]
+        );
+        // Saturday, Sunday then Friday
+        assert_eq!(
+            next("0 8 * * 5-7", 3),
+            vec![at(7, 8, 0), at(12, 8, 0), at(13, 8, 0)]
+        );
+        assert_eq!(next("0 8 * * 0,3", 2), vec![at(7, 8, 0), at(10, 8, 0)]);
+        assert_eq!(next("0 8 * * Mon-Fri", 1), vec![at(8, 8, 0)]);
+    }
+
+    #[test]
+    fn crate_syntax_is_unchanged() {
+        // seconds first, and Sunday is 1
+        assert_eq!(next("0 0 8 * * 1", 1), vec![at(7, 8, 0)]);
+        assert_eq!(
+            next("0 */15 * * * *", 2),
+            vec![at(6, 12, 15), at(6, 12, 30)]
+        );
+    }
+
+    #[test]
+    fn days_of_the_month_and_week_both_match() {
+        // the next Monday the 1st
+        let first_monday = Utc.with_ymd_and_hms(2024, 4, 1, 8, 0, 0).unwrap();
+        assert_eq!(next("0 8 1 * 1", 1), vec![first_monday]);
+        assert_eq!(next("0 0 8 1 * Mon", 1), vec![first_monday]);
+    }
+
+    #[test]
+    fn invalid_weekdays() {
+        assert!(task("0 8 * * 8").schedule().is_err());
+        assert!(task("0 8 * * 5-2").schedule().is_err());
+        assert!(task("0 8 * * 5-7/2").schedule().is_err());
+        assert!(task("not a cron").schedule().is_err());
+    }
+}
@@ -0,0 +1,32 @@
+//! # Scheduler
+//!
+//! Runs registered [`task::Tasks`] with the application context on the cron
+//! schedules of the `scheduler` configuration.
+//!
+//! # Example:
+//!
+//! ```rust
+//! use insane_core::{context::Context, error::Result};
+//! use insane_scheduler::prelude::*;
+//! use std::sync::Arc;
+//!
+//! async fn cleanup(_ctx: Arc<Box<dyn Context>>) -> Result<()> {
+//!     tracing::info!("removing the expired sessions");
+//!     Ok(())
+//! }
+//!
+//! let tasks = Tasks::new().add("cleanup", cleanup);
+//! let server = SchedulerServer::new(tasks);
+//! ```
+
+pub mod command;
+pub mod config;
+pub mod server;
+pub mod task;
+
+pub mod prelude {
+    pub use crate::command::SchedulerCommand;
+    pub use crate::config::SchedulerConfig;
+    pub use crate::server::SchedulerServer;
+    pub use crate::task::Tasks;
+}
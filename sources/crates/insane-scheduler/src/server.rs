@@ -0,0 +1,359 @@
+use std::{
+    panic::AssertUnwindSafe,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use cron::Schedule;
+use futures_util::FutureExt;
+use insane_core::{
+    context::Context,
+    error::{Error, Result},
+    metrics,
+    server::{Readiness, Server},
+    shutdown::ShutdownToken,
+};
+use tokio::{sync::Mutex, task::JoinSet};
+use tracing::Instrument;
+
+use crate::{
+    config::{SchedulerConfig, TaskConfig},
+    task::{Task, Tasks},
+};
+
+/// Server running the registered [`Tasks`] on the schedules of the
+/// `scheduler` configuration.
+pub struct SchedulerServer {
+    pub tasks: Tasks,
+    pub config: Arc<Mutex<Option<SchedulerConfig>>>,
+}
+
+impl SchedulerServer {
+    /// Create a new instance of the server
+    #[must_use]
+    pub fn new(tasks: Tasks) -> Self {
+        Self {
+            tasks,
+            config: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Load the `scheduler` configuration once and cache it.
+    async fn config(&self, context: &Arc<Box<dyn Context>>) -> Result<SchedulerConfig> {
+        let mut guard = self.config.lock().await;
+        if let Some(config) = &*guard {
+            return Ok(config.clone());
+        }
+
+        let config =
+            SchedulerConfig::new(context.environment(), &context.config().application_name)?;
+        *guard = Some(config.clone());
+        Ok(config)
+    }
+}
+
+#[async_trait::async_trait]
+impl Server for SchedulerServer {
+    fn name(&self) -> String {
+        "scheduler_server".to_string()
+    }
+
+    async fn enable(&self, context: Arc<Box<dyn Context>>) -> Result<bool> {
+        Ok(self.config(&context).await?.enable)
+    }
+
+    /// Run every enabled task on its schedule until `shutdown` is triggered,
+    /// then wait for the running tasks.
+    async fn serve(
+        &self,
+        context: Arc<Box<dyn Context>>,
+        shutdown: ShutdownToken,
+        ready: Readiness,
+    ) -> Result<()> {
+        let config = self.config(&context).await?;
+
+        let mut scheduled = Vec::new();
+        for (name, task_config) in config.tasks.iter().filter(|(_, task)| task.enable) {
+            let task = self.tasks.get(name).ok_or_else(|| {
+                Error::Message(format!("task `{name}` is scheduled but not registered"))
+            })?;
+            scheduled.push((
+                name.clone(),
+                task.clone(),
+                task_config.schedule()?,
+                task_config.clone(),
+            ));
+        }
+        for name in self.tasks.names() {
+            if !config.tasks.contains_key(name) {
+                tracing::warn!(task = name, "task is registered but has no schedule");
+            }
+        }
+
+        tracing::info!(
+            tasks = ?scheduled.iter().map(|(name, ..)| name.as_str()).collect::<Vec<_>>().join(","),
+            "scheduler started"
+        );
+        ready.ready();
+
+        let mut schedules = JoinSet::new();
+        for (name, task, schedule, task_config) in scheduled {
+            schedules.spawn(schedule_task(
+                name,
+                task,
+                schedule,
+                task_config,
+                context.clone(),
+                shutdown.clone(),
+            ));
+        }
+        while schedules.join_next().await.is_some() {}
+        Ok(())
+    }
+}
+
+/// Start a run of the task at each tick of its schedule, skipping the ticks
+/// while a previous run is still going unless `allow_overlap` is set.
+async fn schedule_task(
+    name: String,
+    task: Task,
+    schedule: Schedule,
+    config: TaskConfig,
+    context: Arc<Box<dyn Context>>,
+    shutdown: ShutdownToken,
+) {
+    let mut runs = JoinSet::new();
+    let mut last_tick = Utc::now();
+
+    loop {
+        let Some(tick) = schedule.after(&last_tick).next() else {
+            tracing::info!(task = name, "task has no upcoming run");
+            break;
+        };
+        last_tick = tick;
+
+        let delay = (tick - Utc::now()).to_std().unwrap_or_default();
+        tokio::select! {
+            () = tokio::time::sleep(delay) => {},
+            () = shutdown.triggered() => break,
+        }
+
+        while runs.try_join_next().is_some() {}
+        if !config.allow_overlap && !runs.is_empty() {
+            tracing::warn!(task = name, %tick, "previous run still in progress, skipping");
+            record(&name, "skipped", None);
+            continue;
+        }
+
+        let (name, task, context, timeout) = (
+            name.clone(),
+            task.clone(),
+            context.clone(),
+            config.timeout(),
+        );
+        runs.spawn(async move { run(&name, &task, context, timeout, "schedule").await });
+    }
+
+    while runs.join_next().await.is_some() {}
+}
+
+/// Run a task once, cancelling it after `timeout`. Each run is traced in its
+/// own `scheduled_task` span.
+///
+/// # Errors
+/// When the task fails, panics or times out.
+pub async fn run(
+    name: &str,
+    task: &Task,
+    context: Arc<Box<dyn Context>>,
+    timeout: Option<Duration>,
+    trigger: &'static str,
+) -> Result<()> {
+    let span = tracing::info_span!("scheduled_task", task = name, trigger);
+
+    async move {
+        tracing::info!("task started");
+        let started = Instant::now();
+
+        let running = AssertUnwindSafe(task.run(context)).catch_unwind();
+        let result = match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, running)
+                .await
+                .unwrap_or_else(|_| {
+                    Ok(Err(Error::Message(format!(
+                        "task timed out after {timeout:?}"
+                    ))))
+                }),
+            None => running.await,
+        };
+        let result = result.unwrap_or_else(|panic| {
+            let msg = panic.downcast_ref::<String>().map_or_else(
+                || {
+                    panic
+                        .downcast_ref::<&str>()
+                        .map_or("no error details", |s| s)
+                },
+                |s| s.as_str(),
+            );
+            Err(Error::Message(format!("task panicked: {msg}")))
+        });
+
+        let elapsed = started.elapsed();
+        match &result {
+            Ok(()) => {
+                tracing::info!(?elapsed, "task completed");
+                record(name, "completed", Some(elapsed));
+            }
+            Err(err) => {
+                tracing::error!(?elapsed, err.msg = %err, "task failed");
+                record(name, "failed", Some(elapsed));
+            }
+        }
+        result
+    }
+    .instrument(span)
+    .await
+}
+
+/// Count the run in `scheduled_tasks_total` by `outcome`, and time it in
+/// `scheduled_task_duration_seconds`, see [`insane_core::metrics`].
+fn record(task: &str, outcome: &str, elapsed: Option<Duration>) {
+    let recorded = metrics::counter(
+        "scheduled_tasks_total",
+        "Scheduled task runs",
+        &["task", "outcome"],
+    )
+    .and_then(|runs| {
+        runs.with_label_values(&[task, outcome]).inc();
+        if let Some(elapsed) = elapsed {
+            metrics::histogram(
+                "scheduled_task_duration_seconds",
+                "Duration of the scheduled task runs",
+                &["task"],
+            )?
+            .with_label_values(&[task])
+            .observe(elapsed.as_secs_f64());
+        }
+        Ok(())
+    });
+    if let Err(err) = recorded {
+        tracing::error!(err.msg = %err, "failed to record the task metrics");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use insane_core::{config::InsaneConfig, context::test_context};
+    use tokio::sync::Semaphore;
+
+    use super::*;
+
+    fn context() -> Arc<Box<dyn Context>> {
+        test_context(InsaneConfig::default())
+    }
+
+    fn every_second(allow_overlap: bool) -> TaskConfig {
+        TaskConfig {
+            cron: "* * * * * *".to_string(),
+            timeout: None,
+            allow_overlap,
+            enable: true,
+        }
+    }
+
+    /// The number of runs started in 2.5 seconds by a task running until
+    /// the end of the test.
+    async fn runs_started(allow_overlap: bool) -> usize {
+        let started = Arc::new(AtomicUsize::new(0));
+        let release = Arc::new(Semaphore::new(0));
+        let task = Tasks::new().add("busy", {
+            let (started, release) = (started.clone(), release.clone());
+            move |_context| {
+                let (started, release) = (started.clone(), release.clone());
+                async move {
+                    started.fetch_add(1, Ordering::SeqCst);
+                    let _ = release.acquire().await;
+                    Ok(())
+                }
+            }
+        });
+        let task = task.get("busy").unwrap().clone();
+
+        let shutdown = ShutdownToken::new();
+        let schedule = every_second(allow_overlap).schedule().unwrap();
+        let scheduled = tokio::spawn(schedule_task(
+            "busy".to_string(),
+            task,
+            schedule,
+            every_second(allow_overlap),
+            context(),
+            shutdown.clone(),
+        ));
+        tokio::time::sleep(Duration::from_millis(2_500)).await;
+        shutdown.trigger();
+        // the running runs are awaited on shutdown
+        release.close();
+        scheduled.await.unwrap();
+        started.load(Ordering::SeqCst)
+    }
+
+    #[tokio::test]
+    async fn skips_the_ticks_while_the_previous_run_is_going() {
+        let (exclusive, overlapping) = tokio::join!(runs_started(false), runs_started(true));
+        assert_eq!(exclusive, 1);
+        assert!(overlapping >= 2, "{overlapping} runs started");
+    }
+
+    #[tokio::test]
+    async fn cancels_the_runs_after_their_timeout() {
+        let tasks = Tasks::new()
+            .add("slow", |_context| async {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                Ok(())
+            })
+            .add("quick", |_context| async { Ok(()) });
+
+        let started = Instant::now();
+        let timeout = Some(Duration::from_millis(50));
+        let err = run(
+            "slow",
+            tasks.get("slow").unwrap(),
+            context(),
+            timeout,
+            "manual",
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.to_string(), "task timed out after 50ms");
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        assert!(run(
+            "quick",
+            tasks.get("quick").unwrap(),
+            context(),
+            timeout,
+            "manual"
+        )
+        .await
+        .is_ok());
+    }
+
+    #[tokio::test]
+    async fn fails_the_runs_that_panic() {
+        let tasks = Tasks::new().add("broken", |_context| async { panic!("boom") });
+
+        let err = run(
+            "broken",
+            tasks.get("broken").unwrap(),
+            context(),
+            None,
+            "manual",
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.to_string(), "task panicked: boom");
+    }
+}
@@ -0,0 +1,56 @@
+use std::{collections::BTreeMap, future::Future, sync::Arc};
+
+use futures_util::future::BoxFuture;
+use insane_core::{context::Context, error::Result};
+
+type TaskFn = dyn Fn(Arc<Box<dyn Context>>) -> BoxFuture<'static, Result<()>> + Send + Sync;
+
+/// A registered task, run with the application context.
+#[derive(Clone)]
+pub struct Task(Arc<TaskFn>);
+
+impl Task {
+    /// Start a run of the task.
+    pub fn run(&self, context: Arc<Box<dyn Context>>) -> BoxFuture<'static, Result<()>> {
+        (self.0)(context)
+    }
+}
+
+/// The tasks the scheduler can run, keyed by the name used in the
+/// `scheduler.tasks` configuration.
+#[derive(Default, Clone)]
+pub struct Tasks {
+    tasks: BTreeMap<String, Task>,
+}
+
+impl Tasks {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a task, replacing any task registered with the same name.
+    #[must_use]
+    pub fn add<F, Fut>(mut self, name: &str, task: F) -> Self
+    where
+        F: Fn(Arc<Box<dyn Context>>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.tasks.insert(
+            name.to_string(),
+            Task(Arc::new(move |context| Box::pin(task(context)))),
+        );
+        self
+    }
+
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Task> {
+        self.tasks.get(name)
+    }
+
+    /// Names of the registered tasks, sorted.
+    #[must_use]
+    pub fn names(&self) -> Vec<&str> {
+        self.tasks.keys().map(String::as_str).collect()
+    }
+}